@@ -0,0 +1,4 @@
+// The migrations are embedded with sqlx::migrate!, so new migration files need a rebuild
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS passkeys (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    user_id INT NOT NULL,
+    credential_id VARCHAR(512) NOT NULL UNIQUE,
+    public_key VARCHAR(255) NOT NULL,
+    sign_count INT UNSIGNED NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    last_used_at DATETIME NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS security_events (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    user_id INT NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    session_id VARCHAR(64),
+    ip VARCHAR(45),
+    user_agent VARCHAR(512),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    INDEX security_events_user_id (user_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS security_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    session_id VARCHAR(64),
+    ip VARCHAR(45),
+    user_agent VARCHAR(512),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS security_events_user_id ON security_events (user_id);
@@ -1,76 +1,167 @@
-use axum::{error_handling::HandleErrorLayer, middleware, BoxError, Router};
 use backend::{
-    middleware::{jwt::jwt_middleware, language::language_middleware},
-    models::general::AppState,
-    routes::{auth::auth_routes, otc::otc_routes, user::user_routes},
-    utils::env::get_environment_variable,
+    models::{config::models::Config, general::AppState, migration::models::ApiCommand},
+    routes::app::app_routes,
+    traits::mailer::SmtpMailer,
+    utils::{
+        auth::init_password_hasher,
+        config::load_config,
+        database::DatabasePool,
+        jwt_keys::init_jwt_keys,
+        migrations::{
+            check_schema_version, format_migration_status, get_migration_status, parse_api_command,
+            run_migrations,
+        },
+        password_policy::init_password_policy,
+        redis_pool::{parse_redis_target, RedisPool},
+        social::init_social_providers,
+    },
 };
 use dotenv::dotenv;
-use http::{header, HeaderValue, Method, StatusCode};
-use redis::Client;
-use sqlx::mysql::MySqlPoolOptions;
-use std::{sync::Arc, time::Duration};
-use tokio::{net::TcpListener, sync::Mutex};
-use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
+use http::{header, HeaderValue, Method};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::net::TcpListener;
 use tower_http::cors::CorsLayer;
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
-    let database_url = match get_environment_variable("DATABASE_URL") {
-        Ok(database_url) => database_url,
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let command = match parse_api_command(&args) {
+        Ok(command) => command,
         Err(err) => {
-            eprintln!("Error getting DATABASE_URL: {}", err);
-            std::process::exit(1);
+            eprintln!("{}", err);
+            std::process::exit(2);
         }
     };
 
-    let pool = MySqlPoolOptions::new()
-        .connect(&database_url)
-        .await
-        .expect("Unable to connect to database");
-
-    let redis_url = match get_environment_variable("REDIS_URL") {
-        Ok(redis_url) => redis_url,
-        Err(err) => {
-            eprintln!("Error getting REDIS_URL: {}", err);
+    // Leaked once so the state and background tasks can hold on to it for the lifetime of the server
+    let config: &'static Config = match load_config() {
+        Ok(config) => Box::leak(Box::new(config)),
+        Err(errors) => {
+            eprintln!("Invalid configuration:\n  - {}", errors.join("\n  - "));
             std::process::exit(1);
         }
     };
 
-    let redis_client = match Client::open(redis_url) {
-        Ok(client) => client,
+    let pool = match DatabasePool::connect(&config.database_url).await {
+        Ok(pool) => pool,
         Err(err) => {
-            eprintln!("Error connecting to Redis: {}", err);
+            eprintln!("Error connecting to database: {}", err);
             std::process::exit(1);
         }
     };
 
-    let redis_connection = match redis_client.get_tokio_connection().await {
-        Ok(connection) => connection,
+    match command {
+        ApiCommand::MigrateUp => {
+            match run_migrations(&pool).await {
+                Ok(applied_migrations) => {
+                    for migration in &applied_migrations {
+                        println!("Applied {} {}", migration.version, migration.description);
+                    }
+                    println!("{} migrations applied", applied_migrations.len());
+                }
+                Err(err) => {
+                    eprintln!("Error applying migrations: {}", err);
+                    std::process::exit(1);
+                }
+            }
+
+            return;
+        }
+        ApiCommand::MigrateStatus => {
+            match get_migration_status(&pool).await {
+                Ok(status) => println!("{}", format_migration_status(&status)),
+                Err(err) => {
+                    eprintln!("Error reading migration status: {}", err);
+                    std::process::exit(1);
+                }
+            }
+
+            return;
+        }
+        ApiCommand::Serve => {}
+    }
+
+    if config.run_migrations {
+        if let Err(err) = run_migrations(&pool).await {
+            eprintln!("Error applying migrations: {}", err);
+            std::process::exit(1);
+        }
+    }
+
+    let migration_status = match get_migration_status(&pool).await {
+        Ok(migration_status) => migration_status,
         Err(err) => {
-            eprintln!("Error setting up Redis connection: {}", err);
+            eprintln!("Error reading migration status: {}", err);
             std::process::exit(1);
         }
     };
 
-    let redis_connection = Arc::new(Mutex::new(redis_connection));
+    if let Err(err) = check_schema_version(&migration_status) {
+        eprintln!("Refusing to start: {}", err);
+        std::process::exit(1);
+    }
+
+    let pending_migrations = migration_status
+        .migrations
+        .iter()
+        .filter(|migration| !migration.is_applied)
+        .count();
 
-    let state = AppState {
-        db_pool: pool,
-        redis: redis_connection,
+    if pending_migrations > 0 {
+        eprintln!(
+            "{} migrations are pending, run `api migrate up` or set RUN_MIGRATIONS",
+            pending_migrations
+        );
+    }
+
+    if let Err(err) = init_jwt_keys(&config.jwt_keys) {
+        eprintln!("Error loading JWT keys: {}", err);
+        std::process::exit(1);
+    }
+
+    if let Err(err) = init_password_hasher(&config.password_hashing) {
+        eprintln!("Error loading password hasher: {}", err);
+        std::process::exit(1);
+    }
+
+    if let Err(err) = init_password_policy(&config.password_policy) {
+        eprintln!("Error loading password policy: {}", err);
+        std::process::exit(1);
+    }
+
+    if let Err(err) = init_social_providers(&config.social_providers).await {
+        eprintln!("Error loading social login providers: {}", err);
+        std::process::exit(1);
+    }
+
+    let redis_target = match parse_redis_target(&config.redis_url) {
+        Ok(redis_target) => redis_target,
+        Err(err) => {
+            eprintln!("Error parsing REDIS_URL: {}", err);
+            std::process::exit(1);
+        }
     };
 
-    let allow_origin = match get_environment_variable("CLIENT_BASE_URL") {
-        Ok(allow_origin_url) => allow_origin_url,
+    let redis_pool = match RedisPool::connect(redis_target).await {
+        Ok(redis_pool) => Arc::new(redis_pool),
         Err(err) => {
-            eprintln!("Error getting CLIENT_BASE_URL: {}", err);
+            eprintln!("Error connecting to Redis: {}", err);
             std::process::exit(1);
         }
     };
 
-    let allow_origin = match allow_origin.parse::<HeaderValue>() {
+    redis_pool.spawn_health_checks();
+
+    let mailer = Arc::new(SmtpMailer {
+        email_config: &config.email,
+    });
+
+    let state = AppState::with_database(pool, redis_pool, mailer, config);
+
+    let allow_origin = match config.client_base_url.parse::<HeaderValue>() {
         Ok(header) => header,
         Err(err) => {
             eprintln!(
@@ -97,30 +188,9 @@ async fn main() {
         ])
         .allow_credentials(true);
 
-    let app = Router::new()
-        .nest("/api/user", user_routes())
-        .nest("/api/auth", auth_routes())
-        .nest("/api/otc", otc_routes())
-        .layer(middleware::from_fn_with_state(
-            state.clone(),
-            jwt_middleware,
-        ))
-        .layer(middleware::from_fn(language_middleware))
-        .with_state(state)
-        .layer(
-            ServiceBuilder::new()
-                .layer(HandleErrorLayer::new(|err: BoxError| async move {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        eprintln!("Error creating ServiceBuilder: {}", err),
-                    )
-                }))
-                .layer(BufferLayer::new(1024))
-                .layer(RateLimitLayer::new(5, Duration::from_secs(1))),
-        )
-        .layer(cors);
-
-    let listener = match TcpListener::bind("0.0.0.0:8080").await {
+    let app = app_routes(state).layer(cors);
+
+    let listener = match TcpListener::bind(&config.bind_address).await {
         Ok(listner) => listner,
         Err(err) => {
             eprintln!("Error creating TCP listener: {}", err);
@@ -128,7 +198,13 @@ async fn main() {
         }
     };
 
-    match axum::serve(listener, app).await {
+    // Connect info gives the session registry and rate limiter the client IP of each request
+    match axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    {
         Ok(server) => server,
         Err(err) => {
             eprintln!("Error serving application: {}", err);
@@ -1,2 +1,14 @@
-pub const BEARER_EXPIRATION_SECONDS: i32 = 10 * 60; // 10 minutes
-pub const REFRESH_EXPIRATION_SECONDS: i32 = 8 * 60 * 60; // 8 hours
+// Token lifetimes when the config doesn't set them
+pub const DEFAULT_BEARER_EXPIRATION_SECONDS: i32 = 10 * 60; // 10 minutes
+pub const DEFAULT_REFRESH_EXPIRATION_SECONDS: i32 = 8 * 60 * 60; // 8 hours
+pub const DEFAULT_JWT_EXPIRATION_SECONDS: i64 = 24 * 60 * 60; // 24 hours
+pub const REFRESH_TOKEN_BYTES: usize = 32; // 256 bits
+
+// A rotated refresh token presented again this soon is a concurrent refresh (two tabs), not a replay
+pub const REFRESH_TOKEN_REUSE_GRACE_SECONDS: i64 = 30;
+
+pub const JWT_ID_LENGTH: usize = 32;
+// OWASP recommended minimum for Argon2id, overridable with ARGON2_MEMORY_COST and ARGON2_TIME_COST
+pub const ARGON2_DEFAULT_MEMORY_COST_KIB: u32 = 19 * 1024;
+pub const ARGON2_DEFAULT_TIME_COST: u32 = 2;
+pub const ARGON2_PARALLELISM: u32 = 1;
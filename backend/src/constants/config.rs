@@ -0,0 +1,4 @@
+pub const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0:8080";
+pub const DEFAULT_COMPANY_NAME: &str = "Authentication Inc.";
+// Mailpit catches all mail in development, it runs without TLS or credentials
+pub const MAILPIT_SMTP_HOST: &str = "mailpit";
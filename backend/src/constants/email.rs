@@ -0,0 +1,3 @@
+// Shown inline in the emails, read when an email is sent over SMTP
+pub const CODE_IMAGE_PATH: &str = "/app/src/static/images/code_image.png";
+pub const SUCCESS_IMAGE_PATH: &str = "/app/src/static/images/success_image.png";
@@ -1,4 +1,15 @@
 pub mod auth;
+pub mod config;
+pub mod email;
+pub mod oauth;
 pub mod otc;
+pub mod passkey;
+pub mod password_policy;
+pub mod rate_limit;
+pub mod redis;
 pub mod routes;
+pub mod security_event;
+pub mod social;
+pub mod throttle;
+pub mod two_factor;
 pub mod user;
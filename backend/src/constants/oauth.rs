@@ -0,0 +1,34 @@
+pub const OAUTH_SUPPORTED_SCOPES: &[&str] = &["openid", "profile", "email", "phone"];
+
+// Scope that turns an OAuth request into an OpenID Connect one
+pub const OIDC_SCOPE: &str = "openid";
+pub const OIDC_SUPPORTED_CLAIMS: &[&str] = &[
+    "sub",
+    "iss",
+    "aud",
+    "exp",
+    "iat",
+    "nonce",
+    "name",
+    "email",
+    "email_verified",
+    "phone_number",
+];
+
+pub const OAUTH_CLIENT_ID_LENGTH: usize = 24;
+pub const OAUTH_CLIENT_SECRET_BYTES: usize = 32;
+pub const OAUTH_AUTHORIZATION_CODE_BYTES: usize = 32;
+pub const OAUTH_REFRESH_TOKEN_BYTES: usize = 32;
+
+pub const OAUTH_MAX_REDIRECT_URIS: usize = 10;
+pub const OAUTH_MAX_CLIENT_NAME_LENGTH: usize = 255;
+
+// RFC 7636 allows verifiers of 43 to 128 characters
+pub const OAUTH_CODE_VERIFIER_MIN_LENGTH: usize = 43;
+pub const OAUTH_CODE_VERIFIER_MAX_LENGTH: usize = 128;
+
+pub const OAUTH_AUTHORIZATION_CODE_EXPIRATION_SECONDS: i32 = 60; // 1 minute
+pub const OAUTH_ACCESS_TOKEN_EXPIRATION_SECONDS: i64 = 10 * 60; // 10 minutes
+pub const OAUTH_REFRESH_TOKEN_EXPIRATION_SECONDS: i32 = 30 * 24 * 60 * 60; // 30 days
+pub const OAUTH_CONSENT_EXPIRATION_SECONDS: i32 = 90 * 24 * 60 * 60; // 90 days
+pub const OIDC_ID_TOKEN_EXPIRATION_SECONDS: i64 = 10 * 60; // 10 minutes
@@ -1 +1,5 @@
 pub const OTC_EXPIRATION_SECONDS: i32 = 10 * 60; // 10 minutes
+pub const OTC_LENGTH: usize = 6;
+pub const OTC_CHARACTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+// Codes that can be entered for an email before its pending codes are burned
+pub const OTC_MAX_ATTEMPTS: i64 = 5;
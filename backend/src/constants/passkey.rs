@@ -0,0 +1,5 @@
+pub const WEBAUTHN_CHALLENGE_BYTES: usize = 32;
+pub const WEBAUTHN_CHALLENGE_EXPIRATION_SECONDS: i32 = 5 * 60; // 5 minutes
+pub const WEBAUTHN_TIMEOUT_MILLISECONDS: u64 = 5 * 60 * 1000; // 5 minutes
+pub const WEBAUTHN_ES256_ALGORITHM: i64 = -7; // ECDSA with P-256 and SHA-256, see the COSE algorithms registry
+pub const WEBAUTHN_MAX_CREDENTIAL_ID_LENGTH: usize = 512;
@@ -0,0 +1,47 @@
+pub const PASSWORD_DEFAULT_MIN_LENGTH: usize = 8;
+pub const PASSWORD_DEFAULT_MAX_LENGTH: usize = 128;
+pub const PASSWORD_DEFAULT_MIN_SCORE: u8 = 3;
+
+// Parts of a name or email shorter than this are too common to reject in a password
+pub const PASSWORD_PERSONAL_INFO_MIN_LENGTH: usize = 3;
+
+// Common password fragments, a match only counts as a single guess per character
+pub const COMMON_PASSWORD_WORDS: &[&str] = &[
+    "password",
+    "passw0rd",
+    "qwerty",
+    "azerty",
+    "asdf",
+    "zxcv",
+    "letmein",
+    "welcome",
+    "admin",
+    "login",
+    "secret",
+    "monkey",
+    "dragon",
+    "master",
+    "shadow",
+    "sunshine",
+    "princess",
+    "football",
+    "baseball",
+    "iloveyou",
+    "trustno1",
+    "superman",
+    "batman",
+    "hello",
+    "freedom",
+    "whatever",
+    "starwars",
+    "summer",
+    "winter",
+    "spring",
+    "autumn",
+    "changeme",
+    "default",
+    "wachtwoord",
+];
+
+// Number of previous passwords, besides the current one, a new password may not be equal to
+pub const PASSWORD_HISTORY_SIZE: i64 = 5;
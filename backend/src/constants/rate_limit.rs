@@ -0,0 +1,13 @@
+// Requests per sliding window, per client IP or per user for authenticated routes. These are the
+// defaults, the [rate_limits] section of the config file overrides them
+pub const DEFAULT_RATE_LIMIT: i64 = 120;
+pub const DEFAULT_RATE_LIMIT_WINDOW_SECONDS: i64 = 60; // 1 minute
+
+pub const AUTH_RATE_LIMIT: i64 = 20;
+pub const AUTH_RATE_LIMIT_WINDOW_SECONDS: i64 = 60; // 1 minute
+
+pub const OTC_VERIFY_RATE_LIMIT: i64 = 10;
+pub const OTC_VERIFY_RATE_LIMIT_WINDOW_SECONDS: i64 = 5 * 60; // 5 minutes
+
+pub const PASSWORD_RESET_REQUEST_RATE_LIMIT: i64 = 5;
+pub const PASSWORD_RESET_REQUEST_RATE_LIMIT_WINDOW_SECONDS: i64 = 60 * 60; // 1 hour
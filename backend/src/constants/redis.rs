@@ -0,0 +1,13 @@
+// Reconnect backoff of the connection manager: 200ms, 400ms, 800ms and so on, at most 5 seconds apart
+pub const REDIS_RECONNECT_EXPONENT_BASE: u64 = 2;
+pub const REDIS_RECONNECT_FACTOR_MILLISECONDS: u64 = 100;
+pub const REDIS_RECONNECT_MAX_DELAY_MILLISECONDS: u64 = 5 * 1000;
+pub const REDIS_RECONNECT_RETRIES: usize = 6;
+
+pub const REDIS_CONNECTION_TIMEOUT_SECONDS: u64 = 5;
+pub const REDIS_RESPONSE_TIMEOUT_SECONDS: u64 = 5;
+pub const REDIS_HEALTH_CHECK_INTERVAL_SECONDS: u64 = 15;
+
+pub const REDIS_SENTINEL_SCHEME: &str = "redis+sentinel";
+pub const REDIS_SENTINEL_TLS_SCHEME: &str = "rediss+sentinel";
+pub const REDIS_SENTINEL_DEFAULT_PORT: u16 = 26379;
@@ -5,4 +5,21 @@ pub const PROTECTED_ROUTES: &[(&str, &Method)] = &[
     ("/api/user", &Method::PATCH),
     ("/api/user", &Method::DELETE),
     ("/api/auth/logout", &Method::POST),
+    ("/api/auth/sessions", &Method::GET),
+    ("/api/auth/sessions", &Method::DELETE),
+    ("/api/auth/sessions/{id}", &Method::DELETE),
+    ("/api/auth/2fa", &Method::DELETE),
+    ("/api/auth/2fa/setup", &Method::POST),
+    ("/api/auth/2fa/confirm", &Method::POST),
+    ("/api/auth/2fa/recovery-codes", &Method::GET),
+    ("/api/auth/2fa/recovery-codes", &Method::POST),
+    ("/api/auth/2fa/recovery-codes/regenerate", &Method::POST),
+    ("/api/auth/passkeys/register/options", &Method::POST),
+    ("/api/auth/passkeys/register", &Method::POST),
+    ("/oauth/authorize", &Method::POST),
+    ("/oauth/clients", &Method::GET),
+    ("/oauth/clients", &Method::POST),
 ];
+
+// Routes that get the claims when the request has a valid session, but are also reachable without one
+pub const OPTIONAL_AUTH_ROUTES: &[(&str, &Method)] = &[("/oauth/authorize", &Method::GET)];
@@ -0,0 +1,4 @@
+// Event types stored in the security_events table
+pub const SECURITY_EVENT_REFRESH_TOKEN_REUSE: &str = "refresh_token_reuse";
+
+pub const SECURITY_EVENT_MAX_USER_AGENT_LENGTH: usize = 512;
@@ -0,0 +1,24 @@
+pub const SOCIAL_LOGIN_STATE_BYTES: usize = 32;
+pub const SOCIAL_LOGIN_CODE_VERIFIER_BYTES: usize = 32;
+pub const SOCIAL_LOGIN_STATE_EXPIRATION_SECONDS: i32 = 10 * 60; // 10 minutes
+
+// Holds the state in the browser that started the login, so a callback from another browser is refused
+pub const SOCIAL_LOGIN_STATE_COOKIE: &str = "SocialLoginState";
+
+// A provider identity is only linked to an existing account once its password is confirmed
+pub const SOCIAL_LINK_TOKEN_BYTES: usize = 32;
+pub const SOCIAL_LINK_EXPIRATION_SECONDS: i32 = 10 * 60; // 10 minutes
+
+// Accounts created through a provider get a random password, so only a password reset enables password login
+pub const SOCIAL_LOGIN_PASSWORD_LENGTH: usize = 64;
+
+pub const SOCIAL_LOGIN_HTTP_TIMEOUT_SECONDS: u64 = 10;
+
+pub const GOOGLE_AUTHORIZATION_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+pub const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+pub const GOOGLE_USERINFO_URL: &str = "https://openidconnect.googleapis.com/v1/userinfo";
+
+pub const GITHUB_AUTHORIZATION_URL: &str = "https://github.com/login/oauth/authorize";
+pub const GITHUB_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+pub const GITHUB_USER_URL: &str = "https://api.github.com/user";
+pub const GITHUB_EMAILS_URL: &str = "https://api.github.com/user/emails";
@@ -0,0 +1,11 @@
+// Failed logins are counted per account and per client IP, an IP gets more room since it may be shared by many users
+pub const LOGIN_FAILURE_WINDOW_SECONDS: i32 = 60 * 60; // 1 hour
+pub const LOGIN_BACKOFF_BASE_SECONDS: u64 = 1;
+
+pub const LOGIN_EMAIL_FREE_ATTEMPTS: i64 = 3;
+pub const LOGIN_EMAIL_LOCKOUT_FAILURES: i64 = 10;
+pub const LOGIN_EMAIL_LOCKOUT_SECONDS: u64 = 15 * 60; // 15 minutes
+
+pub const LOGIN_IP_FREE_ATTEMPTS: i64 = 20;
+pub const LOGIN_IP_LOCKOUT_FAILURES: i64 = 100;
+pub const LOGIN_IP_LOCKOUT_SECONDS: u64 = 60 * 60; // 1 hour
@@ -0,0 +1,10 @@
+pub const TOTP_SECRET_BYTES: usize = 20; // 160 bits, as recommended by RFC 4226
+pub const TOTP_PERIOD_SECONDS: u64 = 30;
+pub const TOTP_DIGITS: u32 = 6;
+pub const TOTP_ALLOWED_DRIFT_STEPS: u64 = 1; // Accept one step before and after the current one
+pub const TWO_FACTOR_CHALLENGE_EXPIRATION_SECONDS: i32 = 5 * 60; // 5 minutes
+pub const TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS: u32 = 5;
+pub const RECOVERY_CODES_REGENERATION_EXPIRATION_SECONDS: i32 = 10 * 60; // 10 minutes
+pub const RECOVERY_CODE_COUNT: usize = 10;
+pub const RECOVERY_CODE_LENGTH: usize = 10;
+pub const RECOVERY_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789"; // No 0/O or 1/I to avoid typos
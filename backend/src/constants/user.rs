@@ -1 +1,2 @@
 pub const PASSWORD_RESET_TOKEN_EXPIRATION_SECONDS: i32 = 10 * 60; // 10 minutes
+pub const PASSWORD_RESET_TOKEN_BYTES: usize = 32; // 256 bits
@@ -1,27 +1,90 @@
 use std::sync::Arc;
 
 use crate::{
-    constants::{
-        auth::{BEARER_EXPIRATION_SECONDS, REFRESH_EXPIRATION_SECONDS},
-        routes::PROTECTED_ROUTES,
+    models::{
+        auth::models::JwtClaims,
+        general::AppState,
+        session::models::{RotatedRefreshToken, SessionClient},
+        translations::Translations,
     },
-    models::{auth::models::JwtClaims, general::AppState, translations::Translations},
     utils::{
         cookie::{get_cookie, set_cookie},
-        jwt::{decode_jwt, encode_jwt, format_refresh_token_key, generate_refresh_token},
-        redis::{get_token, remove_token, set_token},
+        jwt::{decode_jwt, is_jwt_revoked},
         responses::AppError,
-        user::get_user_by_id,
+        routes::{is_optional_auth_route, is_protected_route},
+        session::{get_session_client, rotate_refresh_token},
     },
 };
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
+    http::{header, Request, StatusCode},
     middleware::Next,
     response::IntoResponse,
 };
 
+// Authenticates with the Bearer cookie, or rotates the refresh token when the JWT is gone
+async fn authenticate_request(
+    state: &AppState,
+    translations: &Translations,
+    jwt_cookie: Option<String>,
+    refresh_token_cookie: Option<String>,
+    client: &SessionClient,
+) -> Result<(JwtClaims, Option<RotatedRefreshToken>), AppError> {
+    match jwt_cookie {
+        Some(bearer) => {
+            let claims = decode_jwt(&bearer)
+                .map_err(|error| match error.status_code() {
+                    StatusCode::UNAUTHORIZED => AppError::format_error(
+                        translations,
+                        StatusCode::UNAUTHORIZED,
+                        "auth.errors.failed_to_read_token_payload",
+                    ),
+                    _ => AppError::format_service_error(translations, error),
+                })?
+                .claims;
+
+            if is_jwt_revoked(state, &claims)
+                .await
+                .map_err(|error| AppError::format_service_error(translations, error))?
+            {
+                return Err(AppError::format_error(
+                    translations,
+                    StatusCode::UNAUTHORIZED,
+                    "auth.errors.token_revoked",
+                ));
+            }
+
+            Ok((claims, None))
+        }
+        None => match refresh_token_cookie {
+            Some(refresh_token) => {
+                let rotated = rotate_refresh_token(state, &refresh_token, client)
+                    .await
+                    .map_err(|status| match status {
+                        StatusCode::UNAUTHORIZED => AppError::format_error(
+                            translations,
+                            StatusCode::UNAUTHORIZED,
+                            "auth.errors.failed_to_read_token_payload",
+                        ),
+                        _ => AppError::format_internal_error(translations),
+                    })?;
+
+                let claims = decode_jwt(&rotated.jwt)
+                    .map_err(|error| AppError::format_service_error(translations, error))?
+                    .claims;
+
+                Ok((claims, Some(rotated)))
+            }
+            None => Err(AppError::format_error(
+                translations,
+                StatusCode::UNAUTHORIZED,
+                "auth.errors.failed_to_read_token_payload",
+            )),
+        },
+    }
+}
+
 pub async fn jwt_middleware(
     State(state): State<AppState>,
     req: Request<Body>,
@@ -30,121 +93,78 @@ pub async fn jwt_middleware(
     let path = req.uri().path().to_string();
     let method = req.method().clone();
 
-    let jwt_cookie = get_cookie(&req, "Bearer");
-    let refresh_token_cookie = get_cookie(&req, "RefreshToken");
-
     let translations = req.extensions().get::<Arc<Translations>>().cloned();
 
     let mut request = req;
 
-    if !PROTECTED_ROUTES.contains(&(path.as_str(), &method)) {
+    let is_protected = is_protected_route(&path, &method);
+    let is_optional_auth = is_optional_auth_route(&path, &method);
+
+    if !is_protected && !is_optional_auth {
         return Ok(next.run(request).await);
     }
 
-    match translations {
-        Some(translations) => {
-            let claims: JwtClaims;
-
-            match jwt_cookie {
-                Some(bearer) => {
-                    claims = decode_jwt(&bearer)
-                        .map_err(|_| AppError::format_internal_error(&translations))?
-                        .claims;
-                }
-                None => match refresh_token_cookie {
-                    Some(refresh_token) => {
-                        let formatted_refresh_token_key = format_refresh_token_key(&refresh_token);
-                        let token_payload_user_id =
-                            match get_token(&state, &formatted_refresh_token_key).await {
-                                Ok(Some(user_id)) => user_id,
-                                Ok(None) => {
-                                    return Err(AppError::format_error(
-                                        &translations,
-                                        StatusCode::UNAUTHORIZED,
-                                        "auth.errors.failed_to_read_token_payload",
-                                    ))
-                                }
-                                Err(_) => {
-                                    return Err(AppError::format_internal_error(&translations))
-                                }
-                            };
-
-                        let token_payload_user_id: i32 = match token_payload_user_id.parse() {
-                            Ok(id) => id,
-                            Err(_) => {
-                                return Err(AppError::format_error(
-                                    &translations,
-                                    StatusCode::UNAUTHORIZED,
-                                    "auth.errors.failed_to_read_token_payload",
-                                ))
-                            }
-                        };
-
-                        remove_token(&state, &formatted_refresh_token_key)
-                            .await
-                            .map_err(|_| AppError::format_internal_error(&translations))?;
-
-                        let user_data = match get_user_by_id(&state, &token_payload_user_id).await {
-                            Ok(user) => user,
-                            Err(_) => return Err(AppError::format_internal_error(&translations)),
-                        };
-
-                        let new_jwt = encode_jwt(&user_data.0, &user_data.1, &user_data.2)
-                            .map_err(|_| AppError::format_internal_error(&translations))?;
-
-                        let new_refresh_token = generate_refresh_token();
-                        let new_refresh_token_key = format_refresh_token_key(&new_refresh_token);
-
-                        let new_jwt_claims = decode_jwt(&new_jwt)
-                            .map_err(|_| AppError::format_internal_error(&translations))?
-                            .claims;
-
-                        claims = new_jwt_claims;
-
-                        let _ = set_token(
-                            &state,
-                            &new_refresh_token_key,
-                            &token_payload_user_id.to_string(),
-                            REFRESH_EXPIRATION_SECONDS,
-                        )
-                        .await;
-
-                        request = set_cookie(
-                            &translations,
-                            request,
-                            "Bearer",
-                            &new_jwt,
-                            Some(BEARER_EXPIRATION_SECONDS),
-                        )?;
-
-                        // Don't need to delete the old RefreshToken from the cookies, because it is overwritten here
-                        request = set_cookie(
-                            &translations,
-                            request,
-                            "RefreshToken",
-                            &new_refresh_token,
-                            Some(REFRESH_EXPIRATION_SECONDS),
-                        )?;
-                    }
-                    None => {
-                        return Err(AppError::format_error(
-                            &translations,
-                            StatusCode::UNAUTHORIZED,
-                            "auth.errors.failed_to_read_token_payload",
-                        ))
-                    }
-                },
-            }
-
-            request.extensions_mut().insert(claims);
-        }
+    let translations = match translations {
+        Some(translations) => translations,
         None => {
             return Err(AppError::format_raw_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Failed to read translations",
             ))
         }
+    };
+
+    let jwt_cookie = get_cookie(&request, "Bearer");
+    let refresh_token_cookie = get_cookie(&request, "RefreshToken");
+    let client = get_session_client(state.config, request.headers(), request.extensions());
+
+    let (claims, rotated_refresh_token) = match authenticate_request(
+        &state,
+        &translations,
+        jwt_cookie,
+        refresh_token_cookie,
+        &client,
+    )
+    .await
+    {
+        Ok(authentication) => authentication,
+        // Optional auth routes handle anonymous requests themselves, they just don't get claims
+        Err(_) if !is_protected => return Ok(next.run(request).await),
+        Err(err) => return Err(err),
+    };
+
+    request.extensions_mut().insert(claims);
+
+    let mut response = next.run(request).await;
+
+    // The rotated tokens have to reach the client, otherwise its next request presents a spent refresh token.
+    // Handlers that end the session, like logout, set their own cookies which must not be overwritten.
+    let is_refresh_token_cookie_set = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .any(|cookie| cookie.as_bytes().starts_with(b"RefreshToken="));
+
+    if let Some(rotated) = rotated_refresh_token.filter(|_| !is_refresh_token_cookie_set) {
+        response = set_cookie(
+            &translations,
+            response,
+            "Bearer",
+            &rotated.jwt,
+            Some(state.config.tokens.bearer_expiration_seconds),
+        )?;
+
+        // Don't need to delete the old RefreshToken from the cookies, because it is overwritten here
+        if let Some(refresh_token) = &rotated.refresh_token {
+            response = set_cookie(
+                &translations,
+                response,
+                "RefreshToken",
+                refresh_token,
+                Some(state.config.tokens.refresh_expiration_seconds),
+            )?;
+        }
     }
 
-    Ok(next.run(request).await)
+    Ok(response)
 }
@@ -1,2 +1,3 @@
 pub mod jwt;
 pub mod language;
+pub mod rate_limit;
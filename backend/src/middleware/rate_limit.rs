@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    models::{general::AppState, translations::Translations},
+    utils::{
+        client_ip::get_client_ip,
+        cookie::get_cookie,
+        jwt::decode_jwt,
+        rate_limit::{check_rate_limit, get_rate_limit_policy, RateLimitStatus},
+        responses::AppError,
+    },
+};
+
+// Standard RateLimit headers, see the IETF RateLimit header fields draft
+fn set_rate_limit_headers(response: &mut Response, status: &RateLimitStatus) {
+    let headers = response.headers_mut();
+
+    headers.insert("RateLimit-Limit", HeaderValue::from(status.limit));
+    headers.insert("RateLimit-Remaining", HeaderValue::from(status.remaining));
+    headers.insert("RateLimit-Reset", HeaderValue::from(status.reset_seconds));
+
+    if let Ok(policy) =
+        HeaderValue::from_str(&format!("{};w={}", status.limit, status.window_seconds))
+    {
+        headers.insert("RateLimit-Policy", policy);
+    }
+}
+
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if req.method() == Method::OPTIONS {
+        return next.run(req).await;
+    }
+
+    let policy = get_rate_limit_policy(&state.config.rate_limits, req.uri().path());
+
+    // Runs before the JWT middleware so unauthenticated floods are counted as well, a valid Bearer is enough to key by user
+    let user_id = policy
+        .key_by_user
+        .then(|| get_cookie(&req, "Bearer"))
+        .flatten()
+        .and_then(|bearer| decode_jwt(&bearer).ok())
+        .map(|token| token.claims.id);
+
+    let client_key = match (
+        user_id,
+        get_client_ip(state.config, req.headers(), req.extensions()),
+    ) {
+        (Some(user_id), _) => format!("user:{}", user_id),
+        (None, Some(ip)) => format!("ip:{}", ip),
+        (None, None) => return next.run(req).await,
+    };
+
+    // Fails open, an unavailable Redis shouldn't take every route down with it
+    let status = match check_rate_limit(&state, &policy, &client_key).await {
+        Ok(status) => status,
+        Err(_) => return next.run(req).await,
+    };
+
+    let mut response = if status.is_limited {
+        let error = match req.extensions().get::<Arc<Translations>>() {
+            Some(translations) => AppError::format_error(
+                translations,
+                StatusCode::TOO_MANY_REQUESTS,
+                "general.errors.too_many_requests",
+            ),
+            None => AppError::format_raw_error(StatusCode::TOO_MANY_REQUESTS, "Too many requests"),
+        };
+
+        error
+            .with_retry_after(status.reset_seconds as u64)
+            .into_response()
+    } else {
+        next.run(req).await
+    };
+
+    set_rate_limit_headers(&mut response, &status);
+
+    response
+}
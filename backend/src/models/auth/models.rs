@@ -7,6 +7,11 @@ pub struct LoginUser {
     pub password: Password,
 }
 
+#[derive(Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: Email,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ResetPasswordTokenUser {
     pub email: Email,
@@ -17,12 +22,22 @@ pub struct ResetPasswordTokenUser {
 pub struct JwtClaims {
     pub exp: usize, // Expiry time of the token
     pub iat: usize, // Issued at time of the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat_ms: Option<i64>, // Issued at time in milliseconds, revocations compare with it, absent in older tokens
     pub id: Id,
     pub name: Name,
     pub email: Email,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>, // Id of the session in the session registry
+    #[serde(default)]
+    pub jti: String, // Unique id of the token, used to revoke it before it expires, empty in older tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>, // OAuth client the access token was issued to, absent for our own session tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>, // Space separated OAuth scopes granted to the client
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct AuthResponse {
     pub id: Id,
     pub name: Name,
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::models::password_policy::models::CharacterSet;
+
+// Loaded once at startup from the optional TOML file in CONFIG_PATH, overridden by environment variables.
+// Debug is implemented by hand to keep the secrets out of logs
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind_address: String,
+    pub client_base_url: String,
+    pub company_name: String,
+    pub database_url: String,
+    // Applies the embedded migrations before the server starts
+    pub run_migrations: bool,
+    pub redis_url: String,
+    pub hash_secret_key: String,
+    // Users that may register OAuth clients
+    pub oauth_admin_emails: Vec<String>,
+    // Public URL of the API, the iss of ID tokens and base of the discovery document
+    pub oidc_issuer_url: String,
+    // Reverse proxies whose X-Forwarded-For is trusted, IPs or CIDR ranges
+    pub trusted_proxies: Vec<String>,
+    // Directory with the Have I Been Pwned password ranges, the check is skipped without it
+    pub breached_passwords_dir: Option<String>,
+    pub email: EmailConfig,
+    pub tokens: TokenConfig,
+    pub jwt_keys: JwtKeysConfig,
+    pub password_hashing: PasswordHashingConfig,
+    pub password_policy: PasswordPolicyConfig,
+    // Social login providers by name, like google, github or any OpenID Connect provider
+    pub social_providers: BTreeMap<String, SocialProviderConfig>,
+    pub rate_limits: RateLimitsConfig,
+}
+
+#[derive(Deserialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    pub user: String,
+    // Not needed for the local mailpit SMTP server
+    pub password: Option<String>,
+    pub smtp_host: String,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokenConfig {
+    pub bearer_expiration_seconds: i32,
+    pub refresh_expiration_seconds: i32,
+    pub jwt_expiration_seconds: i64,
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtKeysConfig {
+    // PEM private key (RSA or Ed25519) that signs new JWTs
+    pub private_key_path: String,
+    // The kid of the signing key, its public key is <kid>.pem in public_keys_dir
+    pub signing_key_id: String,
+    pub public_keys_dir: String,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordHashingConfig {
+    // argon2id or bcrypt, for new hashes only
+    pub algorithm: String,
+    pub argon2_memory_cost_kib: u32,
+    pub argon2_time_cost: u32,
+    pub bcrypt_cost: u32,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordPolicyConfig {
+    // JSON file in the format of the public endpoint, replaces the rules below
+    pub path: Option<String>,
+    pub min_length: usize,
+    pub max_length: usize,
+    pub required_character_sets: Vec<CharacterSet>,
+    pub allowed_character_sets: Vec<CharacterSet>,
+    pub min_score: u8,
+    pub reject_personal_info: bool,
+}
+
+#[derive(Deserialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SocialProviderConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    // Only for providers other than Google and GitHub, their endpoints are discovered from it
+    pub issuer_url: Option<String>,
+    pub scopes: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitsConfig {
+    pub default: RateLimitConfig,
+    pub auth: RateLimitConfig,
+    pub otc_verify: RateLimitConfig,
+    pub password_reset_request: RateLimitConfig,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub limit: i64,
+    pub window_seconds: i64,
+}
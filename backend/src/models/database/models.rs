@@ -0,0 +1,7 @@
+// The database behind DATABASE_URL, picked from the scheme of the URL
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DatabaseKind {
+    MySql,
+    Postgres,
+    Sqlite,
+}
@@ -1,11 +1,84 @@
-use redis::aio::Connection;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
-use sqlx::MySqlPool;
+use crate::{
+    models::config::models::Config,
+    traits::{
+        linked_identity_repository::{
+            InMemoryLinkedIdentityRepository, LinkedIdentityRepository, SqlLinkedIdentityRepository,
+        },
+        mailer::{InMemoryMailer, Mailer},
+        oauth_client_repository::{
+            InMemoryOAuthClientRepository, OAuthClientRepository, SqlOAuthClientRepository,
+        },
+        passkey_repository::{InMemoryPasskeyRepository, PasskeyRepository, SqlPasskeyRepository},
+        security_event_repository::{
+            InMemorySecurityEventRepository, SecurityEventRepository, SqlSecurityEventRepository,
+        },
+        token_store::{InMemoryTokenStore, TokenStore},
+        two_factor_repository::{
+            InMemoryTwoFactorRepository, SqlTwoFactorRepository, TwoFactorRepository,
+        },
+        user_repository::{InMemoryUserRepository, SqlUserRepository, UserRepository},
+    },
+    utils::database::DatabasePool,
+};
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db_pool: MySqlPool,
-    pub redis: Arc<Mutex<Connection>>,
+    pub users: Arc<dyn UserRepository>,
+    pub two_factors: Arc<dyn TwoFactorRepository>,
+    pub passkeys: Arc<dyn PasskeyRepository>,
+    pub oauth_clients: Arc<dyn OAuthClientRepository>,
+    pub security_events: Arc<dyn SecurityEventRepository>,
+    pub linked_identities: Arc<dyn LinkedIdentityRepository>,
+    pub tokens: Arc<dyn TokenStore>,
+    pub mailer: Arc<dyn Mailer>,
+    pub config: &'static Config,
+}
+
+impl AppState {
+    // Every table in the database behind the pool
+    pub fn with_database(
+        db_pool: DatabasePool,
+        tokens: Arc<dyn TokenStore>,
+        mailer: Arc<dyn Mailer>,
+        config: &'static Config,
+    ) -> Self {
+        AppState {
+            users: Arc::new(SqlUserRepository {
+                db_pool: db_pool.clone(),
+            }),
+            two_factors: Arc::new(SqlTwoFactorRepository {
+                db_pool: db_pool.clone(),
+            }),
+            passkeys: Arc::new(SqlPasskeyRepository {
+                db_pool: db_pool.clone(),
+            }),
+            oauth_clients: Arc::new(SqlOAuthClientRepository {
+                db_pool: db_pool.clone(),
+            }),
+            security_events: Arc::new(SqlSecurityEventRepository {
+                db_pool: db_pool.clone(),
+            }),
+            linked_identities: Arc::new(SqlLinkedIdentityRepository { db_pool }),
+            tokens,
+            mailer,
+            config,
+        }
+    }
+
+    // Nothing leaves the process, for tests running the whole router
+    pub fn in_memory(config: &'static Config) -> Self {
+        AppState {
+            users: Arc::new(InMemoryUserRepository::new()),
+            two_factors: Arc::new(InMemoryTwoFactorRepository::new()),
+            passkeys: Arc::new(InMemoryPasskeyRepository::new()),
+            oauth_clients: Arc::new(InMemoryOAuthClientRepository::new()),
+            security_events: Arc::new(InMemorySecurityEventRepository::new()),
+            linked_identities: Arc::new(InMemoryLinkedIdentityRepository::new()),
+            tokens: Arc::new(InMemoryTokenStore::new()),
+            mailer: Arc::new(InMemoryMailer::new()),
+            config,
+        }
+    }
 }
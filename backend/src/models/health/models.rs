@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+// Reported the same way by every token store, whatever backs it
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct TokenStoreHealth {
+    #[serde(rename = "isHealthy")]
+    pub is_healthy: bool,
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: Option<u64>,
+    // Only logged, the error can name internal hosts and isn't for the public health endpoint
+    #[serde(skip_serializing)]
+    pub last_error: Option<String>,
+    // Times the store switched to another server, like a Redis master looked up again through Sentinel
+    pub failovers: u64,
+    #[serde(rename = "checkedAt")]
+    pub checked_at: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub database: bool,
+    // The token store, named after the Redis the server runs with so existing monitors keep working
+    pub redis: TokenStoreHealth,
+}
@@ -0,0 +1,24 @@
+use serde::Serialize;
+
+// A public verification key in JWK format, see RFC 7517 and RFC 8037
+#[derive(Serialize, Clone)]
+pub struct Jwk {
+    pub kty: String,
+    pub kid: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
@@ -0,0 +1,22 @@
+// A migration embedded in the binary and whether the database has it
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrationInfo {
+    pub version: i64,
+    pub description: String,
+    pub is_applied: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrationStatus {
+    pub migrations: Vec<MigrationInfo>,
+    // Applied to the database but unknown to this binary, like after a rollback to an older release
+    pub unknown_versions: Vec<i64>,
+}
+
+// What bin/api was asked to do, serving the API when no subcommand is given
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ApiCommand {
+    Serve,
+    MigrateUp,
+    MigrateStatus,
+}
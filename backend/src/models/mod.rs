@@ -1,5 +1,18 @@
 pub mod auth;
+pub mod config;
+pub mod database;
 pub mod general;
+pub mod health;
+pub mod jwks;
+pub mod migration;
+pub mod oauth;
+pub mod oidc;
 pub mod otc;
+pub mod passkey;
+pub mod password_policy;
+pub mod redis;
+pub mod session;
+pub mod social;
 pub mod translations;
+pub mod two_factor;
 pub mod user;
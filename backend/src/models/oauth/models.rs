@@ -0,0 +1,134 @@
+use crate::models::user::aliases::Id;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub redirect_uris: String, // Space separated, URIs can't contain spaces
+    pub scopes: String,        // Space separated
+}
+
+#[derive(Deserialize)]
+pub struct OAuthClientRequest {
+    pub name: String,
+    #[serde(rename = "redirectUris")]
+    pub redirect_uris: Vec<String>,
+    pub scopes: Option<Vec<String>>,
+    // Public clients like SPAs and mobile apps can't keep a secret and rely on PKCE alone
+    #[serde(rename = "isConfidential", default = "default_is_confidential")]
+    pub is_confidential: bool,
+}
+
+fn default_is_confidential() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+pub struct OAuthClientResponse {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    // Only returned once, when the client is registered
+    #[serde(rename = "clientSecret", skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub name: String,
+    #[serde(rename = "redirectUris")]
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    #[serde(rename = "isConfidential")]
+    pub is_confidential: bool,
+}
+
+#[derive(Deserialize)]
+pub struct AuthorizeRequest {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub nonce: Option<String>,
+}
+
+// Sent by the frontend consent page, with the parameters of the authorization request it was shown for
+#[derive(Deserialize)]
+pub struct AuthorizeConsentRequest {
+    #[serde(flatten)]
+    pub authorization: AuthorizeRequest,
+    pub approved: bool,
+}
+
+// The frontend sends the browser on to the client, a fetch can't follow a redirect to another origin
+#[derive(Serialize)]
+pub struct AuthorizeConsentResponse {
+    #[serde(rename = "redirectUri")]
+    pub redirect_uri: String,
+}
+
+// Stored in Redis under the authorization code key until the client exchanges it
+#[derive(Serialize, Deserialize)]
+pub struct AuthorizationCodePayload {
+    pub client_id: String,
+    pub user_id: Id,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    // OpenID Connect nonce, echoed back in the ID token
+    #[serde(default)]
+    pub nonce: Option<String>,
+    // Unix timestamp in milliseconds, grants issued before a revocation of the user are refused
+    #[serde(default)]
+    pub issued_at: i64,
+}
+
+// Stored in Redis under the consent key of a user and client
+#[derive(Serialize, Deserialize)]
+pub struct OAuthConsentPayload {
+    pub scope: String,
+    // Unix timestamp in milliseconds
+    pub granted_at: i64,
+}
+
+// Stored in Redis under the OAuth refresh token key
+#[derive(Serialize, Deserialize)]
+pub struct OAuthRefreshTokenPayload {
+    pub client_id: String,
+    pub user_id: Id,
+    pub scope: String,
+    // Unix timestamp in milliseconds
+    #[serde(default)]
+    pub issued_at: i64,
+}
+
+// Field names follow RFC 6749, so they are snake_case on the wire
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub scope: String,
+    // Only issued when the openid scope was granted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+    pub error_description: String,
+}
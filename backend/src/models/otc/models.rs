@@ -1,10 +1,16 @@
+use crate::models::auth::models::AuthResponse;
 use crate::models::otc::aliases::Otc;
+use crate::models::two_factor::models::TwoFactorChallengeResponse;
 use crate::models::user::aliases::{Email, Id, Name, Phone};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
 pub struct OtcRequest {
     pub otc: Otc,
+    // The email the code was sent to, codes are scoped to it
+    pub email: Email,
+    // Only for login codes, which are accepted from the signed magic link alone, see is_valid_login_otc
+    pub signature: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -12,11 +18,13 @@ pub enum OtcPayloadAction {
     ConfirmAccount,
     DeleteAccount,
     UpdateAccount,
+    DisableTwoFactor,
+    RegenerateRecoveryCodes,
+    Login,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct OtcPayload {
-    pub otc: String,
     pub user_id: Id,
     pub action: OtcPayloadAction,
     pub name: Name,
@@ -24,3 +32,10 @@ pub struct OtcPayload {
     pub email: Email,
     pub password_hash: Option<String>,
 }
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum OtcResponse {
+    Account(AuthResponse),
+    TwoFactorChallenge(TwoFactorChallengeResponse),
+}
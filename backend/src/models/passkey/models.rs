@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasskeyUserEntity {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicKeyCredentialParameters {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub alg: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicKeyCredentialDescriptor {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AuthenticatorSelection {
+    #[serde(rename = "residentKey")]
+    pub resident_key: String,
+    #[serde(rename = "userVerification")]
+    pub user_verification: String,
+}
+
+// Mirrors PublicKeyCredentialCreationOptions, passed to navigator.credentials.create()
+#[derive(Serialize, Deserialize)]
+pub struct PasskeyRegistrationOptions {
+    pub rp: RelyingParty,
+    pub user: PasskeyUserEntity,
+    pub challenge: String,
+    #[serde(rename = "pubKeyCredParams")]
+    pub pub_key_cred_params: Vec<PublicKeyCredentialParameters>,
+    pub timeout: u64,
+    pub attestation: String,
+    #[serde(rename = "excludeCredentials")]
+    pub exclude_credentials: Vec<PublicKeyCredentialDescriptor>,
+    #[serde(rename = "authenticatorSelection")]
+    pub authenticator_selection: AuthenticatorSelection,
+}
+
+// Mirrors PublicKeyCredentialRequestOptions, passed to navigator.credentials.get()
+#[derive(Serialize, Deserialize)]
+pub struct PasskeyAuthenticationOptions {
+    pub challenge: String,
+    #[serde(rename = "rpId")]
+    pub rp_id: String,
+    pub timeout: u64,
+    #[serde(rename = "userVerification")]
+    pub user_verification: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasskeyAttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasskeyRegistrationRequest {
+    pub id: String,
+    pub response: PasskeyAttestationResponse,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasskeyAssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(rename = "userHandle")]
+    pub user_handle: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasskeyAuthenticationRequest {
+    pub id: String,
+    pub response: PasskeyAssertionResponse,
+}
+
+// The client data the browser signs over, see https://www.w3.org/TR/webauthn-2/#dictionary-client-data
+#[derive(Serialize, Deserialize)]
+pub struct CollectedClientData {
+    #[serde(rename = "type")]
+    pub ceremony_type: String,
+    pub challenge: String,
+    pub origin: String,
+}
+
+pub struct RegisteredPasskey {
+    pub credential_id: Vec<u8>,
+    pub public_key: Vec<u8>,
+    pub sign_count: u32,
+}
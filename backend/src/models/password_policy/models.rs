@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CharacterSet {
+    Lowercase,
+    Uppercase,
+    Number,
+    Space,
+    Symbol,
+    // Letters of scripts without case, like Chinese or Arabic
+    Letter,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PasswordPolicy {
+    #[serde(rename = "minLength")]
+    pub min_length: usize,
+    #[serde(rename = "maxLength")]
+    pub max_length: usize,
+    #[serde(rename = "requiredCharacterSets")]
+    pub required_character_sets: Vec<CharacterSet>,
+    #[serde(rename = "allowedCharacterSets")]
+    pub allowed_character_sets: Vec<CharacterSet>,
+    // Strength score from 0 (guessable) to 4 (very unguessable), like zxcvbn
+    #[serde(rename = "minScore")]
+    pub min_score: u8,
+    #[serde(rename = "rejectPersonalInfo")]
+    pub reject_personal_info: bool,
+}
@@ -0,0 +1,17 @@
+// Where to find Redis, either a single server or the master of a Sentinel service
+#[derive(Clone, Debug, PartialEq)]
+pub enum RedisTarget {
+    Server(String),
+    Sentinel(SentinelTarget),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentinelTarget {
+    pub sentinel_addresses: Vec<(String, u16)>,
+    pub service_name: String,
+    pub db: i64,
+    // Credentials and TLS of the master, the sentinels are reached with the same TLS setting
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub use_tls: bool,
+}
@@ -0,0 +1,67 @@
+use crate::models::user::aliases::Id;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// Stored in Redis under the refresh token key, the session id doubles as the token family
+#[derive(Serialize, Deserialize)]
+pub struct RefreshTokenPayload {
+    pub user_id: Id,
+    pub session_id: String,
+}
+
+// Kept under the rotated key of a spent refresh token, to tell a concurrent refresh from a replay
+#[derive(Serialize, Deserialize)]
+pub struct RotatedRefreshTokenPayload {
+    pub user_id: Id,
+    pub session_id: String,
+    pub rotated_at: DateTime<Utc>,
+}
+
+// One entry in the per-user session registry, kept alive as long as its refresh token
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionPayload {
+    pub id: String,
+    pub user_id: Id,
+    pub refresh_token_key: String,
+    pub created_at: DateTime<Utc>,
+    pub last_refreshed_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+// The device a request comes from, recorded when a session is started or refreshed
+#[derive(Clone, Default)]
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SessionResponse {
+    pub id: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "lastRefreshedAt")]
+    pub last_refreshed_at: DateTime<Utc>,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(rename = "isCurrent")]
+    pub is_current: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SessionPath {
+    pub id: String,
+}
+
+// Result of exchanging a refresh token, the new tokens still have to be set as cookies. A concurrent
+// refresh within the grace window only gets a JWT, the refresh token went to the request that won
+pub struct RotatedRefreshToken {
+    pub user_id: Id,
+    pub name: String,
+    pub email: String,
+    pub session_id: String,
+    pub jwt: String,
+    pub refresh_token: Option<String>,
+}
@@ -0,0 +1,117 @@
+use crate::models::user::aliases::Id;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SocialProviderKind {
+    // Any provider with a standard OpenID Connect userinfo endpoint, like Google
+    Oidc,
+    // GitHub only speaks plain OAuth 2.0, the profile and emails come from its REST API
+    GitHub,
+}
+
+#[derive(Clone, Debug)]
+pub struct SocialProvider {
+    pub name: String,
+    pub kind: SocialProviderKind,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorization_url: String,
+    pub token_url: String,
+    pub userinfo_url: String,
+    pub emails_url: Option<String>,
+    pub scopes: String,
+}
+
+// Stored in Redis under the state key until the provider redirects back
+#[derive(Serialize, Deserialize)]
+pub struct SocialLoginPayload {
+    pub provider: String,
+    pub code_verifier: String,
+}
+
+// Stored in Redis under the link token key until the user confirms the password of the account
+#[derive(Serialize, Deserialize)]
+pub struct SocialLinkPayload {
+    pub user_id: Id,
+    pub provider: String,
+    pub subject: String,
+}
+
+#[derive(Deserialize)]
+pub struct SocialLinkRequest {
+    #[serde(rename = "linkToken")]
+    pub link_token: String,
+    pub password: String,
+}
+
+// Outcome of matching a provider identity to an account
+#[derive(Debug, PartialEq)]
+pub enum SocialUser {
+    // Linked before, or a new account was created for it
+    Linked(Id),
+    // An account with the same email exists, the token links it once the password is confirmed
+    PendingLink(String),
+    // Without a verified email anyone could claim an existing account by setting its email at the provider
+    EmailNotVerified,
+}
+
+#[derive(Deserialize)]
+pub struct SocialProviderPath {
+    pub provider: String,
+}
+
+#[derive(Deserialize)]
+pub struct SocialCallbackRequest {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SocialProvidersResponse {
+    pub providers: Vec<String>,
+}
+
+// The user as described by the provider, the subject is only unique within that provider
+#[derive(Debug, PartialEq)]
+pub struct SocialProfile {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ProviderTokenResponse {
+    pub access_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct OidcDiscoveryDocument {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+}
+
+#[derive(Deserialize)]
+pub struct OidcUserInfo {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubUser {
+    pub id: i64,
+    pub login: String,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubEmail {
+    pub email: String,
+    pub primary: bool,
+    pub verified: bool,
+}
@@ -0,0 +1,45 @@
+use crate::models::user::aliases::Id;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    pub code: Option<String>,
+    #[serde(rename = "recoveryCode")]
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorSetupResponse {
+    pub secret: String,
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorChallengeResponse {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+}
+
+// Stored in Redis while a user that passed the password check still has to provide a TOTP code
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorChallengePayload {
+    pub user_id: Id,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    pub codes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecoveryCodesStatusResponse {
+    pub remaining: i64,
+}
@@ -0,0 +1,10 @@
+pub const CREATE_LINKED_IDENTITY: &str = r#"
+    INSERT INTO linked_identities (user_id, provider, subject)
+    VALUES (?, ?, ?);
+"#;
+
+pub const GET_LINKED_IDENTITY_USER_ID: &str = r#"
+    SELECT user_id
+    FROM linked_identities
+    WHERE provider = ? AND subject = ?;
+"#;
@@ -1 +1,7 @@
+pub mod linked_identity;
+pub mod oauth;
+pub mod passkey;
+pub mod password_history;
+pub mod security_event;
+pub mod two_factor;
 pub mod user;
@@ -0,0 +1,17 @@
+pub const CREATE_OAUTH_CLIENT: &str = r#"
+    INSERT INTO oauth_clients (client_id, client_secret_hash, name, redirect_uris, scopes, owner_id)
+    VALUES (?, ?, ?, ?, ?, ?)
+"#;
+
+pub const GET_OAUTH_CLIENT_BY_CLIENT_ID: &str = r#"
+    SELECT client_id, client_secret_hash, name, redirect_uris, scopes
+    FROM oauth_clients
+    WHERE client_id = ?;
+"#;
+
+pub const GET_OAUTH_CLIENTS_BY_OWNER_ID: &str = r#"
+    SELECT client_id, client_secret_hash, name, redirect_uris, scopes
+    FROM oauth_clients
+    WHERE owner_id = ?
+    ORDER BY created_at;
+"#;
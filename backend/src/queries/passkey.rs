@@ -0,0 +1,22 @@
+pub const CREATE_PASSKEY: &str = r#"
+    INSERT INTO passkeys (user_id, credential_id, public_key, sign_count)
+    VALUES (?, ?, ?, ?)
+"#;
+
+pub const GET_PASSKEY_BY_CREDENTIAL_ID: &str = r#"
+    SELECT user_id, public_key, sign_count
+    FROM passkeys
+    WHERE credential_id = ?;
+"#;
+
+pub const GET_PASSKEY_CREDENTIAL_IDS_BY_USER_ID: &str = r#"
+    SELECT credential_id
+    FROM passkeys
+    WHERE user_id = ?;
+"#;
+
+pub const UPDATE_PASSKEY_SIGN_COUNT: &str = r#"
+    UPDATE passkeys
+    SET sign_count = ?, last_used_at = CURRENT_TIMESTAMP
+    WHERE credential_id = ?;
+"#;
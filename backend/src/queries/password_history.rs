@@ -0,0 +1,28 @@
+pub const CREATE_PASSWORD_HISTORY_ENTRY: &str = r#"
+    INSERT INTO password_history (user_id, password_hash)
+    SELECT id, password_hash
+    FROM users
+    WHERE id = ?;
+"#;
+
+pub const GET_PASSWORD_HISTORY_BY_USER_ID: &str = r#"
+    SELECT password_hash
+    FROM password_history
+    WHERE user_id = ?
+    ORDER BY id DESC
+    LIMIT ?;
+"#;
+
+// MySQL doesn't allow LIMIT in an IN subquery, the extra derived table works around that
+pub const DELETE_OLD_PASSWORD_HISTORY_BY_USER_ID: &str = r#"
+    DELETE FROM password_history
+    WHERE user_id = ? AND id NOT IN (
+        SELECT id FROM (
+            SELECT id
+            FROM password_history
+            WHERE user_id = ?
+            ORDER BY id DESC
+            LIMIT ?
+        ) AS recent_password_history
+    );
+"#;
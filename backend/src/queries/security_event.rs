@@ -0,0 +1,4 @@
+pub const CREATE_SECURITY_EVENT: &str = r#"
+    INSERT INTO security_events (user_id, event_type, session_id, ip, user_agent)
+    VALUES (?, ?, ?, ?, ?)
+"#;
@@ -0,0 +1,50 @@
+pub const GET_TWO_FACTOR_BY_USER_ID: &str = r#"
+    SELECT secret, is_enabled
+    FROM user_totp
+    WHERE user_id = ?;
+"#;
+
+pub const UPSERT_TWO_FACTOR_SECRET: &str = r#"
+    INSERT INTO user_totp (user_id, secret, is_enabled)
+    VALUES (?, ?, false)
+    ON DUPLICATE KEY UPDATE secret = VALUES(secret), is_enabled = false;
+"#;
+
+// Postgres and SQLite spell the upsert with ON CONFLICT
+pub const UPSERT_TWO_FACTOR_SECRET_ON_CONFLICT: &str = r#"
+    INSERT INTO user_totp (user_id, secret, is_enabled)
+    VALUES (?, ?, false)
+    ON CONFLICT (user_id) DO UPDATE SET secret = excluded.secret, is_enabled = false;
+"#;
+
+pub const ENABLE_TWO_FACTOR: &str = r#"
+    UPDATE user_totp
+    SET is_enabled = true
+    WHERE user_id = ?;
+"#;
+
+pub const DELETE_TWO_FACTOR: &str = r#"
+    DELETE FROM user_totp
+    WHERE user_id = ?;
+"#;
+
+pub const CREATE_RECOVERY_CODE: &str = r#"
+    INSERT INTO recovery_codes (user_id, code_hash)
+    VALUES (?, ?)
+"#;
+
+pub const COUNT_RECOVERY_CODES_BY_USER_ID: &str = r#"
+    SELECT COUNT(*)
+    FROM recovery_codes
+    WHERE user_id = ?;
+"#;
+
+pub const DELETE_RECOVERY_CODE: &str = r#"
+    DELETE FROM recovery_codes
+    WHERE user_id = ? AND code_hash = ?;
+"#;
+
+pub const DELETE_RECOVERY_CODES_BY_USER_ID: &str = r#"
+    DELETE FROM recovery_codes
+    WHERE user_id = ?;
+"#;
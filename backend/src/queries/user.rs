@@ -3,6 +3,13 @@ pub const CREATE_USER: &str = r#"
     VALUES (?, ?, ?)
 "#;
 
+// Postgres has no last insert id, SQLite takes the same query
+pub const CREATE_USER_RETURNING_ID: &str = r#"
+    INSERT INTO users (name, email, password_hash)
+    VALUES (?, ?, ?)
+    RETURNING id
+"#;
+
 pub const DELETE_USER: &str = r#"
     DELETE FROM users 
     WHERE id = ?;
@@ -43,3 +50,9 @@ pub const GET_USER_BY_ID: &str = r#"
     FROM users
     WHERE id = ?;
 "#;
+
+pub const GET_USER_PASSWORD_HASH_BY_ID: &str = r#"
+    SELECT password_hash
+    FROM users
+    WHERE id = ?;
+"#;
@@ -0,0 +1,33 @@
+use axum::{middleware, Router};
+
+use crate::{
+    middleware::{
+        jwt::jwt_middleware, language::language_middleware, rate_limit::rate_limit_middleware,
+    },
+    models::general::AppState,
+    routes::{
+        auth::auth_routes, health::health_routes, oauth::oauth_routes, otc::otc_routes,
+        user::user_routes, well_known::well_known_routes,
+    },
+};
+
+// The whole API with its middleware, shared by the server and the in-process tests
+pub fn app_routes(state: AppState) -> Router {
+    Router::new()
+        .nest("/api/user", user_routes())
+        .nest("/api/auth", auth_routes())
+        .nest("/api/otc", otc_routes())
+        .nest("/oauth", oauth_routes())
+        .nest("/.well-known", well_known_routes())
+        .nest("/health", health_routes())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            jwt_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit_middleware,
+        ))
+        .layer(middleware::from_fn(language_middleware))
+        .with_state(state)
+}
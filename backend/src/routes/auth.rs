@@ -1,8 +1,26 @@
-use axum::{routing::post, Router};
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
 
 use crate::{
     models::general::AppState,
-    services::auth::{login_user, logout_user, refresh},
+    services::{
+        auth::{login_user, logout_user, refresh, request_magic_link},
+        passkey::{
+            finish_passkey_login, finish_passkey_registration, start_passkey_login,
+            start_passkey_registration,
+        },
+        session::{list_sessions, revoke_other_sessions, revoke_session_by_id},
+        social::{
+            finish_social_login, link_social_identity, list_social_providers, start_social_login,
+        },
+        two_factor::{
+            confirm_two_factor, disable_two_factor, get_recovery_codes_status,
+            regenerate_recovery_codes, request_recovery_codes_regeneration, setup_two_factor,
+            verify_two_factor_login,
+        },
+    },
 };
 
 pub fn auth_routes() -> Router<AppState> {
@@ -10,4 +28,32 @@ pub fn auth_routes() -> Router<AppState> {
         .route("/token", post(refresh))
         .route("/", post(login_user))
         .route("/logout", post(logout_user))
+        .route("/magic-link", post(request_magic_link))
+        .route("/sessions", get(list_sessions))
+        .route("/sessions", delete(revoke_other_sessions))
+        .route("/sessions/{id}", delete(revoke_session_by_id))
+        .route("/2fa", post(verify_two_factor_login))
+        .route("/2fa", delete(disable_two_factor))
+        .route("/2fa/setup", post(setup_two_factor))
+        .route("/2fa/confirm", post(confirm_two_factor))
+        .route("/2fa/recovery-codes", get(get_recovery_codes_status))
+        .route(
+            "/2fa/recovery-codes",
+            post(request_recovery_codes_regeneration),
+        )
+        .route(
+            "/2fa/recovery-codes/regenerate",
+            post(regenerate_recovery_codes),
+        )
+        .route(
+            "/passkeys/register/options",
+            post(start_passkey_registration),
+        )
+        .route("/passkeys/register", post(finish_passkey_registration))
+        .route("/passkeys/login/options", post(start_passkey_login))
+        .route("/passkeys/login", post(finish_passkey_login))
+        .route("/social", get(list_social_providers))
+        .route("/social/link", post(link_social_identity))
+        .route("/social/{provider}", get(start_social_login))
+        .route("/social/{provider}/callback", get(finish_social_login))
 }
@@ -0,0 +1,7 @@
+use axum::{routing::get, Router};
+
+use crate::{models::general::AppState, services::health::get_health};
+
+pub fn health_routes() -> Router<AppState> {
+    Router::new().route("/", get(get_health))
+}
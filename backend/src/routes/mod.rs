@@ -1,3 +1,7 @@
+pub mod app;
 pub mod auth;
+pub mod health;
+pub mod oauth;
 pub mod otc;
 pub mod user;
+pub mod well_known;
@@ -0,0 +1,20 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::{
+    models::general::AppState,
+    services::oauth::{
+        authorize, authorize_consent, list_oauth_clients, register_oauth_client, token, userinfo,
+    },
+};
+
+pub fn oauth_routes() -> Router<AppState> {
+    Router::new()
+        .route("/authorize", get(authorize).post(authorize_consent))
+        .route("/token", post(token))
+        .route("/userinfo", get(userinfo).post(userinfo))
+        .route("/clients", get(list_oauth_clients))
+        .route("/clients", post(register_oauth_client))
+}
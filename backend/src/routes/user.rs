@@ -6,8 +6,8 @@ use axum::{
 use crate::{
     models::general::AppState,
     services::user::{
-        delete_user, get_user, register_user, request_password_reset_token,
-        reset_password_with_token, update_user,
+        delete_user, get_password_policy_settings, get_user, register_user,
+        request_password_reset_token, reset_password_with_token, update_user,
     },
 };
 
@@ -22,4 +22,5 @@ pub fn user_routes() -> Router<AppState> {
             post(request_password_reset_token),
         )
         .route("/reset-password", patch(reset_password_with_token))
+        .route("/password-policy", get(get_password_policy_settings))
 }
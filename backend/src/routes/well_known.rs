@@ -0,0 +1,12 @@
+use axum::{routing::get, Router};
+
+use crate::{
+    models::general::AppState,
+    services::well_known::{get_jwks, get_openid_configuration},
+};
+
+pub fn well_known_routes() -> Router<AppState> {
+    Router::new()
+        .route("/jwks.json", get(get_jwks))
+        .route("/openid-configuration", get(get_openid_configuration))
+}
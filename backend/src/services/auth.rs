@@ -1,15 +1,25 @@
 use std::sync::Arc;
 
-use crate::constants::auth::{BEARER_EXPIRATION_SECONDS, REFRESH_EXPIRATION_SECONDS};
-use crate::models::auth::models::{AuthResponse, LoginUser};
+use crate::models::auth::models::{AuthResponse, JwtClaims, LoginUser, MagicLinkRequest};
 use crate::models::general::AppState;
+use crate::models::otc::models::{OtcPayload, OtcPayloadAction};
+use crate::models::session::models::SessionClient;
 use crate::models::translations::Translations;
-use crate::utils::auth::verify_password;
+use crate::models::two_factor::models::TwoFactorChallengeResponse;
+use crate::utils::auth::{hash_password, needs_password_rehash, verify_password};
 use crate::utils::cookie::{delete_cookie, get_cookie, set_cookie};
-use crate::utils::jwt::{encode_jwt, format_refresh_token_key, generate_refresh_token};
-use crate::utils::redis::{get_token, remove_token, set_token};
+use crate::utils::emails::send_magic_link_email;
+use crate::utils::errors::ServiceError;
+use crate::utils::jwt::{format_refresh_token_key, revoke_jwt};
+use crate::utils::otc::{sign_login_otc, store_otc};
 use crate::utils::responses::{ApiResponse, AppError};
-use crate::utils::user::{get_user_by_email, get_user_by_id};
+use crate::utils::session::{
+    get_refresh_token_payload, get_session, get_session_client, revoke_session,
+    rotate_refresh_token, start_session,
+};
+use crate::utils::throttle::{clear_login_failures, start_login_attempt};
+use crate::utils::two_factor::{create_two_factor_challenge, is_two_factor_enabled};
+use crate::utils::user::{get_user_by_email, update_user_password};
 use axum::response::IntoResponse;
 use axum::{
     body::Body,
@@ -23,17 +33,32 @@ use http::HeaderValue;
 pub async fn login_user(
     State(state): State<AppState>,
     Extension(translations): Extension<Arc<Translations>>,
+    client: SessionClient,
     Json(user_data): Json<LoginUser>,
 ) -> Result<impl IntoResponse, AppError> {
+    if let Some(retry_after) = start_login_attempt(&state, &user_data.email, &client)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::TOO_MANY_REQUESTS,
+            "auth.errors.too_many_attempts",
+        )
+        .with_retry_after(retry_after));
+    }
+
     let user = match get_user_by_email(&state, &user_data.email).await {
         Ok(user) => user,
-        Err(_) => {
+        Err(ServiceError::NotFound) => {
+            // Unknown emails count as failures too, so throttling doesn't reveal which accounts exist
             return Err(AppError::format_error(
                 &translations,
                 StatusCode::UNAUTHORIZED,
                 "auth.errors.invalid_credentials",
-            ))
+            ));
         }
+        Err(error) => return Err(AppError::format_service_error(&translations, error)),
     };
 
     let (id, name, email, password_hash, phone, is_confirmed) = user;
@@ -47,7 +72,8 @@ pub async fn login_user(
     }
 
     if !verify_password(&user_data.password, &password_hash)
-        .map_err(|_| AppError::format_internal_error(&translations))?
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
     {
         return Err(AppError::format_error(
             &translations,
@@ -56,19 +82,38 @@ pub async fn login_user(
         ));
     }
 
-    let new_jwt = encode_jwt(&id, &name, &email)
-        .map_err(|_| AppError::format_internal_error(&translations))?;
-    let new_refresh_token = generate_refresh_token();
-    let new_redis_refresh_token_key = format_refresh_token_key(&new_refresh_token);
+    clear_login_failures(&state, &user_data.email, &client)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    // Hashes made with another algorithm or outdated parameters are upgraded while the password is at hand
+    if needs_password_rehash(&password_hash) {
+        let new_password_hash = hash_password(&user_data.password)
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+        update_user_password(&state, &id, &new_password_hash)
+            .await
+            .map_err(|error| AppError::format_service_error(&translations, error))?;
+    }
+
+    if is_two_factor_enabled(&state, &id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        let challenge_token = create_two_factor_challenge(&state, &id)
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+        let response_body = ApiResponse::format_success(
+            &translations,
+            StatusCode::OK,
+            "auth.success.two_factor_required",
+            Some(TwoFactorChallengeResponse { challenge_token }),
+        );
 
-    set_token(
-        &state,
-        &new_redis_refresh_token_key,
-        &id.to_string(),
-        REFRESH_EXPIRATION_SECONDS,
-    )
-    .await
-    .map_err(|_| AppError::format_internal_error(&translations))?;
+        return Ok(response_body.into_response());
+    }
 
     let response_body = ApiResponse::format_success(
         &translations,
@@ -76,28 +121,15 @@ pub async fn login_user(
         "auth.success.user_logged_in",
         Some(AuthResponse {
             id,
-            name,
-            email,
+            name: name.clone(),
+            email: email.clone(),
             phone,
         }),
     );
 
     let mut response = response_body.into_response();
 
-    response = set_cookie(
-        &translations,
-        response,
-        "Bearer",
-        &new_jwt,
-        Some(BEARER_EXPIRATION_SECONDS),
-    )?;
-    response = set_cookie(
-        &translations,
-        response,
-        "RefreshToken",
-        &new_refresh_token,
-        Some(REFRESH_EXPIRATION_SECONDS),
-    )?;
+    response = start_session(&state, &translations, response, &client, &id, &name, &email).await?;
 
     let content_type_header_value = match "application/json".parse() {
         Ok(header) => header,
@@ -114,18 +146,46 @@ pub async fn login_user(
 pub async fn logout_user(
     State(state): State<AppState>,
     Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
     req: Request<Body>,
 ) -> Result<impl IntoResponse, AppError> {
-    let refresh_token = match get_cookie(&req, "RefreshToken") {
-        Some(payload) => payload,
-        None => return Err(AppError::format_internal_error(&translations)),
+    // The JWT names the session, the refresh token cookie is only needed for JWTs issued without one
+    let session_id = match claims.sid.clone() {
+        Some(session_id) => session_id,
+        None => {
+            let refresh_token = match get_cookie(&req, "RefreshToken") {
+                Some(payload) => payload,
+                None => return Err(AppError::format_internal_error(&translations)),
+            };
+
+            let formatted_refresh_token_key =
+                format_refresh_token_key(state.config, &refresh_token)
+                    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+            let token_payload = get_refresh_token_payload(&state, &formatted_refresh_token_key)
+                .await
+                .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+            match token_payload {
+                Some(payload) => payload.session_id,
+                None => return Err(AppError::format_internal_error(&translations)),
+            }
+        }
     };
 
-    let formatted_refresh_token_key = format_refresh_token_key(&refresh_token);
+    revoke_jwt(&state, &claims)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
-    remove_token(&state, &formatted_refresh_token_key)
+    let session = get_session(&state, &session_id)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    if let Some(session) = session {
+        revoke_session(&state, &session)
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+    }
 
     let response_body = ApiResponse::<()>::format_success(
         &translations,
@@ -161,64 +221,27 @@ pub async fn refresh(
         None => return Err(AppError::format_internal_error(&translations)),
     };
 
-    let formatted_refresh_token_key = format_refresh_token_key(&refresh_token);
+    let client = get_session_client(state.config, req.headers(), req.extensions());
 
-    let token_payload: Option<i32> = get_token(&state, &formatted_refresh_token_key)
+    let rotated_refresh_token = rotate_refresh_token(&state, &refresh_token, &client)
         .await
-        .map_err(|_| {
-            AppError::format_error(
-                &translations,
-                StatusCode::UNAUTHORIZED,
-                "auth.errors.failed_to_read_token_payload",
-            )
-        })?
-        .map(|json| {
-            serde_json::from_str(&json).map_err(|_| AppError::format_internal_error(&translations))
-        })
-        .transpose()?;
-
-    let user_id = match token_payload {
-        Some(payload) => payload,
-        None => {
-            return Err(AppError::format_error(
+        .map_err(|status| match status {
+            StatusCode::UNAUTHORIZED => AppError::format_error(
                 &translations,
                 StatusCode::UNAUTHORIZED,
                 "auth.errors.failed_to_read_token_payload",
-            ))
-        }
-    };
-
-    let user_data = match get_user_by_id(&state, &user_id).await {
-        Ok(user) => user,
-        Err(_) => return Err(AppError::format_internal_error(&translations)),
-    };
-
-    remove_token(&state, &formatted_refresh_token_key)
-        .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
-
-    let new_refresh_token = generate_refresh_token();
-    let new_refresh_token_key = format_refresh_token_key(&new_refresh_token);
-
-    let _ = set_token(
-        &state,
-        &new_refresh_token_key,
-        &user_id.to_string(),
-        REFRESH_EXPIRATION_SECONDS,
-    )
-    .await;
-
-    let new_jwt = encode_jwt(&user_id, &user_data.1, &user_data.2)
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+            ),
+            _ => AppError::format_internal_error(&translations),
+        })?;
 
     let response_body = ApiResponse::format_success(
         &translations,
         StatusCode::OK,
         "auth.success.refresh_processed",
         Some(AuthResponse {
-            id: user_data.0,
-            name: user_data.1,
-            email: user_data.2,
+            id: rotated_refresh_token.user_id,
+            name: rotated_refresh_token.name,
+            email: rotated_refresh_token.email,
             phone: None,
         }),
     );
@@ -235,18 +258,70 @@ pub async fn refresh(
         &translations,
         response,
         "Bearer",
-        &new_jwt,
-        Some(BEARER_EXPIRATION_SECONDS),
+        &rotated_refresh_token.jwt,
+        Some(state.config.tokens.bearer_expiration_seconds),
     )?;
 
-    // Don't need to delete the old RefreshToken from the cookies, because it is overwritten here
-    response = set_cookie(
-        &translations,
-        response,
-        "RefreshToken",
-        &new_refresh_token,
-        Some(REFRESH_EXPIRATION_SECONDS),
-    )?;
+    // Don't need to delete the old RefreshToken from the cookies, because it is overwritten here.
+    // A concurrent refresh has none, the cookie already holds the token of the request that won.
+    if let Some(refresh_token) = &rotated_refresh_token.refresh_token {
+        response = set_cookie(
+            &translations,
+            response,
+            "RefreshToken",
+            refresh_token,
+            Some(state.config.tokens.refresh_expiration_seconds),
+        )?;
+    }
 
     Ok(response)
 }
+
+// Emails a single use login code and signed link, the work happens in the background so the response
+// is the same, and takes as long, whether or not the email belongs to an account
+pub async fn request_magic_link(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Json(user_data): Json<MagicLinkRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let background_translations = translations.clone();
+
+    tokio::spawn(async move {
+        let _ = send_magic_link(&state, &background_translations, &user_data.email).await;
+    });
+
+    Ok(ApiResponse::<()>::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.magic_link_requested",
+        None,
+    ))
+}
+
+async fn send_magic_link(
+    state: &AppState,
+    translations: &Translations,
+    email: &str,
+) -> Result<(), StatusCode> {
+    let (id, name, email, _, phone, is_confirmed) = get_user_by_email(state, email).await?;
+
+    if !is_confirmed {
+        return Ok(());
+    }
+
+    let otc_payload = OtcPayload {
+        user_id: id,
+        action: OtcPayloadAction::Login,
+        name,
+        email: email.clone(),
+        password_hash: None,
+        phone,
+    };
+
+    let otc = store_otc(state, &email, &otc_payload).await?;
+    let signature = sign_login_otc(state.config, &otc, &email)?;
+
+    send_magic_link_email(state, translations, &otc, &signature, &email).await?;
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use axum::{extract::State, response::IntoResponse, Json};
+use http::StatusCode;
+
+use crate::models::{general::AppState, health::models::HealthResponse};
+
+// For load balancers and orchestrators, served as plain JSON and answering 503 when a dependency is down
+pub async fn get_health(State(state): State<AppState>) -> impl IntoResponse {
+    let database = state.users.is_healthy().await;
+
+    let tokens = state.tokens.check_health().await;
+
+    if let Some(last_error) = &tokens.last_error {
+        eprintln!("Token store is unhealthy: {}", last_error);
+    }
+
+    let (status_code, status) = if database && tokens.is_healthy {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "degraded")
+    };
+
+    (
+        status_code,
+        Json(HealthResponse {
+            status,
+            database,
+            redis: tokens,
+        }),
+    )
+}
@@ -1,3 +1,10 @@
 pub mod auth;
+pub mod health;
+pub mod oauth;
 pub mod otc;
+pub mod passkey;
+pub mod session;
+pub mod social;
+pub mod two_factor;
 pub mod user;
+pub mod well_known;
@@ -0,0 +1,719 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{OriginalUri, Query, State},
+    http::Uri,
+    response::{IntoResponse, Redirect, Response},
+    Extension, Form, Json,
+};
+use chrono::Utc;
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use url::Url;
+
+use crate::{
+    constants::oauth::{
+        OAUTH_ACCESS_TOKEN_EXPIRATION_SECONDS, OAUTH_AUTHORIZATION_CODE_BYTES,
+        OAUTH_AUTHORIZATION_CODE_EXPIRATION_SECONDS, OAUTH_CLIENT_SECRET_BYTES,
+        OAUTH_MAX_CLIENT_NAME_LENGTH, OAUTH_MAX_REDIRECT_URIS, OAUTH_REFRESH_TOKEN_BYTES,
+        OAUTH_REFRESH_TOKEN_EXPIRATION_SECONDS, OAUTH_SUPPORTED_SCOPES, OIDC_SCOPE,
+    },
+    models::{
+        auth::models::JwtClaims,
+        general::AppState,
+        oauth::models::{
+            AuthorizationCodePayload, AuthorizeConsentRequest, AuthorizeConsentResponse,
+            AuthorizeRequest, OAuthClient, OAuthClientRequest, OAuthClientResponse,
+            OAuthRefreshTokenPayload, TokenRequest, TokenResponse,
+        },
+        translations::Translations,
+    },
+    utils::{
+        jwt::{decode_access_token, encode_access_token, is_jwt_revoked},
+        oauth::{
+            authenticate_oauth_client, create_oauth_client, format_authorization_code_key,
+            format_oauth_refresh_token_key, format_redirect_uri, generate_oauth_client_id,
+            generate_oauth_token, get_oauth_client_by_client_id, get_oauth_clients_by_owner_id,
+            has_oauth_consent, hash_client_secret, is_oauth_admin, is_oauth_grant_revoked,
+            is_valid_redirect_uri, parse_basic_authorization, resolve_scope, store_oauth_consent,
+            verify_pkce_challenge,
+        },
+        oidc::{encode_id_token, format_oidc_user_claims, get_issuer_url, has_scope},
+        redis::{set_token, take_token},
+        responses::{ApiResponse, AppError, OAuthError},
+        user::get_user_by_id,
+    },
+};
+
+fn format_oauth_client_response(
+    client: OAuthClient,
+    client_secret: Option<String>,
+) -> OAuthClientResponse {
+    OAuthClientResponse {
+        redirect_uris: client.get_redirect_uris(),
+        scopes: client.get_scopes(),
+        is_confidential: client.is_confidential(),
+        client_id: client.client_id,
+        client_secret,
+        name: client.name,
+    }
+}
+
+pub async fn register_oauth_client(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(client_data): Json<OAuthClientRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    // Clients pick their own redirect URIs and get codes for any user, so only admins can add them
+    if !is_oauth_admin(&state, &claims.email) {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::FORBIDDEN,
+            "auth.errors.oauth_client_registration_forbidden",
+        ));
+    }
+
+    let name = client_data.name.trim();
+
+    if name.is_empty() || name.len() > OAUTH_MAX_CLIENT_NAME_LENGTH {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.invalid_oauth_client_name",
+        ));
+    }
+
+    if client_data.redirect_uris.is_empty()
+        || client_data.redirect_uris.len() > OAUTH_MAX_REDIRECT_URIS
+        || !client_data
+            .redirect_uris
+            .iter()
+            .all(|redirect_uri| is_valid_redirect_uri(redirect_uri))
+    {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.invalid_oauth_redirect_uri",
+        ));
+    }
+
+    let supported_scopes: Vec<String> = OAUTH_SUPPORTED_SCOPES
+        .iter()
+        .map(|scope| scope.to_string())
+        .collect();
+
+    let scopes = match &client_data.scopes {
+        Some(scopes) => resolve_scope(Some(&scopes.join(" ")), &supported_scopes),
+        None => resolve_scope(None, &supported_scopes),
+    };
+
+    let scopes = match scopes {
+        Some(scopes) => scopes,
+        None => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::BAD_REQUEST,
+                "auth.errors.invalid_oauth_scope",
+            ))
+        }
+    };
+
+    let client_secret = client_data
+        .is_confidential
+        .then(|| generate_oauth_token(OAUTH_CLIENT_SECRET_BYTES));
+
+    let client_secret_hash = client_secret
+        .as_deref()
+        .map(|client_secret| hash_client_secret(state.config, client_secret))
+        .transpose()
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let client = OAuthClient {
+        client_id: generate_oauth_client_id(),
+        client_secret_hash,
+        name: name.to_string(),
+        redirect_uris: client_data.redirect_uris.join(" "),
+        scopes,
+    };
+
+    create_oauth_client(&state, &client, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::CREATED,
+        "auth.success.oauth_client_registered",
+        Some(format_oauth_client_response(client, client_secret)),
+    ))
+}
+
+pub async fn list_oauth_clients(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    let clients: Vec<OAuthClientResponse> = get_oauth_clients_by_owner_id(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+        .into_iter()
+        .map(|client| format_oauth_client_response(client, None))
+        .collect();
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.oauth_clients_fetched",
+        Some(clients),
+    ))
+}
+
+// Once the redirect URI is trusted, errors go back to the client instead of being shown to the user
+fn format_authorization_error(
+    redirect_uri: &str,
+    error: &str,
+    oauth_state: &Option<String>,
+) -> Result<String, StatusCode> {
+    let mut params = vec![("error", error)];
+
+    if let Some(oauth_state) = oauth_state {
+        params.push(("state", oauth_state));
+    }
+
+    format_redirect_uri(redirect_uri, &params)
+}
+
+// Signs the user in through the frontend, which sends the browser back here afterwards
+fn format_login_redirect(state: &AppState, original_uri: &str) -> Result<Response, StatusCode> {
+    let client_base_url = &state.config.client_base_url;
+
+    let login_uri = format_redirect_uri(
+        &format!("{}/login", client_base_url.trim_end_matches('/')),
+        &[("redirect", original_uri)],
+    )?;
+
+    Ok(Redirect::to(&login_uri).into_response())
+}
+
+// Asks the user to approve the client on the frontend, which posts the same parameters back to POST /authorize
+fn format_consent_redirect(
+    state: &AppState,
+    original_uri: &Uri,
+    client: &OAuthClient,
+    scope: &str,
+) -> Result<Response, StatusCode> {
+    let client_base_url = &state.config.client_base_url;
+
+    let mut consent_uri = Url::parse(&format!(
+        "{}/oauth/consent",
+        client_base_url.trim_end_matches('/')
+    ))
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    consent_uri.set_query(original_uri.query());
+    consent_uri
+        .query_pairs_mut()
+        .append_pair("client_name", &client.name)
+        .append_pair("granted_scope", scope);
+
+    Ok(Redirect::to(consent_uri.as_str()).into_response())
+}
+
+// An authorization request that passed every check, so errors from here on can go back to the client
+struct ValidAuthorizeRequest {
+    client: OAuthClient,
+    redirect_uri: String,
+    scope: String,
+    code_challenge: String,
+}
+
+enum AuthorizeRequestError {
+    // The client or redirect URI can't be trusted, so the error is shown to the user
+    Invalid(AppError),
+    // Redirect URI with the error for the client
+    Redirect(String),
+}
+
+async fn validate_authorize_request(
+    state: &AppState,
+    translations: &Translations,
+    params: &AuthorizeRequest,
+) -> Result<ValidAuthorizeRequest, AuthorizeRequestError> {
+    let client = match &params.client_id {
+        Some(client_id) => get_oauth_client_by_client_id(state, client_id)
+            .await
+            .map_err(|_| {
+                AuthorizeRequestError::Invalid(AppError::format_internal_error(translations))
+            })?,
+        None => None,
+    };
+
+    let client = match client {
+        Some(client) => client,
+        None => {
+            return Err(AuthorizeRequestError::Invalid(AppError::format_error(
+                translations,
+                StatusCode::BAD_REQUEST,
+                "auth.errors.invalid_oauth_client",
+            )))
+        }
+    };
+
+    let registered_redirect_uris = client.get_redirect_uris();
+
+    // The redirect URI may only be left out when the client registered exactly one
+    let redirect_uri = match &params.redirect_uri {
+        Some(redirect_uri) if registered_redirect_uris.contains(redirect_uri) => {
+            redirect_uri.clone()
+        }
+        None if registered_redirect_uris.len() == 1 => registered_redirect_uris[0].clone(),
+        _ => {
+            return Err(AuthorizeRequestError::Invalid(AppError::format_error(
+                translations,
+                StatusCode::BAD_REQUEST,
+                "auth.errors.invalid_oauth_redirect_uri",
+            )))
+        }
+    };
+
+    let authorization_error = |error: &str| {
+        let error_uri = format_authorization_error(&redirect_uri, error, &params.state);
+
+        match error_uri {
+            Ok(error_uri) => AuthorizeRequestError::Redirect(error_uri),
+            Err(_) => AuthorizeRequestError::Invalid(AppError::format_internal_error(translations)),
+        }
+    };
+
+    if params.response_type.as_deref() != Some("code") {
+        return Err(authorization_error("unsupported_response_type"));
+    }
+
+    // PKCE is required for every client, and only with S256
+    let code_challenge = match (&params.code_challenge, &params.code_challenge_method) {
+        (Some(code_challenge), Some(method)) if method == "S256" => code_challenge.clone(),
+        _ => return Err(authorization_error("invalid_request")),
+    };
+
+    let scope = match resolve_scope(params.scope.as_deref(), &client.get_scopes()) {
+        Some(scope) => scope,
+        None => return Err(authorization_error("invalid_scope")),
+    };
+
+    Ok(ValidAuthorizeRequest {
+        client,
+        redirect_uri,
+        scope,
+        code_challenge,
+    })
+}
+
+// Stores a single use code for the user and returns the redirect URI that hands it to the client
+async fn issue_authorization_code(
+    state: &AppState,
+    translations: &Translations,
+    request: ValidAuthorizeRequest,
+    params: &AuthorizeRequest,
+    user_id: &i32,
+) -> Result<String, AppError> {
+    let code = generate_oauth_token(OAUTH_AUTHORIZATION_CODE_BYTES);
+
+    let code_payload = serde_json::to_string(&AuthorizationCodePayload {
+        client_id: request.client.client_id,
+        user_id: *user_id,
+        redirect_uri: request.redirect_uri.clone(),
+        scope: request.scope,
+        code_challenge: request.code_challenge,
+        nonce: params.nonce.clone(),
+        issued_at: Utc::now().timestamp_millis(),
+    })
+    .map_err(|error| AppError::format_service_error(translations, error))?;
+
+    set_token(
+        state,
+        &format_authorization_code_key(&code),
+        &code_payload,
+        OAUTH_AUTHORIZATION_CODE_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(translations, error))?;
+
+    let mut redirect_params = vec![("code", code.as_str())];
+
+    if let Some(oauth_state) = &params.state {
+        redirect_params.push(("state", oauth_state));
+    }
+
+    format_redirect_uri(&request.redirect_uri, &redirect_params)
+        .map_err(|error| AppError::format_status_error(translations, error))
+}
+
+pub async fn authorize(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    claims: Option<Extension<JwtClaims>>,
+    OriginalUri(original_uri): OriginalUri,
+    Query(params): Query<AuthorizeRequest>,
+) -> Result<Response, AppError> {
+    let request = match validate_authorize_request(&state, &translations, &params).await {
+        Ok(request) => request,
+        Err(AuthorizeRequestError::Invalid(error)) => return Err(error),
+        Err(AuthorizeRequestError::Redirect(error_uri)) => {
+            return Ok(Redirect::to(&error_uri).into_response())
+        }
+    };
+
+    let claims = match claims {
+        Some(Extension(claims)) => claims,
+        None => {
+            return format_login_redirect(&state, &original_uri.to_string())
+                .map_err(|error| AppError::format_status_error(&translations, error))
+        }
+    };
+
+    let has_consent = has_oauth_consent(
+        &state,
+        &claims.id,
+        &request.client.client_id,
+        &request.scope,
+    )
+    .await
+    .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    if !has_consent {
+        return format_consent_redirect(&state, &original_uri, &request.client, &request.scope)
+            .map_err(|error| AppError::format_status_error(&translations, error));
+    }
+
+    let redirect_uri =
+        issue_authorization_code(&state, &translations, request, &params, &claims.id).await?;
+
+    Ok(Redirect::to(&redirect_uri).into_response())
+}
+
+// Records the decision of the consent page, approving remembers the consent and issues a code right away
+pub async fn authorize_consent(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(consent_data): Json<AuthorizeConsentRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let params = &consent_data.authorization;
+
+    let redirect_uri = match validate_authorize_request(&state, &translations, params).await {
+        Ok(request) if consent_data.approved => {
+            store_oauth_consent(
+                &state,
+                &claims.id,
+                &request.client.client_id,
+                &request.scope,
+            )
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+            issue_authorization_code(&state, &translations, request, params, &claims.id).await?
+        }
+        Ok(request) => {
+            format_authorization_error(&request.redirect_uri, "access_denied", &params.state)
+                .map_err(|error| AppError::format_status_error(&translations, error))?
+        }
+        Err(AuthorizeRequestError::Invalid(error)) => return Err(error),
+        Err(AuthorizeRequestError::Redirect(error_uri)) => error_uri,
+    };
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.oauth_consent_processed",
+        Some(AuthorizeConsentResponse { redirect_uri }),
+    ))
+}
+
+// Client credentials come from HTTP Basic authentication or from the form body, not both
+async fn authenticate_token_request(
+    state: &AppState,
+    headers: &HeaderMap,
+    token_data: &TokenRequest,
+) -> Result<OAuthClient, OAuthError> {
+    let basic_credentials = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|authorization| authorization.to_str().ok())
+        .and_then(parse_basic_authorization);
+
+    let (client_id, client_secret) = match (basic_credentials, &token_data.client_id) {
+        (Some(_), Some(_)) => {
+            return Err(OAuthError::invalid_request(
+                "Client credentials must be sent in one way only",
+            ))
+        }
+        (Some((client_id, client_secret)), None) => (client_id, Some(client_secret)),
+        (None, Some(client_id)) => (client_id.clone(), token_data.client_secret.clone()),
+        (None, None) => return Err(OAuthError::invalid_client()),
+    };
+
+    let client = get_oauth_client_by_client_id(state, &client_id)
+        .await
+        .map_err(|_| OAuthError::server_error())?
+        .ok_or_else(OAuthError::invalid_client)?;
+
+    if !authenticate_oauth_client(state.config, &client, client_secret.as_deref())
+        .map_err(|_| OAuthError::server_error())?
+    {
+        return Err(OAuthError::invalid_client());
+    }
+
+    Ok(client)
+}
+
+async fn issue_tokens(
+    state: &AppState,
+    client_id: &str,
+    user_id: &i32,
+    scope: &str,
+    nonce: Option<&str>,
+) -> Result<TokenResponse, OAuthError> {
+    let user = get_user_by_id(state, user_id)
+        .await
+        .map_err(|_| OAuthError::invalid_grant("The user no longer exists"))?;
+    let (id, name, email, _, _) = user.clone();
+
+    let access_token = encode_access_token(&id, &name, &email, client_id, scope)
+        .map_err(|_| OAuthError::server_error())?;
+
+    let refresh_token = generate_oauth_token(OAUTH_REFRESH_TOKEN_BYTES);
+
+    let refresh_token_payload = serde_json::to_string(&OAuthRefreshTokenPayload {
+        client_id: client_id.to_string(),
+        user_id: id,
+        scope: scope.to_string(),
+        issued_at: Utc::now().timestamp_millis(),
+    })
+    .map_err(|_| OAuthError::server_error())?;
+
+    let refresh_token_key = format_oauth_refresh_token_key(state.config, &refresh_token)
+        .map_err(|_| OAuthError::server_error())?;
+
+    set_token(
+        state,
+        &refresh_token_key,
+        &refresh_token_payload,
+        OAUTH_REFRESH_TOKEN_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|_| OAuthError::server_error())?;
+
+    let id_token = if has_scope(scope, OIDC_SCOPE) {
+        let issuer_url = get_issuer_url(state.config);
+
+        let id_token = encode_id_token(
+            &issuer_url,
+            client_id,
+            nonce,
+            format_oidc_user_claims(user, scope),
+        )
+        .map_err(|_| OAuthError::server_error())?;
+
+        Some(id_token)
+    } else {
+        None
+    };
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: OAUTH_ACCESS_TOKEN_EXPIRATION_SECONDS,
+        refresh_token,
+        scope: scope.to_string(),
+        id_token,
+    })
+}
+
+// Reads and deletes a single use token in one step, so concurrent requests can't both exchange it
+async fn take_grant<T: serde::de::DeserializeOwned>(
+    state: &AppState,
+    key: &str,
+) -> Result<Option<T>, OAuthError> {
+    take_token(state, key)
+        .await
+        .map_err(|_| OAuthError::server_error())?
+        .map(|json| serde_json::from_str(&json).map_err(|_| OAuthError::server_error()))
+        .transpose()
+}
+
+async fn ensure_grant_not_revoked(
+    state: &AppState,
+    user_id: &i32,
+    issued_at: i64,
+) -> Result<(), OAuthError> {
+    if is_oauth_grant_revoked(state, user_id, issued_at)
+        .await
+        .map_err(|_| OAuthError::server_error())?
+    {
+        return Err(OAuthError::invalid_grant("The grant has been revoked"));
+    }
+
+    Ok(())
+}
+
+async fn exchange_authorization_code(
+    state: &AppState,
+    client: &OAuthClient,
+    token_data: &TokenRequest,
+) -> Result<TokenResponse, OAuthError> {
+    let (code, code_verifier) = match (&token_data.code, &token_data.code_verifier) {
+        (Some(code), Some(code_verifier)) => (code, code_verifier),
+        _ => {
+            return Err(OAuthError::invalid_request(
+                "code and code_verifier are required",
+            ))
+        }
+    };
+
+    let code_payload: AuthorizationCodePayload =
+        take_grant(state, &format_authorization_code_key(code))
+            .await?
+            .ok_or_else(|| OAuthError::invalid_grant("Invalid or expired authorization code"))?;
+
+    if code_payload.client_id != client.client_id {
+        return Err(OAuthError::invalid_grant(
+            "The authorization code was issued to another client",
+        ));
+    }
+
+    // Required even when the client left it out of the authorization request, see RFC 6749 section 4.1.3
+    if token_data.redirect_uri.as_deref() != Some(code_payload.redirect_uri.as_str()) {
+        return Err(OAuthError::invalid_grant("redirect_uri does not match"));
+    }
+
+    if !verify_pkce_challenge(code_verifier, &code_payload.code_challenge) {
+        return Err(OAuthError::invalid_grant("Invalid code_verifier"));
+    }
+
+    ensure_grant_not_revoked(state, &code_payload.user_id, code_payload.issued_at).await?;
+
+    issue_tokens(
+        state,
+        &client.client_id,
+        &code_payload.user_id,
+        &code_payload.scope,
+        code_payload.nonce.as_deref(),
+    )
+    .await
+}
+
+async fn exchange_refresh_token(
+    state: &AppState,
+    client: &OAuthClient,
+    token_data: &TokenRequest,
+) -> Result<TokenResponse, OAuthError> {
+    let refresh_token = match &token_data.refresh_token {
+        Some(refresh_token) => refresh_token,
+        None => return Err(OAuthError::invalid_request("refresh_token is required")),
+    };
+
+    let refresh_token_key = format_oauth_refresh_token_key(state.config, refresh_token)
+        .map_err(|_| OAuthError::server_error())?;
+
+    let refresh_token_payload: OAuthRefreshTokenPayload = take_grant(state, &refresh_token_key)
+        .await?
+        .ok_or_else(|| OAuthError::invalid_grant("Invalid or expired refresh token"))?;
+
+    if refresh_token_payload.client_id != client.client_id {
+        return Err(OAuthError::invalid_grant(
+            "The refresh token was issued to another client",
+        ));
+    }
+
+    ensure_grant_not_revoked(
+        state,
+        &refresh_token_payload.user_id,
+        refresh_token_payload.issued_at,
+    )
+    .await?;
+
+    // A refresh may narrow the scope, but never widen it beyond the original grant
+    let granted_scopes: Vec<String> = refresh_token_payload
+        .scope
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+
+    let scope = resolve_scope(token_data.scope.as_deref(), &granted_scopes)
+        .ok_or_else(OAuthError::invalid_scope)?;
+
+    issue_tokens(
+        state,
+        &client.client_id,
+        &refresh_token_payload.user_id,
+        &scope,
+        None,
+    )
+    .await
+}
+
+pub async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(token_data): Form<TokenRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+    let client = authenticate_token_request(&state, &headers, &token_data).await?;
+
+    let token_response = match token_data.grant_type.as_str() {
+        "authorization_code" => exchange_authorization_code(&state, &client, &token_data).await?,
+        "refresh_token" => exchange_refresh_token(&state, &client, &token_data).await?,
+        _ => return Err(OAuthError::unsupported_grant_type()),
+    };
+
+    let mut response = (StatusCode::OK, Json(token_response)).into_response();
+
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+        .headers_mut()
+        .insert(header::PRAGMA, HeaderValue::from_static("no-cache"));
+
+    Ok(response)
+}
+
+// Returns the claims of the user an access token was issued for, see OpenID Connect Core section 5.3
+pub async fn userinfo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, OAuthError> {
+    let access_token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|authorization| authorization.to_str().ok())
+        .and_then(|authorization| authorization.strip_prefix("Bearer "))
+        .ok_or_else(OAuthError::invalid_token)?;
+
+    let claims = decode_access_token(access_token.trim(), None)
+        .map_err(|_| OAuthError::invalid_token())?
+        .claims;
+
+    if is_jwt_revoked(&state, &claims)
+        .await
+        .map_err(|_| OAuthError::server_error())?
+    {
+        return Err(OAuthError::invalid_token());
+    }
+
+    let scope = claims.scope.unwrap_or_default();
+
+    if !has_scope(&scope, OIDC_SCOPE) {
+        return Err(OAuthError::insufficient_scope());
+    }
+
+    let user = get_user_by_id(&state, &claims.id)
+        .await
+        .map_err(|_| OAuthError::invalid_token())?;
+
+    let mut response =
+        (StatusCode::OK, Json(format_oidc_user_claims(user, &scope))).into_response();
+
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+    Ok(response)
+}
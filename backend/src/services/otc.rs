@@ -8,44 +8,44 @@ use axum::{
 use http::{header, HeaderValue, StatusCode};
 
 use crate::{
-    constants::auth::BEARER_EXPIRATION_SECONDS,
     models::{
         auth::models::AuthResponse,
         general::AppState,
-        otc::models::{OtcPayload, OtcPayloadAction, OtcRequest},
+        otc::models::{OtcPayloadAction, OtcRequest, OtcResponse},
+        session::models::SessionClient,
         translations::Translations,
+        two_factor::models::TwoFactorChallengeResponse,
     },
     utils::{
         cookie::{delete_cookie, set_cookie},
         emails::send_otc_success_email,
         jwt::encode_jwt,
-        otc::format_otc_key,
-        redis::{get_token, remove_token},
+        otc::{is_valid_login_otc, take_otc_payload},
+        password_history::change_user_password,
+        recovery_codes::{approve_recovery_codes_regeneration, delete_recovery_codes},
         responses::{ApiResponse, AppError},
-        user::{confirm_user, delete_user_by_id, update_user_email, update_user_password},
+        session::{revoke_user_access, start_session},
+        two_factor::{create_two_factor_challenge, delete_two_factor, is_two_factor_enabled},
+        user::{confirm_user, delete_user_by_id, update_user_email},
     },
 };
 
 pub async fn otc_user(
     State(state): State<AppState>,
     Extension(translations): Extension<Arc<Translations>>,
+    client: SessionClient,
     Query(params): Query<OtcRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    let otc_key = format_otc_key(&params.otc);
-
-    let token_payload: Option<OtcPayload> = get_token(&state, &otc_key)
+    let token_payload = take_otc_payload(&state, &params.email, &params.otc)
         .await
-        .map_err(|_| {
-            AppError::format_error(
+        .map_err(|status| match status {
+            StatusCode::TOO_MANY_REQUESTS => AppError::format_error(
                 &translations,
-                StatusCode::UNAUTHORIZED,
-                "auth.errors.failed_to_read_token_payload",
-            )
-        })?
-        .map(|json| {
-            serde_json::from_str(&json).map_err(|_| AppError::format_internal_error(&translations))
-        })
-        .transpose()?;
+                StatusCode::TOO_MANY_REQUESTS,
+                "auth.errors.too_many_otc_attempts",
+            ),
+            _ => AppError::format_internal_error(&translations),
+        })?;
 
     let token_payload = match token_payload {
         Some(payload) => payload,
@@ -65,65 +65,139 @@ pub async fn otc_user(
 
     let mut cookies_to_set: Vec<(&str, String, Option<i32>)> = Vec::new();
     let mut cookies_to_delete: Vec<&str> = Vec::new();
-    let mut response_data: Option<AuthResponse> = None;
+    let mut response_data: Option<OtcResponse> = None;
+    let mut session_user: Option<AuthResponse> = None;
 
     match action {
         OtcPayloadAction::UpdateAccount => {
             confirm_mail_type = "update_account";
 
             if let Some(password) = &token_payload.password_hash {
-                update_user_password(&state, &user_id, &password)
+                change_user_password(&state, &user_id, &password)
                     .await
-                    .map_err(|_| AppError::format_internal_error(&translations))?;
+                    .map_err(|error| AppError::format_status_error(&translations, error))?;
             } else {
                 update_user_email(&state, &user_id, &token_payload.email)
                     .await
-                    .map_err(|_| AppError::format_internal_error(&translations))?;
-
-                let new_jwt = encode_jwt(&user_id, &token_payload.name, &token_payload.email)
-                    .map_err(|_| AppError::format_internal_error(&translations))?;
-
-                cookies_to_set.push(("Bearer", new_jwt, Some(BEARER_EXPIRATION_SECONDS)));
+                    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+                // The OTC link isn't tied to a session, the next refresh adds the session id back
+                let new_jwt = encode_jwt(
+                    state.config,
+                    &user_id,
+                    &token_payload.name,
+                    &token_payload.email,
+                    None,
+                )
+                .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+                cookies_to_set.push((
+                    "Bearer",
+                    new_jwt,
+                    Some(state.config.tokens.bearer_expiration_seconds),
+                ));
             }
 
-            response_data = Some(AuthResponse {
+            response_data = Some(OtcResponse::Account(AuthResponse {
                 id: user_id,
                 name: token_payload.name,
                 email: token_payload.email.clone(),
                 phone: token_payload.phone,
-            });
+            }));
         }
         OtcPayloadAction::DeleteAccount => {
             confirm_mail_type = "delete_account";
 
-            // TODO: Would be good to remove the refresh token from redis here with remove_token, but not sure how yet as otc is not a protected route thus the refresh token is not available in extensions
             cookies_to_delete.push("Bearer");
             cookies_to_delete.push("RefreshToken");
 
+            // The refresh sessions go too, or a refresh token would outlive the account
+            revoke_user_access(&state, &user_id)
+                .await
+                .map_err(|error| AppError::format_status_error(&translations, error))?;
+
             delete_user_by_id(&state, &user_id)
                 .await
-                .map_err(|_| AppError::format_internal_error(&translations))?;
+                .map_err(|error| AppError::format_service_error(&translations, error))?;
         }
         OtcPayloadAction::ConfirmAccount => {
             confirm_mail_type = "confirm_account";
 
             confirm_user(&state, &user_id)
                 .await
-                .map_err(|_| AppError::format_internal_error(&translations))?;
+                .map_err(|error| AppError::format_service_error(&translations, error))?;
         }
-    }
+        OtcPayloadAction::DisableTwoFactor => {
+            confirm_mail_type = "disable_two_factor";
 
-    send_otc_success_email(&translations, confirm_mail_type, &token_payload.email)
-        .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+            delete_two_factor(&state, &user_id)
+                .await
+                .map_err(|error| AppError::format_status_error(&translations, error))?;
 
-    remove_token(&state, &otc_key).await.map_err(|_| {
-        AppError::format_error(
-            &translations,
-            StatusCode::UNAUTHORIZED,
-            "auth.errors.failed_to_remove_token",
-        )
-    })?;
+            delete_recovery_codes(&state, &user_id)
+                .await
+                .map_err(|error| AppError::format_status_error(&translations, error))?;
+        }
+        OtcPayloadAction::RegenerateRecoveryCodes => {
+            confirm_mail_type = "regenerate_recovery_codes";
+
+            // Anyone with the code could call this endpoint, so the codes themselves aren't returned here
+            approve_recovery_codes_regeneration(&state, &user_id)
+                .await
+                .map_err(|error| AppError::format_status_error(&translations, error))?;
+        }
+        OtcPayloadAction::Login => {
+            confirm_mail_type = "login";
+
+            if !is_valid_login_otc(
+                state.config,
+                &params.otc,
+                &params.email,
+                params.signature.as_deref(),
+            )
+            .map_err(|error| AppError::format_status_error(&translations, error))?
+            {
+                return Err(AppError::format_error(
+                    &translations,
+                    StatusCode::UNAUTHORIZED,
+                    "auth.errors.failed_to_read_token_payload",
+                ));
+            }
+
+            // The code only replaces the password, a second factor is still required
+            if is_two_factor_enabled(&state, &user_id)
+                .await
+                .map_err(|error| AppError::format_status_error(&translations, error))?
+            {
+                let challenge_token = create_two_factor_challenge(&state, &user_id)
+                    .await
+                    .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+                response_data = Some(OtcResponse::TwoFactorChallenge(
+                    TwoFactorChallengeResponse { challenge_token },
+                ));
+            } else {
+                let user = AuthResponse {
+                    id: user_id,
+                    name: token_payload.name.clone(),
+                    email: token_payload.email.clone(),
+                    phone: token_payload.phone.clone(),
+                };
+
+                response_data = Some(OtcResponse::Account(user.clone()));
+                session_user = Some(user);
+            }
+        }
+    }
+
+    send_otc_success_email(
+        &state,
+        &translations,
+        confirm_mail_type,
+        &token_payload.email,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
 
     let api_response = ApiResponse::format_success(
         &translations,
@@ -148,5 +222,18 @@ pub async fn otc_user(
         response = delete_cookie(&translations, response, key)?;
     }
 
+    if let Some(user) = session_user {
+        response = start_session(
+            &state,
+            &translations,
+            response,
+            &client,
+            &user.id,
+            &user.name,
+            &user.email,
+        )
+        .await?;
+    }
+
     Ok(response)
 }
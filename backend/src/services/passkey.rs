@@ -0,0 +1,326 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, State},
+    response::IntoResponse,
+    Extension,
+};
+use http::{header, HeaderValue, StatusCode};
+
+use crate::{
+    constants::passkey::{
+        WEBAUTHN_CHALLENGE_EXPIRATION_SECONDS, WEBAUTHN_ES256_ALGORITHM,
+        WEBAUTHN_MAX_CREDENTIAL_ID_LENGTH, WEBAUTHN_TIMEOUT_MILLISECONDS,
+    },
+    models::{
+        auth::models::{AuthResponse, JwtClaims},
+        general::AppState,
+        passkey::models::{
+            AuthenticatorSelection, PasskeyAuthenticationOptions, PasskeyAuthenticationRequest,
+            PasskeyRegistrationOptions, PasskeyRegistrationRequest, PasskeyUserEntity,
+            PublicKeyCredentialDescriptor, PublicKeyCredentialParameters, RelyingParty,
+        },
+        session::models::SessionClient,
+        translations::Translations,
+    },
+    utils::{
+        encoding::{decode_base64url, encode_base64url},
+        passkey::{
+            create_passkey, get_passkey_by_credential_id, get_passkey_credential_ids_by_user_id,
+            update_passkey_sign_count,
+        },
+        redis::{get_token, remove_token, set_token},
+        responses::{ApiResponse, AppError},
+        session::start_session,
+        user::get_user_by_id,
+        webauthn::{
+            format_authentication_challenge_key, format_registration_challenge_key,
+            generate_webauthn_challenge, get_relying_party, parse_client_data, verify_assertion,
+            verify_client_data, verify_registration,
+        },
+    },
+};
+
+// Malformed or unverifiable passkey data is reported to the client, anything else is internal
+fn format_passkey_error(translations: &Translations, status_code: StatusCode) -> AppError {
+    if status_code == StatusCode::INTERNAL_SERVER_ERROR {
+        return AppError::format_internal_error(translations);
+    }
+
+    AppError::format_error(translations, status_code, "auth.errors.invalid_passkey")
+}
+
+pub async fn start_passkey_registration(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    let (rp_id, _) = get_relying_party(state.config)
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let challenge = generate_webauthn_challenge();
+
+    set_token(
+        &state,
+        &format_registration_challenge_key(&claims.id),
+        &challenge,
+        WEBAUTHN_CHALLENGE_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    let exclude_credentials = get_passkey_credential_ids_by_user_id(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+        .into_iter()
+        .map(|credential_id| PublicKeyCredentialDescriptor {
+            credential_type: "public-key".to_string(),
+            id: credential_id,
+        })
+        .collect();
+
+    let options = PasskeyRegistrationOptions {
+        rp: RelyingParty {
+            id: rp_id,
+            name: state.config.company_name.clone(),
+        },
+        user: PasskeyUserEntity {
+            id: encode_base64url(claims.id.to_string().as_bytes()),
+            name: claims.email,
+            display_name: claims.name,
+        },
+        challenge,
+        pub_key_cred_params: vec![PublicKeyCredentialParameters {
+            credential_type: "public-key".to_string(),
+            alg: WEBAUTHN_ES256_ALGORITHM,
+        }],
+        timeout: WEBAUTHN_TIMEOUT_MILLISECONDS,
+        attestation: "none".to_string(),
+        exclude_credentials,
+        authenticator_selection: AuthenticatorSelection {
+            resident_key: "required".to_string(),
+            user_verification: "preferred".to_string(),
+        },
+    };
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.passkey_registration_started",
+        Some(options),
+    ))
+}
+
+pub async fn finish_passkey_registration(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(credential): Json<PasskeyRegistrationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let (rp_id, origin) = get_relying_party(state.config)
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let challenge_key = format_registration_challenge_key(&claims.id);
+
+    let challenge = match get_token(&state, &challenge_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?
+    {
+        Some(challenge) => challenge,
+        None => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::UNAUTHORIZED,
+                "auth.errors.invalid_passkey_challenge",
+            ))
+        }
+    };
+
+    // Challenges are single use, also when the ceremony fails
+    remove_token(&state, &challenge_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    let client_data_json = decode_base64url(&credential.response.client_data_json)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+    let client_data = parse_client_data(&client_data_json)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+
+    verify_client_data(&client_data, "webauthn.create", &challenge, &origin)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+
+    let attestation_object = decode_base64url(&credential.response.attestation_object)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+
+    let registered_passkey = verify_registration(&attestation_object, &rp_id)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+
+    let credential_id = encode_base64url(&registered_passkey.credential_id);
+
+    if credential_id != credential.id || credential_id.len() > WEBAUTHN_MAX_CREDENTIAL_ID_LENGTH {
+        return Err(format_passkey_error(&translations, StatusCode::BAD_REQUEST));
+    }
+
+    create_passkey(
+        &state,
+        &claims.id,
+        &credential_id,
+        &encode_base64url(&registered_passkey.public_key),
+        registered_passkey.sign_count,
+    )
+    .await
+    .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    Ok(ApiResponse::<()>::format_success(
+        &translations,
+        StatusCode::CREATED,
+        "auth.success.passkey_registered",
+        None,
+    ))
+}
+
+pub async fn start_passkey_login(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+) -> Result<impl IntoResponse, AppError> {
+    let (rp_id, _) = get_relying_party(state.config)
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let challenge = generate_webauthn_challenge();
+
+    set_token(
+        &state,
+        &format_authentication_challenge_key(&challenge),
+        &challenge,
+        WEBAUTHN_CHALLENGE_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    // No allowCredentials, so the authenticator offers its discoverable passkeys for this RP
+    let options = PasskeyAuthenticationOptions {
+        challenge,
+        rp_id,
+        timeout: WEBAUTHN_TIMEOUT_MILLISECONDS,
+        user_verification: "preferred".to_string(),
+    };
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.passkey_login_started",
+        Some(options),
+    ))
+}
+
+pub async fn finish_passkey_login(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    client: SessionClient,
+    Json(credential): Json<PasskeyAuthenticationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let (rp_id, origin) = get_relying_party(state.config)
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let client_data_json = decode_base64url(&credential.response.client_data_json)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+    let client_data = parse_client_data(&client_data_json)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+
+    let challenge_key = format_authentication_challenge_key(&client_data.challenge);
+
+    let challenge = match get_token(&state, &challenge_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?
+    {
+        Some(challenge) => challenge,
+        None => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::UNAUTHORIZED,
+                "auth.errors.invalid_passkey_challenge",
+            ))
+        }
+    };
+
+    remove_token(&state, &challenge_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    verify_client_data(&client_data, "webauthn.get", &challenge, &origin)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+
+    let (user_id, public_key, stored_sign_count) =
+        get_passkey_by_credential_id(&state, &credential.id)
+            .await
+            .map_err(|status| format_passkey_error(&translations, status))?;
+
+    if let Some(user_handle) = &credential.response.user_handle {
+        let user_handle = decode_base64url(user_handle)
+            .map_err(|status| format_passkey_error(&translations, status))?;
+
+        if user_handle != user_id.to_string().as_bytes() {
+            return Err(format_passkey_error(
+                &translations,
+                StatusCode::UNAUTHORIZED,
+            ));
+        }
+    }
+
+    let authenticator_data = decode_base64url(&credential.response.authenticator_data)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+    let signature = decode_base64url(&credential.response.signature)
+        .map_err(|status| format_passkey_error(&translations, status))?;
+    let public_key = decode_base64url(&public_key)
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let sign_count = verify_assertion(
+        &authenticator_data,
+        &client_data_json,
+        &signature,
+        &public_key,
+        &rp_id,
+        stored_sign_count,
+    )
+    .map_err(|status| format_passkey_error(&translations, status))?;
+
+    update_passkey_sign_count(&state, &credential.id, sign_count)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let (id, name, email, phone, is_confirmed) = get_user_by_id(&state, &user_id)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    if !is_confirmed {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::UNAUTHORIZED,
+            "auth.errors.account_not_confirmed",
+        ));
+    }
+
+    let response_body = ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.user_logged_in",
+        Some(AuthResponse {
+            id,
+            name: name.clone(),
+            email: email.clone(),
+            phone,
+        }),
+    );
+
+    let mut response = response_body.into_response();
+
+    response = start_session(&state, &translations, response, &client, &id, &name, &email).await?;
+
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("application/json")
+            .map_err(|_| AppError::format_internal_error(&translations))?,
+    );
+
+    Ok(response)
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension,
+};
+use http::{header, HeaderValue, StatusCode};
+
+use crate::{
+    models::{
+        auth::models::JwtClaims,
+        general::AppState,
+        session::models::{SessionPath, SessionResponse},
+        translations::Translations,
+    },
+    utils::{
+        cookie::delete_cookie,
+        responses::{ApiResponse, AppError},
+        session::{get_session, get_user_sessions, revoke_session},
+    },
+};
+
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    let sessions: Vec<SessionResponse> = get_user_sessions(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+        .into_iter()
+        .map(|session| SessionResponse {
+            is_current: claims.sid.as_deref() == Some(session.id.as_str()),
+            id: session.id,
+            created_at: session.created_at,
+            last_refreshed_at: session.last_refreshed_at,
+            user_agent: session.user_agent,
+            ip: session.ip,
+        })
+        .collect();
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.sessions_fetched",
+        Some(sessions),
+    ))
+}
+
+pub async fn revoke_session_by_id(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(path): Path<SessionPath>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = get_session(&state, &path.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    // Sessions of other users are reported as missing, so their ids can't be probed
+    let session = match session {
+        Some(session) if session.user_id == claims.id => session,
+        _ => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::NOT_FOUND,
+                "auth.errors.session_not_found",
+            ))
+        }
+    };
+
+    revoke_session(&state, &session)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let response_body = ApiResponse::<()>::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.session_revoked",
+        None,
+    );
+
+    let mut response = response_body.into_response();
+
+    if claims.sid.as_deref() == Some(session.id.as_str()) {
+        response = delete_cookie(&translations, response, "Bearer")?;
+        response = delete_cookie(&translations, response, "RefreshToken")?;
+
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_str("application/json")
+                .map_err(|_| AppError::format_internal_error(&translations))?,
+        );
+    }
+
+    Ok(response)
+}
+
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    let sessions = get_user_sessions(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    for session in sessions
+        .iter()
+        .filter(|session| claims.sid.as_deref() != Some(session.id.as_str()))
+    {
+        revoke_session(&state, session)
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+    }
+
+    Ok(ApiResponse::<()>::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.other_sessions_revoked",
+        None,
+    ))
+}
@@ -0,0 +1,323 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Redirect, Response},
+    Extension, Json,
+};
+use http::{HeaderMap, StatusCode};
+
+use crate::{
+    constants::social::{
+        SOCIAL_LOGIN_CODE_VERIFIER_BYTES, SOCIAL_LOGIN_STATE_BYTES, SOCIAL_LOGIN_STATE_COOKIE,
+        SOCIAL_LOGIN_STATE_EXPIRATION_SECONDS,
+    },
+    models::{
+        general::AppState,
+        session::models::SessionClient,
+        social::models::{
+            SocialCallbackRequest, SocialLinkPayload, SocialLinkRequest, SocialLoginPayload,
+            SocialProvider, SocialProviderPath, SocialProvidersResponse, SocialUser,
+        },
+        translations::Translations,
+    },
+    utils::{
+        auth::verify_password,
+        cookie::{delete_cookie, get_header_cookie, set_cookie},
+        http::get_http_client,
+        oauth::{create_pkce_challenge, format_redirect_uri, generate_oauth_token},
+        redis::{set_token, take_token},
+        responses::{ApiResponse, AppError},
+        session::start_session,
+        social::{
+            create_linked_identity, exchange_social_authorization_code, fetch_social_profile,
+            format_social_authorization_url, format_social_callback_url, format_social_link_key,
+            format_social_login_state_key, get_social_provider, get_social_providers,
+            resolve_social_user,
+        },
+        two_factor::{create_two_factor_challenge, is_two_factor_enabled},
+        user::{get_user_by_email, get_user_by_id},
+    },
+};
+
+fn get_provider(
+    translations: &Translations,
+    provider_name: &str,
+) -> Result<&'static SocialProvider, AppError> {
+    get_social_provider(provider_name).ok_or_else(|| {
+        AppError::format_error(
+            translations,
+            StatusCode::NOT_FOUND,
+            "auth.errors.social_provider_not_found",
+        )
+    })
+}
+
+pub async fn list_social_providers(
+    Extension(translations): Extension<Arc<Translations>>,
+) -> Result<impl IntoResponse, AppError> {
+    let providers = get_social_providers()
+        .iter()
+        .map(|provider| provider.name.clone())
+        .collect();
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.social_providers_fetched",
+        Some(SocialProvidersResponse { providers }),
+    ))
+}
+
+// Sends the user to the provider, with a state to tie the callback to this browser and PKCE to tie the code to us
+pub async fn start_social_login(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Path(path): Path<SocialProviderPath>,
+) -> Result<Response, AppError> {
+    let provider = get_provider(&translations, &path.provider)?;
+
+    let login_state = generate_oauth_token(SOCIAL_LOGIN_STATE_BYTES);
+    let code_verifier = generate_oauth_token(SOCIAL_LOGIN_CODE_VERIFIER_BYTES);
+
+    let login_payload = serde_json::to_string(&SocialLoginPayload {
+        provider: provider.name.clone(),
+        code_verifier: code_verifier.clone(),
+    })
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    set_token(
+        &state,
+        &format_social_login_state_key(&login_state),
+        &login_payload,
+        SOCIAL_LOGIN_STATE_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    let callback_url = format_social_callback_url(state.config, provider);
+
+    let authorization_url = format_social_authorization_url(
+        provider,
+        &callback_url,
+        &login_state,
+        &create_pkce_challenge(&code_verifier),
+    )
+    .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    set_cookie(
+        &translations,
+        Redirect::to(&authorization_url).into_response(),
+        SOCIAL_LOGIN_STATE_COOKIE,
+        &login_state,
+        Some(SOCIAL_LOGIN_STATE_EXPIRATION_SECONDS),
+    )
+}
+
+// Links a provider identity to the existing account with its email, the password proves the account is theirs.
+// The token is single use, so a wrong password needs a new login through the provider.
+pub async fn link_social_identity(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Json(link_data): Json<SocialLinkRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let invalid_link_error = || {
+        AppError::format_error(
+            &translations,
+            StatusCode::UNAUTHORIZED,
+            "auth.errors.invalid_social_link",
+        )
+    };
+
+    let link_key = format_social_link_key(state.config, &link_data.link_token)
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let link_payload: SocialLinkPayload = take_token(&state, &link_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|error| AppError::format_service_error(&translations, error))?
+        .ok_or_else(invalid_link_error)?;
+
+    let (_, _, email, _, _) = get_user_by_id(&state, &link_payload.user_id)
+        .await
+        .map_err(|_| invalid_link_error())?;
+
+    let (_, _, _, password_hash, _, _) = get_user_by_email(&state, &email)
+        .await
+        .map_err(|_| invalid_link_error())?;
+
+    if !verify_password(&link_data.password, &password_hash)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        return Err(invalid_link_error());
+    }
+
+    create_linked_identity(
+        &state,
+        &link_payload.user_id,
+        &link_payload.provider,
+        &link_payload.subject,
+    )
+    .await
+    .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    Ok(ApiResponse::<()>::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.social_identity_linked",
+        None,
+    ))
+}
+
+// Reads and deletes the state in one step, so a callback can't be replayed. The state has to match the
+// cookie set when the login started, otherwise an attacker could finish their own login in the victim's browser
+async fn take_social_login_payload(
+    state: &AppState,
+    translations: &Translations,
+    headers: &HeaderMap,
+    login_state: Option<&str>,
+) -> Result<SocialLoginPayload, AppError> {
+    let invalid_state_error = || {
+        AppError::format_error(
+            translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.invalid_social_login_state",
+        )
+    };
+
+    let login_state = login_state.ok_or_else(invalid_state_error)?;
+    if get_header_cookie(headers, SOCIAL_LOGIN_STATE_COOKIE).as_deref() != Some(login_state) {
+        return Err(invalid_state_error());
+    }
+
+    let login_payload = take_token(state, &format_social_login_state_key(login_state))
+        .await
+        .map_err(|error| AppError::format_service_error(translations, error))?
+        .ok_or_else(invalid_state_error)?;
+
+    serde_json::from_str(&login_payload)
+        .map_err(|error| AppError::format_service_error(translations, error))
+}
+
+pub async fn finish_social_login(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    client: SessionClient,
+    Path(path): Path<SocialProviderPath>,
+    headers: HeaderMap,
+    Query(params): Query<SocialCallbackRequest>,
+) -> Result<Response, AppError> {
+    let provider = get_provider(&translations, &path.provider)?;
+
+    let login_payload =
+        take_social_login_payload(&state, &translations, &headers, params.state.as_deref()).await?;
+
+    if login_payload.provider != provider.name {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.invalid_social_login_state",
+        ));
+    }
+
+    let social_login_failed_error = || {
+        AppError::format_error(
+            &translations,
+            StatusCode::UNAUTHORIZED,
+            "auth.errors.social_login_failed",
+        )
+    };
+
+    // The user denied access or the provider failed, in both cases there is no code
+    let code = match (&params.code, &params.error) {
+        (Some(code), None) => code,
+        _ => return Err(social_login_failed_error()),
+    };
+
+    let callback_url = format_social_callback_url(state.config, provider);
+
+    let access_token = exchange_social_authorization_code(
+        get_http_client(),
+        provider,
+        code,
+        &login_payload.code_verifier,
+        &callback_url,
+    )
+    .await
+    .map_err(|_| social_login_failed_error())?;
+
+    let profile = fetch_social_profile(get_http_client(), provider, &access_token)
+        .await
+        .map_err(|_| social_login_failed_error())?;
+
+    let social_user = resolve_social_user(&state, &provider.name, profile)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let client_base_url = &state.config.client_base_url;
+
+    let user_id = match social_user {
+        SocialUser::Linked(user_id) => user_id,
+        // The frontend asks for the password of the account and sends it with the token to POST /social/link
+        SocialUser::PendingLink(link_token) => {
+            let link_url = format_redirect_uri(
+                &format!("{}/login", client_base_url.trim_end_matches('/')),
+                &[
+                    ("socialLinkToken", &link_token),
+                    ("provider", &provider.name),
+                ],
+            )
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+            return delete_cookie(
+                &translations,
+                Redirect::to(&link_url).into_response(),
+                SOCIAL_LOGIN_STATE_COOKIE,
+            );
+        }
+        SocialUser::EmailNotVerified => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::FORBIDDEN,
+                "auth.errors.social_email_not_verified",
+            ))
+        }
+    };
+
+    // The second factor is still required, the frontend completes it with the challenge token
+    if is_two_factor_enabled(&state, &user_id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        let challenge_token = create_two_factor_challenge(&state, &user_id)
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+        let two_factor_url = format_redirect_uri(
+            &format!("{}/login", client_base_url.trim_end_matches('/')),
+            &[("challengeToken", &challenge_token)],
+        )
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+        return delete_cookie(
+            &translations,
+            Redirect::to(&two_factor_url).into_response(),
+            SOCIAL_LOGIN_STATE_COOKIE,
+        );
+    }
+
+    let (id, name, email, _, _) = get_user_by_id(&state, &user_id)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    let response = delete_cookie(
+        &translations,
+        Redirect::to(client_base_url).into_response(),
+        SOCIAL_LOGIN_STATE_COOKIE,
+    )?;
+
+    start_session(&state, &translations, response, &client, &id, &name, &email).await
+}
@@ -0,0 +1,477 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, State},
+    response::IntoResponse,
+    Extension,
+};
+use chrono::Utc;
+use http::{header, HeaderValue, StatusCode};
+
+use crate::{
+    constants::two_factor::{
+        TOTP_ALLOWED_DRIFT_STEPS, TOTP_PERIOD_SECONDS, TWO_FACTOR_CHALLENGE_EXPIRATION_SECONDS,
+        TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS,
+    },
+    models::{
+        auth::models::{AuthResponse, JwtClaims},
+        general::AppState,
+        otc::models::{OtcPayload, OtcPayloadAction},
+        session::models::SessionClient,
+        translations::Translations,
+        two_factor::models::{
+            RecoveryCodesResponse, RecoveryCodesStatusResponse, TwoFactorChallengePayload,
+            TwoFactorCodeRequest, TwoFactorLoginRequest, TwoFactorSetupResponse,
+        },
+    },
+    utils::{
+        emails::send_otc_email,
+        otc::store_otc,
+        recovery_codes::{
+            count_recovery_codes, replace_recovery_codes,
+            take_recovery_codes_regeneration_approval, use_recovery_code,
+        },
+        redis::{
+            get_token, increment_counter, remove_token, set_token, set_token_if_absent, take_token,
+        },
+        responses::{ApiResponse, AppError},
+        session::start_session,
+        totp::{format_otpauth_uri, generate_totp_secret, verify_totp_code},
+        two_factor::{
+            enable_two_factor, format_totp_last_step_key, format_totp_used_step_key,
+            format_two_factor_challenge_attempts_key, format_two_factor_challenge_key,
+            get_two_factor_by_user_id, is_two_factor_enabled, set_two_factor_secret,
+        },
+        user::get_user_by_id,
+    },
+};
+
+pub async fn setup_two_factor(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    let two_factor = get_two_factor_by_user_id(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    if let Some((_, true)) = two_factor {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::CONFLICT,
+            "auth.errors.two_factor_already_enabled",
+        ));
+    }
+
+    let secret = generate_totp_secret();
+
+    set_two_factor_secret(&state, &claims.id, &secret)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let otpauth_uri = format_otpauth_uri(state.config, &secret, &claims.email);
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.two_factor_setup_started",
+        Some(TwoFactorSetupResponse {
+            secret,
+            otpauth_uri,
+        }),
+    ))
+}
+
+pub async fn confirm_two_factor(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(code_data): Json<TwoFactorCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let two_factor = get_two_factor_by_user_id(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    let secret = match two_factor {
+        Some((_, true)) => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::CONFLICT,
+                "auth.errors.two_factor_already_enabled",
+            ))
+        }
+        Some((secret, false)) => secret,
+        None => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::BAD_REQUEST,
+                "auth.errors.two_factor_not_set_up",
+            ))
+        }
+    };
+
+    let now = Utc::now().timestamp() as u64;
+
+    if verify_totp_code(&secret, &code_data.code, now).is_none() {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.invalid_two_factor_code",
+        ));
+    }
+
+    enable_two_factor(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    // The first batch of recovery codes is handed out right away, so the user can store them
+    let codes = replace_recovery_codes(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.two_factor_enabled",
+        Some(RecoveryCodesResponse { codes }),
+    ))
+}
+
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    let two_factor = get_two_factor_by_user_id(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    if two_factor.is_none() {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.two_factor_not_set_up",
+        ));
+    }
+
+    let otc_payload = OtcPayload {
+        user_id: claims.id,
+        action: OtcPayloadAction::DisableTwoFactor,
+        name: claims.name,
+        email: claims.email.clone(),
+        password_hash: None,
+        phone: None,
+    };
+
+    let otc = store_otc(&state, &claims.email, &otc_payload)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    send_otc_email(
+        &state,
+        &translations,
+        "disable_two_factor",
+        &otc,
+        &claims.email,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    Ok(ApiResponse::<()>::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.two_factor_disable_requested",
+        None,
+    ))
+}
+
+pub async fn get_recovery_codes_status(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    if !is_two_factor_enabled(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.two_factor_not_set_up",
+        ));
+    }
+
+    let remaining = count_recovery_codes(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.recovery_codes_fetched",
+        Some(RecoveryCodesStatusResponse { remaining }),
+    ))
+}
+
+pub async fn request_recovery_codes_regeneration(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    if !is_two_factor_enabled(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.two_factor_not_set_up",
+        ));
+    }
+
+    let otc_payload = OtcPayload {
+        user_id: claims.id,
+        action: OtcPayloadAction::RegenerateRecoveryCodes,
+        name: claims.name,
+        email: claims.email.clone(),
+        password_hash: None,
+        phone: None,
+    };
+
+    let otc = store_otc(&state, &claims.email, &otc_payload)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    send_otc_email(
+        &state,
+        &translations,
+        "regenerate_recovery_codes",
+        &otc,
+        &claims.email,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    Ok(ApiResponse::<()>::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.recovery_codes_regeneration_requested",
+        None,
+    ))
+}
+
+// Hands out new codes once the emailed code approved them, on the session of the user only
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, AppError> {
+    if !is_two_factor_enabled(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.two_factor_not_set_up",
+        ));
+    }
+
+    if !take_recovery_codes_regeneration_approval(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::FORBIDDEN,
+            "auth.errors.recovery_codes_regeneration_not_approved",
+        ));
+    }
+
+    let codes = replace_recovery_codes(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.recovery_codes_regenerated",
+        Some(RecoveryCodesResponse { codes }),
+    ))
+}
+
+pub async fn verify_two_factor_login(
+    State(state): State<AppState>,
+    Extension(translations): Extension<Arc<Translations>>,
+    client: SessionClient,
+    Json(login_data): Json<TwoFactorLoginRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let challenge_key = format_two_factor_challenge_key(&login_data.challenge_token);
+
+    let challenge_payload: Option<TwoFactorChallengePayload> = get_token(&state, &challenge_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?
+        .map(|json| {
+            serde_json::from_str(&json)
+                .map_err(|error| AppError::format_service_error(&translations, error))
+        })
+        .transpose()?;
+
+    let challenge_payload = match challenge_payload {
+        Some(payload) => payload,
+        None => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::UNAUTHORIZED,
+                "auth.errors.invalid_two_factor_challenge",
+            ))
+        }
+    };
+
+    let user_id = challenge_payload.user_id;
+
+    // Counted before the check, so parallel guesses can't all get in under the limit
+    let attempts_key = format_two_factor_challenge_attempts_key(&login_data.challenge_token);
+
+    let attempts = increment_counter(
+        &state,
+        &attempts_key,
+        TWO_FACTOR_CHALLENGE_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    if attempts > i64::from(TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS) {
+        remove_token(&state, &challenge_key)
+            .await
+            .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::UNAUTHORIZED,
+            "auth.errors.invalid_two_factor_challenge",
+        ));
+    }
+
+    let secret = match get_two_factor_by_user_id(&state, &user_id)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?
+    {
+        Some((secret, true)) => secret,
+        _ => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::UNAUTHORIZED,
+                "auth.errors.invalid_two_factor_challenge",
+            ))
+        }
+    };
+
+    let is_second_factor_valid = match (&login_data.recovery_code, &login_data.code) {
+        (Some(recovery_code), _) => use_recovery_code(&state, &user_id, recovery_code)
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?,
+        (None, Some(code)) => {
+            let last_step_key = format_totp_last_step_key(&user_id);
+
+            let last_used_step: Option<u64> = get_token(&state, &last_step_key)
+                .await
+                .map_err(|error| AppError::format_service_error(&translations, error))?
+                .and_then(|step| step.parse().ok());
+
+            let now = Utc::now().timestamp() as u64;
+
+            // A code that was already accepted once can't be replayed within its validity window
+            let matched_step = verify_totp_code(&secret, code, now)
+                .filter(|step| last_used_step.is_none_or(|last_step| *step > last_step));
+
+            let last_step_expiration_seconds =
+                ((2 * TOTP_ALLOWED_DRIFT_STEPS + 1) * TOTP_PERIOD_SECONDS) as i32;
+
+            let is_step_claimed = match matched_step {
+                Some(step) => set_token_if_absent(
+                    &state,
+                    &format_totp_used_step_key(&user_id, step),
+                    "used",
+                    last_step_expiration_seconds,
+                )
+                .await
+                .map_err(|error| AppError::format_service_error(&translations, error))?,
+                None => false,
+            };
+
+            if let (Some(step), true) = (matched_step, is_step_claimed) {
+                set_token(
+                    &state,
+                    &last_step_key,
+                    &step.to_string(),
+                    last_step_expiration_seconds,
+                )
+                .await
+                .map_err(|error| AppError::format_service_error(&translations, error))?;
+            }
+
+            is_step_claimed
+        }
+        (None, None) => false,
+    };
+
+    if !is_second_factor_valid {
+        if attempts == i64::from(TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS) {
+            remove_token(&state, &challenge_key)
+                .await
+                .map_err(|error| AppError::format_service_error(&translations, error))?;
+        }
+
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::UNAUTHORIZED,
+            "auth.errors.invalid_two_factor_code",
+        ));
+    }
+
+    // Taking the challenge is what completes it, a second request with another valid factor gets nothing
+    if take_token(&state, &challenge_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?
+        .is_none()
+    {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::UNAUTHORIZED,
+            "auth.errors.invalid_two_factor_challenge",
+        ));
+    }
+
+    remove_token(&state, &attempts_key)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    let (id, name, email, phone, _) = get_user_by_id(&state, &user_id)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    let response_body = ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.user_logged_in",
+        Some(AuthResponse {
+            id,
+            name: name.clone(),
+            email: email.clone(),
+            phone,
+        }),
+    );
+
+    let mut response = response_body.into_response();
+
+    response = start_session(&state, &translations, response, &client, &id, &name, &email).await?;
+
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("application/json")
+            .map_err(|_| AppError::format_internal_error(&translations))?,
+    );
+
+    Ok(response)
+}
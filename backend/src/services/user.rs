@@ -8,7 +8,7 @@ use axum::{
 use http::StatusCode;
 
 use crate::{
-    constants::{otc::OTC_EXPIRATION_SECONDS, user::PASSWORD_RESET_TOKEN_EXPIRATION_SECONDS},
+    constants::user::PASSWORD_RESET_TOKEN_EXPIRATION_SECONDS,
     models::{
         auth::models::{AuthResponse, JwtClaims, ResetPasswordTokenUser},
         general::AppState,
@@ -18,13 +18,18 @@ use crate::{
     },
     utils::{
         auth::hash_password,
+        breached_password::is_breached_password,
         emails::{send_otc_email, send_otc_success_email, send_password_reset_email},
-        otc::{create_otc, format_otc_key},
-        redis::{get_token, remove_token, set_token},
+        errors::ServiceError,
+        otc::store_otc,
+        password_history::{change_user_password, is_password_reused},
+        password_policy::get_password_policy,
+        redis::{set_token, take_token},
         responses::{ApiResponse, AppError},
+        session::revoke_user_access,
         user::{
-            create_user, format_reset_token_key, get_user_by_email, get_user_by_id,
-            update_non_sensitive_user_fields, update_user_password,
+            create_user, format_reset_token_key, generate_reset_token, get_user_by_email,
+            get_user_by_id, update_non_sensitive_user_fields,
         },
         validation::{
             validate_password_reset_user_data, validate_register_user_data,
@@ -38,10 +43,9 @@ pub async fn get_user(
     Extension(translations): Extension<Arc<Translations>>,
     Extension(claims): Extension<JwtClaims>,
 ) -> Result<impl IntoResponse, AppError> {
-    let user_data = match get_user_by_id(&state, &claims.id).await {
-        Ok(user) => user,
-        Err(_) => return Err(AppError::format_internal_error(&translations)),
-    };
+    let user_data = get_user_by_id(&state, &claims.id)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
     let response = AuthResponse {
         id: user_data.0,
@@ -74,17 +78,27 @@ pub async fn register_user(
         None => (),
     };
 
-    let existing_user = get_user_by_email(&state, &user_data.email).await;
-
-    if existing_user.is_ok() {
+    if is_breached_password(state.config, &user_data.password).await {
         return Err(AppError::format_error(
             &translations,
-            StatusCode::CONFLICT,
-            "auth.errors.email_already_exists",
+            StatusCode::BAD_REQUEST,
+            "auth.errors.breached_password",
         ));
     }
 
-    let create_user_result = match create_user(
+    match get_user_by_email(&state, &user_data.email).await {
+        Ok(_) => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::CONFLICT,
+                "auth.errors.email_already_exists",
+            ))
+        }
+        Err(ServiceError::NotFound) => (),
+        Err(error) => return Err(AppError::format_service_error(&translations, error)),
+    };
+
+    let created_user_id = match create_user(
         &state,
         &user_data.name,
         &user_data.email,
@@ -92,20 +106,11 @@ pub async fn register_user(
     )
     .await
     {
-        Ok(user) => user,
-        Err(_) => return Err(AppError::format_internal_error(&translations)),
-    };
-
-    let created_user_id: i32 = match create_user_result.last_insert_id().try_into() {
         Ok(id) => id,
-        Err(_) => return Err(AppError::format_internal_error(&translations)),
+        Err(error) => return Err(AppError::format_service_error(&translations, error)),
     };
 
-    let otc = create_otc();
-    let otc_key = format_otc_key(&otc);
-
     let otc_payload = OtcPayload {
-        otc: otc.to_string(),
         user_id: created_user_id,
         action: OtcPayloadAction::ConfirmAccount,
         name: user_data.name.clone(),
@@ -114,16 +119,19 @@ pub async fn register_user(
         phone: None,
     };
 
-    let otc_payload = serde_json::to_string(&otc_payload)
-        .map_err(|_| AppError::format_internal_error(&translations))?;
-
-    set_token(&state, &otc_key, &otc_payload, OTC_EXPIRATION_SECONDS)
+    let otc = store_otc(&state, &user_data.email, &otc_payload)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
 
-    send_otc_email(&translations, "confirm_account", &otc, &user_data.email)
-        .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+    send_otc_email(
+        &state,
+        &translations,
+        "confirm_account",
+        &otc,
+        &user_data.email,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
 
     let response = AuthResponse {
         id: created_user_id,
@@ -165,22 +173,43 @@ pub async fn update_user(
         None => (),
     };
 
-    let needs_otc = user_data.email_confirm.is_some()
-        || user_data.password.is_some() && user_data.password_confirm.is_some();
+    if let Some(password) = &user_data.password {
+        if is_breached_password(state.config, password).await {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::BAD_REQUEST,
+                "auth.errors.breached_password",
+            ));
+        }
+
+        // Checked here, the code only carries the new hash so the plaintext is gone by then
+        let is_reused = is_password_reused(&state, &user_data.id, password)
+            .await
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+        if is_reused {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::BAD_REQUEST,
+                "auth.errors.password_reused",
+            ));
+        }
+    }
+
+    // Validation already made sure a password comes with its confirmation
+    let needs_otc = user_data.email_confirm.is_some() || user_data.password.is_some();
 
     if needs_otc {
         let password_hash = match user_data.password {
             Some(password) => Some(
                 hash_password(&password)
-                    .map_err(|_| AppError::format_internal_error(&translations))?,
+                    .await
+                    .map_err(|error| AppError::format_status_error(&translations, error))?,
             ),
             None => None,
         };
 
-        let otc = create_otc();
-        let otc_key = format_otc_key(&otc);
         let otc_payload = OtcPayload {
-            otc: otc.to_string(),
             user_id: user_data.id,
             action: OtcPayloadAction::UpdateAccount,
             name: user_data.name,
@@ -189,16 +218,14 @@ pub async fn update_user(
             password_hash,
         };
 
-        let otc_payload = serde_json::to_string(&otc_payload)
-            .map_err(|_| AppError::format_internal_error(&translations))?;
-
-        set_token(&state, &otc_key, &otc_payload, OTC_EXPIRATION_SECONDS)
+        // The code goes to the current email, so it is scoped to that one and not the new email
+        let otc = store_otc(&state, &claims.email, &otc_payload)
             .await
-            .map_err(|_| AppError::format_internal_error(&translations))?;
+            .map_err(|error| AppError::format_status_error(&translations, error))?;
 
-        send_otc_email(&translations, "update_account", &otc, &claims.email)
+        send_otc_email(&state, &translations, "update_account", &otc, &claims.email)
             .await
-            .map_err(|_| AppError::format_internal_error(&translations))?;
+            .map_err(|error| AppError::format_service_error(&translations, error))?;
     } else {
         update_non_sensitive_user_fields(
             &state,
@@ -207,7 +234,7 @@ pub async fn update_user(
             user_data.phone.as_deref(),
         )
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
     }
 
     let success_message = if needs_otc {
@@ -229,11 +256,7 @@ pub async fn delete_user(
     Extension(translations): Extension<Arc<Translations>>,
     Extension(claims): Extension<JwtClaims>,
 ) -> Result<impl IntoResponse, AppError> {
-    let otc = create_otc();
-    let otc_key = format_otc_key(&otc);
-
     let otc_payload = OtcPayload {
-        otc: otc.to_string(),
         user_id: claims.id,
         action: OtcPayloadAction::DeleteAccount,
         name: claims.name,
@@ -242,16 +265,13 @@ pub async fn delete_user(
         phone: None,
     };
 
-    let otc_payload_json = serde_json::to_string(&otc_payload)
-        .map_err(|_| AppError::format_internal_error(&translations))?;
-
-    set_token(&state, &otc_key, &otc_payload_json, OTC_EXPIRATION_SECONDS)
+    let otc = store_otc(&state, &claims.email, &otc_payload)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
 
-    send_otc_email(&translations, "delete_account", &otc, &claims.email)
+    send_otc_email(&state, &translations, "delete_account", &otc, &claims.email)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
     Ok(ApiResponse::<()>::format_success(
         &translations,
@@ -279,8 +299,9 @@ pub async fn request_password_reset_token(
         }
     };
 
-    let reset_token = create_otc();
-    let reset_token_key = format_reset_token_key(&reset_token);
+    let reset_token = generate_reset_token();
+    let reset_token_key = format_reset_token_key(state.config, &reset_token)
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
     set_token(
         &state,
@@ -289,11 +310,11 @@ pub async fn request_password_reset_token(
         PASSWORD_RESET_TOKEN_EXPIRATION_SECONDS,
     )
     .await
-    .map_err(|_| AppError::format_internal_error(&translations))?;
+    .map_err(|error| AppError::format_service_error(&translations, error))?;
 
-    send_password_reset_email(&translations, &reset_token, &user_data.email)
+    send_password_reset_email(&state, &translations, &reset_token, &user_data.email)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
     Ok(ApiResponse::<()>::format_success(
         &translations,
@@ -309,20 +330,11 @@ pub async fn reset_password_with_token(
     Query(params): Query<PasswordResetToken>,
     Json(user_data): Json<PasswordResetUser>,
 ) -> Result<impl IntoResponse, AppError> {
-    match validate_password_reset_user_data(&user_data) {
-        Some(validation_error) => {
-            return Err(AppError::format_error(
-                &translations,
-                StatusCode::BAD_REQUEST,
-                validation_error,
-            ))
-        }
-        None => (),
-    };
-
-    let reset_token_key = format_reset_token_key(&params.token);
+    let reset_token_key = format_reset_token_key(state.config, &params.token)
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
-    let token_payload: Option<i32> = get_token(&state, &reset_token_key)
+    // Taken before anything else, so the same link can't reset the password twice at once
+    let token_payload: Option<i32> = take_token(&state, &reset_token_key)
         .await
         .map_err(|_| {
             AppError::format_error(
@@ -332,7 +344,8 @@ pub async fn reset_password_with_token(
             )
         })?
         .map(|json| {
-            serde_json::from_str(&json).map_err(|_| AppError::format_internal_error(&translations))
+            serde_json::from_str(&json)
+                .map_err(|error| AppError::format_service_error(&translations, error))
         })
         .transpose()?;
 
@@ -347,25 +360,62 @@ pub async fn reset_password_with_token(
         }
     };
 
+    let (_, name, email, _, _) = get_user_by_id(&state, &user_id)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
+
+    // Validated once the token names the user, so the policy can reject their name and email
+    match validate_password_reset_user_data(&user_data, &[&name, &email]) {
+        Some(validation_error) => {
+            return Err(AppError::format_error(
+                &translations,
+                StatusCode::BAD_REQUEST,
+                validation_error,
+            ))
+        }
+        None => (),
+    };
+
+    if is_breached_password(state.config, &user_data.password).await {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.breached_password",
+        ));
+    }
+
+    let is_reused = is_password_reused(&state, &user_id, &user_data.password)
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
+
+    if is_reused {
+        return Err(AppError::format_error(
+            &translations,
+            StatusCode::BAD_REQUEST,
+            "auth.errors.password_reused",
+        ));
+    }
+
     let password_hash = hash_password(&user_data.password)
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .await
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
 
-    update_user_password(&state, &user_id, &password_hash)
+    change_user_password(&state, &user_id, &password_hash)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
 
-    remove_token(&state, &reset_token_key)
+    // Whoever knew the old password shouldn't stay logged in
+    revoke_user_access(&state, &user_id)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_status_error(&translations, error))?;
 
-    let updated_user = match get_user_by_id(&state, &user_id).await {
-        Ok(user) => user,
-        Err(_) => return Err(AppError::format_internal_error(&translations)),
-    };
+    let updated_user = get_user_by_id(&state, &user_id)
+        .await
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
-    send_otc_success_email(&translations, "update_account", &updated_user.2)
+    send_otc_success_email(&state, &translations, "update_account", &updated_user.2)
         .await
-        .map_err(|_| AppError::format_internal_error(&translations))?;
+        .map_err(|error| AppError::format_service_error(&translations, error))?;
 
     Ok(ApiResponse::<()>::format_success(
         &translations,
@@ -374,3 +424,14 @@ pub async fn reset_password_with_token(
         None,
     ))
 }
+
+pub async fn get_password_policy_settings(
+    Extension(translations): Extension<Arc<Translations>>,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(ApiResponse::format_success(
+        &translations,
+        StatusCode::OK,
+        "auth.success.password_policy_fetched",
+        Some(get_password_policy().clone()),
+    ))
+}
@@ -0,0 +1,72 @@
+use axum::{extract::State, response::IntoResponse, Json};
+use http::{header, HeaderValue, StatusCode};
+
+use crate::{
+    constants::oauth::{OAUTH_SUPPORTED_SCOPES, OIDC_SUPPORTED_CLAIMS},
+    models::{general::AppState, oidc::models::OpenIdConfiguration},
+    utils::{jwt_keys::get_jwt_keys, oidc::get_issuer_url},
+};
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+// Public keys for verifying our JWTs offline, served as a plain JWK Set instead of an ApiResponse
+pub async fn get_jwks() -> impl IntoResponse {
+    let jwt_keys = match get_jwt_keys() {
+        Ok(jwt_keys) => jwt_keys,
+        Err(error) => return StatusCode::from(error).into_response(),
+    };
+
+    let mut response = (StatusCode::OK, Json(jwt_keys.get_jwk_set())).into_response();
+
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=300"),
+    );
+
+    response
+}
+
+// Lets OpenID Connect client libraries configure themselves from the issuer URL alone
+pub async fn get_openid_configuration(State(state): State<AppState>) -> impl IntoResponse {
+    let jwt_keys = match get_jwt_keys() {
+        Ok(jwt_keys) => jwt_keys,
+        Err(error) => return StatusCode::from(error).into_response(),
+    };
+
+    let issuer_url = get_issuer_url(state.config);
+
+    let signing_algorithm = jwt_keys
+        .get_verification_key(&jwt_keys.signing_key.kid)
+        .map(|verification_key| verification_key.jwk.alg.clone());
+
+    let configuration = OpenIdConfiguration {
+        authorization_endpoint: format!("{}/oauth/authorize", issuer_url),
+        token_endpoint: format!("{}/oauth/token", issuer_url),
+        userinfo_endpoint: format!("{}/oauth/userinfo", issuer_url),
+        jwks_uri: format!("{}/.well-known/jwks.json", issuer_url),
+        issuer: issuer_url,
+        response_types_supported: to_strings(&["code"]),
+        grant_types_supported: to_strings(&["authorization_code", "refresh_token"]),
+        subject_types_supported: to_strings(&["public"]),
+        id_token_signing_alg_values_supported: signing_algorithm.into_iter().collect(),
+        scopes_supported: to_strings(OAUTH_SUPPORTED_SCOPES),
+        claims_supported: to_strings(OIDC_SUPPORTED_CLAIMS),
+        token_endpoint_auth_methods_supported: to_strings(&[
+            "client_secret_basic",
+            "client_secret_post",
+            "none",
+        ]),
+        code_challenge_methods_supported: to_strings(&["S256"]),
+    };
+
+    let mut response = (StatusCode::OK, Json(configuration)).into_response();
+
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=300"),
+    );
+
+    response
+}
@@ -0,0 +1,128 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+
+use crate::{
+    queries::linked_identity::{CREATE_LINKED_IDENTITY, GET_LINKED_IDENTITY_USER_ID},
+    utils::{
+        database::{query_database, DatabasePool},
+        errors::ServiceError,
+    },
+};
+
+// Storage of the social login accounts linked to users, by provider and the subject it knows them by
+#[async_trait]
+pub trait LinkedIdentityRepository: Send + Sync {
+    async fn get_linked_identity_user_id(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<i32>, ServiceError>;
+
+    async fn create_linked_identity(
+        &self,
+        user_id: &i32,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ServiceError>;
+}
+
+pub struct SqlLinkedIdentityRepository {
+    pub db_pool: DatabasePool,
+}
+
+#[async_trait]
+impl LinkedIdentityRepository for SqlLinkedIdentityRepository {
+    async fn get_linked_identity_user_id(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<i32>, ServiceError> {
+        let user_id = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_as::<_, (i32,)>(&sql(GET_LINKED_IDENTITY_USER_ID))
+                .bind(provider)
+                .bind(subject)
+                .fetch_optional(pool)
+                .await
+        })?
+        .map(|(user_id,)| user_id);
+
+        Ok(user_id)
+    }
+
+    async fn create_linked_identity(
+        &self,
+        user_id: &i32,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(CREATE_LINKED_IDENTITY))
+                .bind(user_id)
+                .bind(provider)
+                .bind(subject)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+}
+
+// Keeps the user id per (provider, subject) for tests, an identity can only be linked once
+#[derive(Default)]
+pub struct InMemoryLinkedIdentityRepository {
+    identities: Mutex<HashMap<(String, String), i32>>,
+}
+
+impl InMemoryLinkedIdentityRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_identities<T>(
+        &self,
+        f: impl FnOnce(&mut HashMap<(String, String), i32>) -> Result<T, ServiceError>,
+    ) -> Result<T, ServiceError> {
+        let mut identities = self.identities.lock().map_err(|_| {
+            ServiceError::Configuration("linked identity store is poisoned".to_string())
+        })?;
+
+        f(&mut identities)
+    }
+}
+
+#[async_trait]
+impl LinkedIdentityRepository for InMemoryLinkedIdentityRepository {
+    async fn get_linked_identity_user_id(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<i32>, ServiceError> {
+        self.with_identities(|identities| {
+            Ok(identities
+                .get(&(provider.to_string(), subject.to_string()))
+                .copied())
+        })
+    }
+
+    async fn create_linked_identity(
+        &self,
+        user_id: &i32,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ServiceError> {
+        self.with_identities(|identities| {
+            let key = (provider.to_string(), subject.to_string());
+
+            if identities.contains_key(&key) {
+                return Err(ServiceError::Validation("auth.errors.social_login_failed"));
+            }
+
+            identities.insert(key, *user_id);
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use std::{fs, sync::Mutex};
+
+use async_trait::async_trait;
+use lettre::{
+    message::{header, MultiPart, SinglePart},
+    transport::smtp::{authentication::Credentials, client::Tls},
+    Message, SmtpTransport, Transport,
+};
+
+use crate::{
+    constants::config::MAILPIT_SMTP_HOST, models::config::models::EmailConfig,
+    utils::errors::ServiceError,
+};
+
+// A rendered email, the image is attached inline as cid_image when it is sent
+#[derive(Clone, Debug)]
+pub struct OutgoingEmail {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub image_path: &'static str,
+}
+
+// Delivery of rendered emails, over SMTP or captured in memory
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send_email(&self, email: OutgoingEmail) -> Result<(), ServiceError>;
+}
+
+pub struct SmtpMailer {
+    pub email_config: &'static EmailConfig,
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send_email(&self, email: OutgoingEmail) -> Result<(), ServiceError> {
+        let image_data = fs::read(email.image_path)?;
+
+        let image_contenttype_header = header::ContentType::parse("image/png")
+            .map_err(|error| ServiceError::Email(Box::new(error)))?;
+
+        let message = Message::builder()
+            .from(self.email_config.user.parse()?)
+            .reply_to(email.recipient.parse()?)
+            .to(email.recipient.parse()?)
+            .subject(email.subject)
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(header::ContentType::TEXT_HTML)
+                            .body(email.body),
+                    )
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(image_contenttype_header)
+                            .header(header::ContentDisposition::inline())
+                            .header(header::ContentId::from("cid_image".to_string()))
+                            .body(image_data),
+                    ),
+            )?;
+
+        let transport = if self.email_config.smtp_host == MAILPIT_SMTP_HOST {
+            SmtpTransport::relay(&self.email_config.smtp_host)?
+                .port(1025)
+                .tls(Tls::None)
+                .build()
+        } else {
+            let email_password = self.email_config.password.clone().ok_or_else(|| {
+                ServiceError::Configuration("email.password is not set".to_string())
+            })?;
+
+            let creds = Credentials::new(self.email_config.user.clone(), email_password);
+
+            SmtpTransport::relay(&self.email_config.smtp_host)?
+                .credentials(creds)
+                .build()
+        };
+
+        transport.send(&message)?;
+
+        Ok(())
+    }
+}
+
+// Keeps sent emails instead of delivering them, so tests can read the codes and links in them
+#[derive(Default)]
+pub struct InMemoryMailer {
+    emails: Mutex<Vec<OutgoingEmail>>,
+}
+
+impl InMemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_sent_emails(&self) -> Vec<OutgoingEmail> {
+        self.emails
+            .lock()
+            .map(|emails| emails.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send_email(&self, email: OutgoingEmail) -> Result<(), ServiceError> {
+        let mut emails = self
+            .emails
+            .lock()
+            .map_err(|_| ServiceError::Configuration("mailer is poisoned".to_string()))?;
+
+        emails.push(email);
+
+        Ok(())
+    }
+}
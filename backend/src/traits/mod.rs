@@ -1 +1,9 @@
 pub mod has_headers;
+pub mod linked_identity_repository;
+pub mod mailer;
+pub mod oauth_client_repository;
+pub mod passkey_repository;
+pub mod security_event_repository;
+pub mod token_store;
+pub mod two_factor_repository;
+pub mod user_repository;
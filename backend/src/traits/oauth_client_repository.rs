@@ -0,0 +1,179 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::{
+    models::oauth::models::OAuthClient,
+    queries::oauth::{
+        CREATE_OAUTH_CLIENT, GET_OAUTH_CLIENTS_BY_OWNER_ID, GET_OAUTH_CLIENT_BY_CLIENT_ID,
+    },
+    utils::{
+        database::{query_database, DatabasePool},
+        errors::ServiceError,
+    },
+};
+
+type OAuthClientRow = (String, Option<String>, String, String, String);
+
+impl From<OAuthClientRow> for OAuthClient {
+    fn from((client_id, client_secret_hash, name, redirect_uris, scopes): OAuthClientRow) -> Self {
+        OAuthClient {
+            client_id,
+            client_secret_hash,
+            name,
+            redirect_uris,
+            scopes,
+        }
+    }
+}
+
+// Storage of the registered OAuth clients and the users who own them
+#[async_trait]
+pub trait OAuthClientRepository: Send + Sync {
+    async fn create_oauth_client(
+        &self,
+        client: &OAuthClient,
+        owner_id: &i32,
+    ) -> Result<u64, ServiceError>;
+
+    async fn get_oauth_client_by_client_id(
+        &self,
+        client_id: &str,
+    ) -> Result<Option<OAuthClient>, ServiceError>;
+
+    // Oldest first
+    async fn get_oauth_clients_by_owner_id(
+        &self,
+        owner_id: &i32,
+    ) -> Result<Vec<OAuthClient>, ServiceError>;
+}
+
+pub struct SqlOAuthClientRepository {
+    pub db_pool: DatabasePool,
+}
+
+#[async_trait]
+impl OAuthClientRepository for SqlOAuthClientRepository {
+    async fn create_oauth_client(
+        &self,
+        client: &OAuthClient,
+        owner_id: &i32,
+    ) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(CREATE_OAUTH_CLIENT))
+                .bind(&client.client_id)
+                .bind(&client.client_secret_hash)
+                .bind(&client.name)
+                .bind(&client.redirect_uris)
+                .bind(&client.scopes)
+                .bind(owner_id)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+
+    async fn get_oauth_client_by_client_id(
+        &self,
+        client_id: &str,
+    ) -> Result<Option<OAuthClient>, ServiceError> {
+        let client = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_as::<_, OAuthClientRow>(&sql(GET_OAUTH_CLIENT_BY_CLIENT_ID))
+                .bind(client_id)
+                .fetch_optional(pool)
+                .await
+        })?
+        .map(OAuthClient::from);
+
+        Ok(client)
+    }
+
+    async fn get_oauth_clients_by_owner_id(
+        &self,
+        owner_id: &i32,
+    ) -> Result<Vec<OAuthClient>, ServiceError> {
+        let clients = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_as::<_, OAuthClientRow>(&sql(GET_OAUTH_CLIENTS_BY_OWNER_ID))
+                .bind(owner_id)
+                .fetch_all(pool)
+                .await
+        })?
+        .into_iter()
+        .map(OAuthClient::from)
+        .collect();
+
+        Ok(clients)
+    }
+}
+
+// Keeps clients with their owner in registration order for tests
+#[derive(Default)]
+pub struct InMemoryOAuthClientRepository {
+    clients: Mutex<Vec<(OAuthClient, i32)>>,
+}
+
+impl InMemoryOAuthClientRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_clients<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<(OAuthClient, i32)>) -> Result<T, ServiceError>,
+    ) -> Result<T, ServiceError> {
+        let mut clients = self.clients.lock().map_err(|_| {
+            ServiceError::Configuration("oauth client store is poisoned".to_string())
+        })?;
+
+        f(&mut clients)
+    }
+}
+
+#[async_trait]
+impl OAuthClientRepository for InMemoryOAuthClientRepository {
+    async fn create_oauth_client(
+        &self,
+        client: &OAuthClient,
+        owner_id: &i32,
+    ) -> Result<u64, ServiceError> {
+        self.with_clients(|clients| {
+            if clients
+                .iter()
+                .any(|(stored_client, _)| stored_client.client_id == client.client_id)
+            {
+                return Err(ServiceError::Validation("auth.errors.invalid_oauth_client"));
+            }
+
+            clients.push((client.clone(), *owner_id));
+
+            Ok(1)
+        })
+    }
+
+    async fn get_oauth_client_by_client_id(
+        &self,
+        client_id: &str,
+    ) -> Result<Option<OAuthClient>, ServiceError> {
+        self.with_clients(|clients| {
+            Ok(clients
+                .iter()
+                .find(|(client, _)| client.client_id == client_id)
+                .map(|(client, _)| client.clone()))
+        })
+    }
+
+    async fn get_oauth_clients_by_owner_id(
+        &self,
+        owner_id: &i32,
+    ) -> Result<Vec<OAuthClient>, ServiceError> {
+        self.with_clients(|clients| {
+            Ok(clients
+                .iter()
+                .filter(|(_, client_owner_id)| client_owner_id == owner_id)
+                .map(|(client, _)| client.clone())
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,225 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+
+use crate::{
+    queries::passkey::{
+        CREATE_PASSKEY, GET_PASSKEY_BY_CREDENTIAL_ID, GET_PASSKEY_CREDENTIAL_IDS_BY_USER_ID,
+        UPDATE_PASSKEY_SIGN_COUNT,
+    },
+    utils::{
+        database::{query_database, DatabasePool},
+        errors::ServiceError,
+    },
+};
+
+// (user_id, public_key, sign_count)
+pub type PasskeyRow = (i32, String, u32);
+
+// Storage of the WebAuthn credentials users log in with
+#[async_trait]
+pub trait PasskeyRepository: Send + Sync {
+    async fn create_passkey(
+        &self,
+        user_id: &i32,
+        credential_id: &str,
+        public_key: &str,
+        sign_count: u32,
+    ) -> Result<u64, ServiceError>;
+
+    async fn get_passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Result<PasskeyRow, ServiceError>;
+
+    async fn get_passkey_credential_ids_by_user_id(
+        &self,
+        user_id: &i32,
+    ) -> Result<Vec<String>, ServiceError>;
+
+    async fn update_passkey_sign_count(
+        &self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<u64, ServiceError>;
+}
+
+// Postgres has no unsigned integers, so the sign count is bound as i64 everywhere
+pub struct SqlPasskeyRepository {
+    pub db_pool: DatabasePool,
+}
+
+#[async_trait]
+impl PasskeyRepository for SqlPasskeyRepository {
+    async fn create_passkey(
+        &self,
+        user_id: &i32,
+        credential_id: &str,
+        public_key: &str,
+        sign_count: u32,
+    ) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(CREATE_PASSKEY))
+                .bind(user_id)
+                .bind(credential_id)
+                .bind(public_key)
+                .bind(i64::from(sign_count))
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+
+    async fn get_passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Result<PasskeyRow, ServiceError> {
+        // The MySQL column is INT UNSIGNED, the other dialects store a BIGINT
+        let passkey = match &self.db_pool {
+            DatabasePool::MySql(pool) => {
+                sqlx::query_as::<_, PasskeyRow>(GET_PASSKEY_BY_CREDENTIAL_ID)
+                    .bind(credential_id)
+                    .fetch_one(pool)
+                    .await
+            }
+            db_pool => query_database!(db_pool, |pool, sql| {
+                sqlx::query_as::<_, (i32, String, i64)>(&sql(GET_PASSKEY_BY_CREDENTIAL_ID))
+                    .bind(credential_id)
+                    .fetch_one(pool)
+                    .await
+                    .map(|(user_id, public_key, sign_count)| {
+                        (
+                            user_id,
+                            public_key,
+                            sign_count.clamp(0, u32::MAX.into()) as u32,
+                        )
+                    })
+            }),
+        }?;
+
+        Ok(passkey)
+    }
+
+    async fn get_passkey_credential_ids_by_user_id(
+        &self,
+        user_id: &i32,
+    ) -> Result<Vec<String>, ServiceError> {
+        let credential_ids = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_scalar::<_, String>(&sql(GET_PASSKEY_CREDENTIAL_IDS_BY_USER_ID))
+                .bind(user_id)
+                .fetch_all(pool)
+                .await
+        })?;
+
+        Ok(credential_ids)
+    }
+
+    async fn update_passkey_sign_count(
+        &self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(UPDATE_PASSKEY_SIGN_COUNT))
+                .bind(i64::from(sign_count))
+                .bind(credential_id)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+}
+
+// Keeps passkeys by credential id for tests, credential ids are unique like in the passkeys table
+#[derive(Default)]
+pub struct InMemoryPasskeyRepository {
+    passkeys: Mutex<HashMap<String, PasskeyRow>>,
+}
+
+impl InMemoryPasskeyRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_passkeys<T>(
+        &self,
+        f: impl FnOnce(&mut HashMap<String, PasskeyRow>) -> Result<T, ServiceError>,
+    ) -> Result<T, ServiceError> {
+        let mut passkeys = self
+            .passkeys
+            .lock()
+            .map_err(|_| ServiceError::Configuration("passkey store is poisoned".to_string()))?;
+
+        f(&mut passkeys)
+    }
+}
+
+#[async_trait]
+impl PasskeyRepository for InMemoryPasskeyRepository {
+    async fn create_passkey(
+        &self,
+        user_id: &i32,
+        credential_id: &str,
+        public_key: &str,
+        sign_count: u32,
+    ) -> Result<u64, ServiceError> {
+        self.with_passkeys(|passkeys| {
+            if passkeys.contains_key(credential_id) {
+                return Err(ServiceError::Validation("auth.errors.invalid_passkey"));
+            }
+
+            passkeys.insert(
+                credential_id.to_string(),
+                (*user_id, public_key.to_string(), sign_count),
+            );
+
+            Ok(1)
+        })
+    }
+
+    async fn get_passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Result<PasskeyRow, ServiceError> {
+        self.with_passkeys(|passkeys| {
+            passkeys
+                .get(credential_id)
+                .cloned()
+                .ok_or(ServiceError::NotFound)
+        })
+    }
+
+    async fn get_passkey_credential_ids_by_user_id(
+        &self,
+        user_id: &i32,
+    ) -> Result<Vec<String>, ServiceError> {
+        self.with_passkeys(|passkeys| {
+            Ok(passkeys
+                .iter()
+                .filter(|(_, (passkey_user_id, _, _))| passkey_user_id == user_id)
+                .map(|(credential_id, _)| credential_id.clone())
+                .collect())
+        })
+    }
+
+    async fn update_passkey_sign_count(
+        &self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<u64, ServiceError> {
+        self.with_passkeys(|passkeys| {
+            Ok(match passkeys.get_mut(credential_id) {
+                Some((_, _, stored_sign_count)) => {
+                    *stored_sign_count = sign_count;
+
+                    1
+                }
+                None => 0,
+            })
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::{
+    queries::security_event::CREATE_SECURITY_EVENT,
+    utils::{
+        database::{query_database, DatabasePool},
+        errors::ServiceError,
+    },
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecurityEvent {
+    pub user_id: i32,
+    pub event_type: String,
+    pub session_id: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+// The audit log of logins and other security relevant changes, only ever appended to
+#[async_trait]
+pub trait SecurityEventRepository: Send + Sync {
+    async fn create_security_event(&self, event: &SecurityEvent) -> Result<u64, ServiceError>;
+}
+
+pub struct SqlSecurityEventRepository {
+    pub db_pool: DatabasePool,
+}
+
+#[async_trait]
+impl SecurityEventRepository for SqlSecurityEventRepository {
+    async fn create_security_event(&self, event: &SecurityEvent) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(CREATE_SECURITY_EVENT))
+                .bind(event.user_id)
+                .bind(&event.event_type)
+                .bind(&event.session_id)
+                .bind(&event.ip)
+                .bind(&event.user_agent)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+}
+
+// Keeps the events in order so tests can check what was recorded
+#[derive(Default)]
+pub struct InMemorySecurityEventRepository {
+    events: Mutex<Vec<SecurityEvent>>,
+}
+
+impl InMemorySecurityEventRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_security_events(&self) -> Vec<SecurityEvent> {
+        self.events
+            .lock()
+            .map(|events| events.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl SecurityEventRepository for InMemorySecurityEventRepository {
+    async fn create_security_event(&self, event: &SecurityEvent) -> Result<u64, ServiceError> {
+        let mut events = self.events.lock().map_err(|_| {
+            ServiceError::Configuration("security event store is poisoned".to_string())
+        })?;
+
+        events.push(event.clone());
+
+        Ok(1)
+    }
+}
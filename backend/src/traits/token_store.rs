@@ -0,0 +1,447 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use redis::AsyncCommands;
+
+use crate::{
+    models::health::models::TokenStoreHealth,
+    utils::{errors::ServiceError, redis_pool::RedisPool},
+};
+
+// Short lived keys like tokens, sessions and counters, kept in Redis or in memory
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    async fn set_token(
+        &self,
+        key: &str,
+        value: &str,
+        expiration_seconds: i32,
+    ) -> Result<(), ServiceError>;
+
+    // Only sets a key that doesn't exist yet, returns whether it did, so one caller can claim it
+    async fn set_token_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        expiration_seconds: i32,
+    ) -> Result<bool, ServiceError>;
+
+    async fn get_token(&self, key: &str) -> Result<Option<String>, ServiceError>;
+
+    async fn remove_token(&self, key: &str) -> Result<(), ServiceError>;
+
+    // Reads and removes the key in one step, so a single use token can't be redeemed twice
+    async fn take_token(&self, key: &str) -> Result<Option<String>, ServiceError>;
+
+    // Adds a member and (re)sets the expiration of the whole set
+    async fn add_to_set(
+        &self,
+        key: &str,
+        member: &str,
+        expiration_seconds: i32,
+    ) -> Result<(), ServiceError>;
+
+    async fn get_set_members(&self, key: &str) -> Result<Vec<String>, ServiceError>;
+
+    async fn remove_from_set(&self, key: &str, member: &str) -> Result<(), ServiceError>;
+
+    // Increments a counter and (re)sets its expiration, returns the new count
+    async fn increment_counter(
+        &self,
+        key: &str,
+        expiration_seconds: i32,
+    ) -> Result<i64, ServiceError>;
+
+    // Takes one off a counter that still exists, so an attempt that wasn't made doesn't count
+    async fn decrement_counter(&self, key: &str) -> Result<(), ServiceError>;
+
+    // Returns the seconds until the key expires, or None when it doesn't exist or never expires
+    async fn get_time_to_live(&self, key: &str) -> Result<Option<i64>, ServiceError>;
+
+    async fn check_health(&self) -> TokenStoreHealth;
+}
+
+#[async_trait]
+impl TokenStore for RedisPool {
+    async fn set_token(
+        &self,
+        key: &str,
+        value: &str,
+        expiration_seconds: i32,
+    ) -> Result<(), ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let _: () = redis::cmd("SETEX")
+            .arg(key)
+            .arg(expiration_seconds)
+            .arg(value)
+            .query_async(&mut redis_con)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_token_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        expiration_seconds: i32,
+    ) -> Result<bool, ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let result: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(expiration_seconds)
+            .query_async(&mut redis_con)
+            .await?;
+
+        Ok(result.is_some())
+    }
+
+    async fn get_token(&self, key: &str) -> Result<Option<String>, ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let token_json: Option<String> = redis_con.get(key).await?;
+
+        Ok(token_json)
+    }
+
+    async fn remove_token(&self, key: &str) -> Result<(), ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let _: () = redis_con.del(key).await?;
+
+        Ok(())
+    }
+
+    async fn take_token(&self, key: &str) -> Result<Option<String>, ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let token_json: Option<String> = redis::cmd("GETDEL")
+            .arg(key)
+            .query_async(&mut redis_con)
+            .await?;
+
+        Ok(token_json)
+    }
+
+    async fn add_to_set(
+        &self,
+        key: &str,
+        member: &str,
+        expiration_seconds: i32,
+    ) -> Result<(), ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let _: () = redis::pipe()
+            .cmd("SADD")
+            .arg(key)
+            .arg(member)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(key)
+            .arg(expiration_seconds)
+            .ignore()
+            .query_async(&mut redis_con)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_set_members(&self, key: &str) -> Result<Vec<String>, ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let members: Vec<String> = redis_con.smembers(key).await?;
+
+        Ok(members)
+    }
+
+    async fn remove_from_set(&self, key: &str, member: &str) -> Result<(), ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let _: () = redis_con.srem(key, member).await?;
+
+        Ok(())
+    }
+
+    async fn increment_counter(
+        &self,
+        key: &str,
+        expiration_seconds: i32,
+    ) -> Result<i64, ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let (count,): (i64,) = redis::pipe()
+            .cmd("INCR")
+            .arg(key)
+            .cmd("EXPIRE")
+            .arg(key)
+            .arg(expiration_seconds)
+            .ignore()
+            .query_async(&mut redis_con)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn decrement_counter(&self, key: &str) -> Result<(), ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        // A plain DECR would recreate an expired counter without an expiration
+        let _: i64 = redis::Script::new(
+            "if redis.call('EXISTS', KEYS[1]) == 1 then return redis.call('DECR', KEYS[1]) end return 0",
+        )
+        .key(key)
+        .invoke_async(&mut redis_con)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_time_to_live(&self, key: &str) -> Result<Option<i64>, ServiceError> {
+        let mut redis_con = self.get_connection().await;
+
+        let time_to_live: i64 = redis_con.ttl(key).await?;
+
+        Ok((time_to_live > 0).then_some(time_to_live))
+    }
+
+    async fn check_health(&self) -> TokenStoreHealth {
+        RedisPool::check_health(self).await
+    }
+}
+
+enum InMemoryValue {
+    Text(String),
+    Set(HashSet<String>),
+}
+
+struct InMemoryEntry {
+    value: InMemoryValue,
+    expires_at: Instant,
+}
+
+// Keeps keys in a map for tests, expired keys are dropped on the next access
+#[derive(Default)]
+pub struct InMemoryTokenStore {
+    entries: Mutex<HashMap<String, InMemoryEntry>>,
+}
+
+impl InMemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_entries<T>(
+        &self,
+        f: impl FnOnce(&mut HashMap<String, InMemoryEntry>) -> T,
+    ) -> Result<T, ServiceError> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| ServiceError::Configuration("token store is poisoned".to_string()))?;
+
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.expires_at > now);
+
+        Ok(f(&mut entries))
+    }
+}
+
+fn get_expires_at(expiration_seconds: i32) -> Instant {
+    Instant::now() + Duration::from_secs(expiration_seconds.max(0) as u64)
+}
+
+#[async_trait]
+impl TokenStore for InMemoryTokenStore {
+    async fn set_token(
+        &self,
+        key: &str,
+        value: &str,
+        expiration_seconds: i32,
+    ) -> Result<(), ServiceError> {
+        self.with_entries(|entries| {
+            entries.insert(
+                key.to_string(),
+                InMemoryEntry {
+                    value: InMemoryValue::Text(value.to_string()),
+                    expires_at: get_expires_at(expiration_seconds),
+                },
+            );
+        })
+    }
+
+    async fn set_token_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        expiration_seconds: i32,
+    ) -> Result<bool, ServiceError> {
+        self.with_entries(|entries| {
+            if entries.contains_key(key) {
+                return false;
+            }
+
+            entries.insert(
+                key.to_string(),
+                InMemoryEntry {
+                    value: InMemoryValue::Text(value.to_string()),
+                    expires_at: get_expires_at(expiration_seconds),
+                },
+            );
+
+            true
+        })
+    }
+
+    async fn get_token(&self, key: &str) -> Result<Option<String>, ServiceError> {
+        self.with_entries(|entries| match entries.get(key) {
+            Some(InMemoryEntry {
+                value: InMemoryValue::Text(value),
+                ..
+            }) => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    async fn remove_token(&self, key: &str) -> Result<(), ServiceError> {
+        self.with_entries(|entries| {
+            entries.remove(key);
+        })
+    }
+
+    async fn take_token(&self, key: &str) -> Result<Option<String>, ServiceError> {
+        self.with_entries(|entries| match entries.remove(key) {
+            Some(InMemoryEntry {
+                value: InMemoryValue::Text(value),
+                ..
+            }) => Some(value),
+            _ => None,
+        })
+    }
+
+    async fn add_to_set(
+        &self,
+        key: &str,
+        member: &str,
+        expiration_seconds: i32,
+    ) -> Result<(), ServiceError> {
+        self.with_entries(|entries| {
+            let entry = entries
+                .entry(key.to_string())
+                .or_insert_with(|| InMemoryEntry {
+                    value: InMemoryValue::Set(HashSet::new()),
+                    expires_at: Instant::now(),
+                });
+
+            if let InMemoryValue::Text(_) = entry.value {
+                entry.value = InMemoryValue::Set(HashSet::new());
+            }
+
+            if let InMemoryValue::Set(members) = &mut entry.value {
+                members.insert(member.to_string());
+            }
+
+            entry.expires_at = get_expires_at(expiration_seconds);
+        })
+    }
+
+    async fn get_set_members(&self, key: &str) -> Result<Vec<String>, ServiceError> {
+        self.with_entries(|entries| match entries.get(key) {
+            Some(InMemoryEntry {
+                value: InMemoryValue::Set(members),
+                ..
+            }) => members.iter().cloned().collect(),
+            _ => Vec::new(),
+        })
+    }
+
+    async fn remove_from_set(&self, key: &str, member: &str) -> Result<(), ServiceError> {
+        self.with_entries(|entries| {
+            let is_empty = match entries.get_mut(key) {
+                Some(InMemoryEntry {
+                    value: InMemoryValue::Set(members),
+                    ..
+                }) => {
+                    members.remove(member);
+                    members.is_empty()
+                }
+                _ => false,
+            };
+
+            // Like Redis, a set without members no longer exists
+            if is_empty {
+                entries.remove(key);
+            }
+        })
+    }
+
+    async fn increment_counter(
+        &self,
+        key: &str,
+        expiration_seconds: i32,
+    ) -> Result<i64, ServiceError> {
+        self.with_entries(|entries| {
+            let count = match entries.get(key) {
+                Some(InMemoryEntry {
+                    value: InMemoryValue::Text(value),
+                    ..
+                }) => value.parse::<i64>().unwrap_or_default() + 1,
+                _ => 1,
+            };
+
+            entries.insert(
+                key.to_string(),
+                InMemoryEntry {
+                    value: InMemoryValue::Text(count.to_string()),
+                    expires_at: get_expires_at(expiration_seconds),
+                },
+            );
+
+            count
+        })
+    }
+
+    async fn decrement_counter(&self, key: &str) -> Result<(), ServiceError> {
+        self.with_entries(|entries| {
+            if let Some(InMemoryEntry {
+                value: InMemoryValue::Text(value),
+                ..
+            }) = entries.get_mut(key)
+            {
+                *value = (value.parse::<i64>().unwrap_or_default() - 1).to_string();
+            }
+        })
+    }
+
+    async fn get_time_to_live(&self, key: &str) -> Result<Option<i64>, ServiceError> {
+        self.with_entries(|entries| {
+            entries.get(key).and_then(|entry| {
+                let time_to_live = entry
+                    .expires_at
+                    .saturating_duration_since(Instant::now())
+                    .as_millis()
+                    .div_ceil(1000) as i64;
+
+                (time_to_live > 0).then_some(time_to_live)
+            })
+        })
+    }
+
+    async fn check_health(&self) -> TokenStoreHealth {
+        TokenStoreHealth {
+            is_healthy: true,
+            latency_ms: Some(0),
+            checked_at: Some(Utc::now().timestamp()),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+
+use crate::{
+    models::database::models::DatabaseKind,
+    queries::two_factor::{
+        COUNT_RECOVERY_CODES_BY_USER_ID, CREATE_RECOVERY_CODE, DELETE_RECOVERY_CODE,
+        DELETE_RECOVERY_CODES_BY_USER_ID, DELETE_TWO_FACTOR, ENABLE_TWO_FACTOR,
+        GET_TWO_FACTOR_BY_USER_ID, UPSERT_TWO_FACTOR_SECRET, UPSERT_TWO_FACTOR_SECRET_ON_CONFLICT,
+    },
+    utils::{
+        database::{query_database, DatabasePool},
+        errors::ServiceError,
+    },
+};
+
+// Storage of TOTP secrets and the hashed recovery codes that stand in for them
+#[async_trait]
+pub trait TwoFactorRepository: Send + Sync {
+    // The stored secret and whether it has been confirmed, or None when 2FA was never set up
+    async fn get_two_factor_by_user_id(
+        &self,
+        user_id: &i32,
+    ) -> Result<Option<(String, bool)>, ServiceError>;
+
+    // Sets a new unconfirmed secret, replacing any earlier one
+    async fn set_two_factor_secret(&self, user_id: &i32, secret: &str)
+        -> Result<u64, ServiceError>;
+
+    async fn enable_two_factor(&self, user_id: &i32) -> Result<u64, ServiceError>;
+
+    async fn delete_two_factor(&self, user_id: &i32) -> Result<u64, ServiceError>;
+
+    // Removes every code of the user and stores the new hashes in one step
+    async fn replace_recovery_codes(
+        &self,
+        user_id: &i32,
+        code_hashes: &[String],
+    ) -> Result<(), ServiceError>;
+
+    async fn count_recovery_codes(&self, user_id: &i32) -> Result<i64, ServiceError>;
+
+    // Returns the number of deleted codes, 1 when the code was there to use
+    async fn delete_recovery_code(
+        &self,
+        user_id: &i32,
+        code_hash: &str,
+    ) -> Result<u64, ServiceError>;
+
+    async fn delete_recovery_codes(&self, user_id: &i32) -> Result<u64, ServiceError>;
+}
+
+pub struct SqlTwoFactorRepository {
+    pub db_pool: DatabasePool,
+}
+
+#[async_trait]
+impl TwoFactorRepository for SqlTwoFactorRepository {
+    async fn get_two_factor_by_user_id(
+        &self,
+        user_id: &i32,
+    ) -> Result<Option<(String, bool)>, ServiceError> {
+        let two_factor = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_as::<_, (String, bool)>(&sql(GET_TWO_FACTOR_BY_USER_ID))
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+        })?;
+
+        Ok(two_factor)
+    }
+
+    async fn set_two_factor_secret(
+        &self,
+        user_id: &i32,
+        secret: &str,
+    ) -> Result<u64, ServiceError> {
+        let upsert_query = match self.db_pool.get_kind() {
+            DatabaseKind::MySql => UPSERT_TWO_FACTOR_SECRET,
+            DatabaseKind::Postgres | DatabaseKind::Sqlite => UPSERT_TWO_FACTOR_SECRET_ON_CONFLICT,
+        };
+
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(upsert_query))
+                .bind(user_id)
+                .bind(secret)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+
+    async fn enable_two_factor(&self, user_id: &i32) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(ENABLE_TWO_FACTOR))
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+
+    async fn delete_two_factor(&self, user_id: &i32) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(DELETE_TWO_FACTOR))
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+
+    async fn replace_recovery_codes(
+        &self,
+        user_id: &i32,
+        code_hashes: &[String],
+    ) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            async {
+                let mut transaction = pool.begin().await?;
+
+                sqlx::query(&sql(DELETE_RECOVERY_CODES_BY_USER_ID))
+                    .bind(user_id)
+                    .execute(&mut *transaction)
+                    .await?;
+
+                for code_hash in code_hashes {
+                    sqlx::query(&sql(CREATE_RECOVERY_CODE))
+                        .bind(user_id)
+                        .bind(code_hash)
+                        .execute(&mut *transaction)
+                        .await?;
+                }
+
+                transaction.commit().await
+            }
+            .await
+        })?;
+
+        Ok(())
+    }
+
+    async fn count_recovery_codes(&self, user_id: &i32) -> Result<i64, ServiceError> {
+        let count = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_scalar::<_, i64>(&sql(COUNT_RECOVERY_CODES_BY_USER_ID))
+                .bind(user_id)
+                .fetch_one(pool)
+                .await
+        })?;
+
+        Ok(count)
+    }
+
+    async fn delete_recovery_code(
+        &self,
+        user_id: &i32,
+        code_hash: &str,
+    ) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(DELETE_RECOVERY_CODE))
+                .bind(user_id)
+                .bind(code_hash)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+
+    async fn delete_recovery_codes(&self, user_id: &i32) -> Result<u64, ServiceError> {
+        let rows_affected = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(DELETE_RECOVERY_CODES_BY_USER_ID))
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+}
+
+#[derive(Default)]
+struct InMemoryTwoFactors {
+    // (secret, is_enabled) per user
+    secrets: HashMap<i32, (String, bool)>,
+    recovery_code_hashes: HashMap<i32, Vec<String>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryTwoFactorRepository {
+    two_factors: Mutex<InMemoryTwoFactors>,
+}
+
+impl InMemoryTwoFactorRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_two_factors<T>(
+        &self,
+        f: impl FnOnce(&mut InMemoryTwoFactors) -> T,
+    ) -> Result<T, ServiceError> {
+        let mut two_factors = self
+            .two_factors
+            .lock()
+            .map_err(|_| ServiceError::Configuration("two factor store is poisoned".to_string()))?;
+
+        Ok(f(&mut two_factors))
+    }
+}
+
+#[async_trait]
+impl TwoFactorRepository for InMemoryTwoFactorRepository {
+    async fn get_two_factor_by_user_id(
+        &self,
+        user_id: &i32,
+    ) -> Result<Option<(String, bool)>, ServiceError> {
+        self.with_two_factors(|two_factors| two_factors.secrets.get(user_id).cloned())
+    }
+
+    async fn set_two_factor_secret(
+        &self,
+        user_id: &i32,
+        secret: &str,
+    ) -> Result<u64, ServiceError> {
+        self.with_two_factors(|two_factors| {
+            two_factors
+                .secrets
+                .insert(*user_id, (secret.to_string(), false));
+
+            1
+        })
+    }
+
+    async fn enable_two_factor(&self, user_id: &i32) -> Result<u64, ServiceError> {
+        self.with_two_factors(|two_factors| match two_factors.secrets.get_mut(user_id) {
+            Some((_, is_enabled)) => {
+                *is_enabled = true;
+
+                1
+            }
+            None => 0,
+        })
+    }
+
+    async fn delete_two_factor(&self, user_id: &i32) -> Result<u64, ServiceError> {
+        self.with_two_factors(|two_factors| two_factors.secrets.remove(user_id).is_some() as u64)
+    }
+
+    async fn replace_recovery_codes(
+        &self,
+        user_id: &i32,
+        code_hashes: &[String],
+    ) -> Result<(), ServiceError> {
+        self.with_two_factors(|two_factors| {
+            two_factors
+                .recovery_code_hashes
+                .insert(*user_id, code_hashes.to_vec());
+        })
+    }
+
+    async fn count_recovery_codes(&self, user_id: &i32) -> Result<i64, ServiceError> {
+        self.with_two_factors(|two_factors| {
+            two_factors
+                .recovery_code_hashes
+                .get(user_id)
+                .map_or(0, |code_hashes| code_hashes.len() as i64)
+        })
+    }
+
+    async fn delete_recovery_code(
+        &self,
+        user_id: &i32,
+        code_hash: &str,
+    ) -> Result<u64, ServiceError> {
+        self.with_two_factors(|two_factors| {
+            let code_hashes = match two_factors.recovery_code_hashes.get_mut(user_id) {
+                Some(code_hashes) => code_hashes,
+                None => return 0,
+            };
+
+            let count = code_hashes.len();
+            code_hashes.retain(|stored_hash| stored_hash != code_hash);
+
+            (count - code_hashes.len()) as u64
+        })
+    }
+
+    async fn delete_recovery_codes(&self, user_id: &i32) -> Result<u64, ServiceError> {
+        self.with_two_factors(|two_factors| {
+            two_factors
+                .recovery_code_hashes
+                .remove(user_id)
+                .map_or(0, |code_hashes| code_hashes.len() as u64)
+        })
+    }
+}
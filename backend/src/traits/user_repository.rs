@@ -0,0 +1,472 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+
+use crate::{
+    queries::{
+        password_history::{
+            CREATE_PASSWORD_HISTORY_ENTRY, DELETE_OLD_PASSWORD_HISTORY_BY_USER_ID,
+            GET_PASSWORD_HISTORY_BY_USER_ID,
+        },
+        user::{
+            CONFIRM_USER, CREATE_USER, CREATE_USER_RETURNING_ID, DELETE_USER, GET_USER_BY_EMAIL,
+            GET_USER_BY_ID, GET_USER_PASSWORD_HASH_BY_ID, UPDATE_NON_SENSITIVE_USER_FIELDS,
+            UPDATE_USER_EMAIL, UPDATE_USER_PASSWORD,
+        },
+    },
+    utils::{
+        database::{query_database, DatabasePool},
+        errors::ServiceError,
+    },
+};
+
+// (id, name, email, password_hash, phone, is_confirmed)
+pub type UserWithPasswordHash = (i32, String, String, String, Option<String>, bool);
+
+// (id, name, email, phone, is_confirmed)
+pub type UserRow = (i32, String, String, Option<String>, bool);
+
+// Storage of the users table, so services can run against a database or in memory
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn get_user_by_email(&self, email: &str) -> Result<UserWithPasswordHash, ServiceError>;
+
+    async fn get_user_by_id(&self, id: &i32) -> Result<UserRow, ServiceError>;
+
+    // Returns the id of the new user
+    async fn create_user(
+        &self,
+        name: &str,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<i32, ServiceError>;
+
+    async fn update_non_sensitive_user_fields(
+        &self,
+        id: &i32,
+        name: &str,
+        phone: Option<&str>,
+    ) -> Result<(), ServiceError>;
+
+    async fn update_user_email(&self, id: &i32, email: &str) -> Result<(), ServiceError>;
+
+    // Replaces the hash without keeping the old one, like when rehashing with new parameters
+    async fn update_user_password(&self, id: &i32, password_hash: &str)
+        -> Result<(), ServiceError>;
+
+    // Moves the current hash to the history, keeping at most history_size entries
+    async fn change_user_password(
+        &self,
+        id: &i32,
+        password_hash: &str,
+        history_size: i64,
+    ) -> Result<(), ServiceError>;
+
+    // The current hash first, then up to history_size previous ones from new to old
+    async fn get_password_hashes(
+        &self,
+        id: &i32,
+        history_size: i64,
+    ) -> Result<Vec<String>, ServiceError>;
+
+    async fn confirm_user(&self, id: &i32) -> Result<(), ServiceError>;
+
+    async fn delete_user_by_id(&self, id: &i32) -> Result<(), ServiceError>;
+
+    async fn is_healthy(&self) -> bool;
+}
+
+// Works on MySQL, Postgres and SQLite, whichever the pool connects to
+pub struct SqlUserRepository {
+    pub db_pool: DatabasePool,
+}
+
+#[async_trait]
+impl UserRepository for SqlUserRepository {
+    async fn get_user_by_email(&self, email: &str) -> Result<UserWithPasswordHash, ServiceError> {
+        let user = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_as::<_, UserWithPasswordHash>(&sql(GET_USER_BY_EMAIL))
+                .bind(email)
+                .fetch_one(pool)
+                .await
+        })?;
+
+        Ok(user)
+    }
+
+    async fn get_user_by_id(&self, id: &i32) -> Result<UserRow, ServiceError> {
+        let user = query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query_as::<_, UserRow>(&sql(GET_USER_BY_ID))
+                .bind(id)
+                .fetch_one(pool)
+                .await
+        })?;
+
+        Ok(user)
+    }
+
+    async fn create_user(
+        &self,
+        name: &str,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<i32, ServiceError> {
+        match &self.db_pool {
+            DatabasePool::MySql(pool) => {
+                let created_user_result = sqlx::query(CREATE_USER)
+                    .bind(name)
+                    .bind(email)
+                    .bind(password_hash)
+                    .execute(pool)
+                    .await?;
+
+                created_user_result
+                    .last_insert_id()
+                    .try_into()
+                    .map_err(|error| ServiceError::Database(sqlx::Error::Decode(Box::new(error))))
+            }
+            db_pool => {
+                let id = query_database!(db_pool, |pool, sql| {
+                    sqlx::query_scalar::<_, i32>(&sql(CREATE_USER_RETURNING_ID))
+                        .bind(name)
+                        .bind(email)
+                        .bind(password_hash)
+                        .fetch_one(pool)
+                        .await
+                })?;
+
+                Ok(id)
+            }
+        }
+    }
+
+    async fn update_non_sensitive_user_fields(
+        &self,
+        id: &i32,
+        name: &str,
+        phone: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(UPDATE_NON_SENSITIVE_USER_FIELDS))
+                .bind(name)
+                .bind(phone)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    async fn update_user_email(&self, id: &i32, email: &str) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(UPDATE_USER_EMAIL))
+                .bind(email)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    async fn update_user_password(
+        &self,
+        id: &i32,
+        password_hash: &str,
+    ) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(UPDATE_USER_PASSWORD))
+                .bind(password_hash)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    async fn change_user_password(
+        &self,
+        id: &i32,
+        password_hash: &str,
+        history_size: i64,
+    ) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            async {
+                let mut transaction = pool.begin().await?;
+
+                sqlx::query(&sql(CREATE_PASSWORD_HISTORY_ENTRY))
+                    .bind(id)
+                    .execute(&mut *transaction)
+                    .await?;
+
+                sqlx::query(&sql(DELETE_OLD_PASSWORD_HISTORY_BY_USER_ID))
+                    .bind(id)
+                    .bind(id)
+                    .bind(history_size)
+                    .execute(&mut *transaction)
+                    .await?;
+
+                sqlx::query(&sql(UPDATE_USER_PASSWORD))
+                    .bind(password_hash)
+                    .bind(id)
+                    .execute(&mut *transaction)
+                    .await?;
+
+                transaction.commit().await
+            }
+            .await
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_password_hashes(
+        &self,
+        id: &i32,
+        history_size: i64,
+    ) -> Result<Vec<String>, ServiceError> {
+        let password_hashes = query_database!(&self.db_pool, |pool, sql| {
+            async {
+                let current_password_hash =
+                    sqlx::query_scalar::<_, String>(&sql(GET_USER_PASSWORD_HASH_BY_ID))
+                        .bind(id)
+                        .fetch_one(pool)
+                        .await?;
+
+                let previous_password_hashes =
+                    sqlx::query_scalar::<_, String>(&sql(GET_PASSWORD_HISTORY_BY_USER_ID))
+                        .bind(id)
+                        .bind(history_size)
+                        .fetch_all(pool)
+                        .await?;
+
+                Ok::<_, sqlx::Error>(
+                    std::iter::once(current_password_hash)
+                        .chain(previous_password_hashes)
+                        .collect(),
+                )
+            }
+            .await
+        })?;
+
+        Ok(password_hashes)
+    }
+
+    async fn confirm_user(&self, id: &i32) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(CONFIRM_USER))
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    async fn delete_user_by_id(&self, id: &i32) -> Result<(), ServiceError> {
+        query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql(DELETE_USER))
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    async fn is_healthy(&self) -> bool {
+        query_database!(&self.db_pool, |pool, sql| {
+            sqlx::query(&sql("SELECT 1")).execute(pool).await.is_ok()
+        })
+    }
+}
+
+struct InMemoryUser {
+    name: String,
+    email: String,
+    password_hash: String,
+    phone: Option<String>,
+    is_confirmed: bool,
+    // Previous hashes from new to old
+    password_history: Vec<String>,
+}
+
+#[derive(Default)]
+struct InMemoryUsers {
+    last_id: i32,
+    users: HashMap<i32, InMemoryUser>,
+}
+
+// Keeps users in a map for tests, with a unique email like the users table
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: Mutex<InMemoryUsers>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_users<T>(
+        &self,
+        f: impl FnOnce(&mut InMemoryUsers) -> Result<T, ServiceError>,
+    ) -> Result<T, ServiceError> {
+        let mut users = self
+            .users
+            .lock()
+            .map_err(|_| ServiceError::Configuration("user store is poisoned".to_string()))?;
+
+        f(&mut users)
+    }
+
+    fn with_user<T>(
+        &self,
+        id: &i32,
+        f: impl FnOnce(&mut InMemoryUser) -> T,
+    ) -> Result<T, ServiceError> {
+        self.with_users(|users| users.users.get_mut(id).map(f).ok_or(ServiceError::NotFound))
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn get_user_by_email(&self, email: &str) -> Result<UserWithPasswordHash, ServiceError> {
+        self.with_users(|users| {
+            users
+                .users
+                .iter()
+                .find(|(_, user)| user.email == email)
+                .map(|(id, user)| {
+                    (
+                        *id,
+                        user.name.clone(),
+                        user.email.clone(),
+                        user.password_hash.clone(),
+                        user.phone.clone(),
+                        user.is_confirmed,
+                    )
+                })
+                .ok_or(ServiceError::NotFound)
+        })
+    }
+
+    async fn get_user_by_id(&self, id: &i32) -> Result<UserRow, ServiceError> {
+        self.with_user(id, |user| {
+            (
+                *id,
+                user.name.clone(),
+                user.email.clone(),
+                user.phone.clone(),
+                user.is_confirmed,
+            )
+        })
+    }
+
+    async fn create_user(
+        &self,
+        name: &str,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<i32, ServiceError> {
+        self.with_users(|users| {
+            if users.users.values().any(|user| user.email == email) {
+                return Err(ServiceError::Validation("auth.errors.email_already_exists"));
+            }
+
+            users.last_id += 1;
+            users.users.insert(
+                users.last_id,
+                InMemoryUser {
+                    name: name.to_string(),
+                    email: email.to_string(),
+                    password_hash: password_hash.to_string(),
+                    phone: None,
+                    is_confirmed: false,
+                    password_history: Vec::new(),
+                },
+            );
+
+            Ok(users.last_id)
+        })
+    }
+
+    async fn update_non_sensitive_user_fields(
+        &self,
+        id: &i32,
+        name: &str,
+        phone: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        self.with_user(id, |user| {
+            user.name = name.to_string();
+            user.phone = phone.map(str::to_string);
+        })
+    }
+
+    async fn update_user_email(&self, id: &i32, email: &str) -> Result<(), ServiceError> {
+        self.with_user(id, |user| user.email = email.to_string())
+    }
+
+    async fn update_user_password(
+        &self,
+        id: &i32,
+        password_hash: &str,
+    ) -> Result<(), ServiceError> {
+        self.with_user(id, |user| user.password_hash = password_hash.to_string())
+    }
+
+    async fn change_user_password(
+        &self,
+        id: &i32,
+        password_hash: &str,
+        history_size: i64,
+    ) -> Result<(), ServiceError> {
+        self.with_user(id, |user| {
+            let previous_password_hash =
+                std::mem::replace(&mut user.password_hash, password_hash.to_string());
+
+            user.password_history.insert(0, previous_password_hash);
+            user.password_history.truncate(history_size.max(0) as usize);
+        })
+    }
+
+    async fn get_password_hashes(
+        &self,
+        id: &i32,
+        history_size: i64,
+    ) -> Result<Vec<String>, ServiceError> {
+        self.with_user(id, |user| {
+            std::iter::once(&user.password_hash)
+                .chain(
+                    user.password_history
+                        .iter()
+                        .take(history_size.max(0) as usize),
+                )
+                .cloned()
+                .collect()
+        })
+    }
+
+    async fn confirm_user(&self, id: &i32) -> Result<(), ServiceError> {
+        self.with_user(id, |user| user.is_confirmed = true)
+    }
+
+    async fn delete_user_by_id(&self, id: &i32) -> Result<(), ServiceError> {
+        self.with_users(|users| {
+            users.users.remove(id);
+
+            Ok(())
+        })
+    }
+
+    async fn is_healthy(&self) -> bool {
+        true
+    }
+}
@@ -1,11 +1,254 @@
-use bcrypt::{hash, verify, DEFAULT_COST};
+use std::{str::FromStr, sync::OnceLock};
 
-pub fn verify_password(password: &str, hash: &str) -> Result<bool, bcrypt::BcryptError> {
-    verify(password, hash)
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, SaltString},
+    Algorithm, Argon2, Params, PasswordVerifier, Version,
+};
+use axum::http::StatusCode;
+use bcrypt::{HashParts, DEFAULT_COST};
+
+use crate::{
+    constants::auth::{
+        ARGON2_DEFAULT_MEMORY_COST_KIB, ARGON2_DEFAULT_TIME_COST, ARGON2_PARALLELISM,
+    },
+    models::config::models::PasswordHashingConfig,
+};
+
+static PASSWORD_HASHER: OnceLock<Box<dyn PasswordHasher>> = OnceLock::new();
+
+pub trait PasswordHasher: Send + Sync {
+    fn hash(&self, password: &str) -> Result<String, StatusCode>;
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, StatusCode>;
+
+    // Whether the stored hash was made by this algorithm, detected from its format
+    fn is_own_hash(&self, hash: &str) -> bool;
+
+    // Whether the stored hash should be replaced, because of another algorithm or outdated parameters
+    fn needs_rehash(&self, hash: &str) -> bool;
+}
+
+pub struct Argon2idHasher {
+    params: Params,
+}
+
+impl Argon2idHasher {
+    pub fn new(memory_cost_kib: u32, time_cost: u32, parallelism: u32) -> Result<Self, StatusCode> {
+        let params = Params::new(memory_cost_kib, time_cost, parallelism, None)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok(Self { params })
+    }
+
+    fn argon2(&self) -> Argon2<'_> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordHasher for Argon2idHasher {
+    fn hash(&self, password: &str) -> Result<String, StatusCode> {
+        let salt = SaltString::generate(&mut OsRng);
+
+        let hash =
+            argon2::PasswordHasher::hash_password(&self.argon2(), password.as_bytes(), &salt)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok(hash.to_string())
+    }
+
+    // The parameters of the stored hash are used, so hashes made with older parameters keep verifying
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, StatusCode> {
+        let parsed_hash = PasswordHash::new(hash).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok(Argon2::default()
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .is_ok())
+    }
+
+    fn is_own_hash(&self, hash: &str) -> bool {
+        hash.starts_with("$argon2")
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        let parsed_hash = match PasswordHash::new(hash) {
+            Ok(parsed_hash) => parsed_hash,
+            Err(_) => return true,
+        };
+
+        let is_current_params = Params::try_from(&parsed_hash).is_ok_and(|params| {
+            params.m_cost() == self.params.m_cost()
+                && params.t_cost() == self.params.t_cost()
+                && params.p_cost() == self.params.p_cost()
+        });
+
+        parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into())
+            || !is_current_params
+    }
+}
+
+pub struct BcryptHasher {
+    cost: u32,
+}
+
+impl BcryptHasher {
+    pub fn new(cost: u32) -> Self {
+        Self { cost }
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn hash(&self, password: &str) -> Result<String, StatusCode> {
+        bcrypt::hash(password, self.cost).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, StatusCode> {
+        bcrypt::verify(password, hash).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn is_own_hash(&self, hash: &str) -> bool {
+        ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        !self.is_own_hash(hash)
+            || HashParts::from_str(hash).map_or(true, |parts| parts.get_cost() != self.cost)
+    }
+}
+
+fn get_default_password_hasher() -> Box<dyn PasswordHasher> {
+    Box::new(
+        Argon2idHasher::new(
+            ARGON2_DEFAULT_MEMORY_COST_KIB,
+            ARGON2_DEFAULT_TIME_COST,
+            ARGON2_PARALLELISM,
+        )
+        .expect("Default Argon2 parameters should be valid"),
+    )
+}
+
+// The algorithm picks the hasher for new hashes, costs are checked so a typo doesn't weaken the hashes
+pub fn load_password_hasher(
+    config: &PasswordHashingConfig,
+) -> Result<Box<dyn PasswordHasher>, String> {
+    if config.algorithm.eq_ignore_ascii_case("bcrypt") {
+        if !(4..=31).contains(&config.bcrypt_cost) {
+            return Err(format!(
+                "password_hashing.bcrypt_cost (or BCRYPT_COST) must be between 4 and 31, got {}",
+                config.bcrypt_cost
+            ));
+        }
+
+        return Ok(Box::new(BcryptHasher::new(config.bcrypt_cost)));
+    }
+
+    if !config.algorithm.eq_ignore_ascii_case("argon2id") {
+        return Err(format!(
+            "password_hashing.algorithm (or PASSWORD_HASH_ALGORITHM) must be argon2id or bcrypt, got {}",
+            config.algorithm
+        ));
+    }
+
+    let hasher = Argon2idHasher::new(
+        config.argon2_memory_cost_kib,
+        config.argon2_time_cost,
+        ARGON2_PARALLELISM,
+    )
+    .map_err(|_| {
+        format!(
+            "ARGON2_MEMORY_COST {} and ARGON2_TIME_COST {} are not valid Argon2id parameters",
+            config.argon2_memory_cost_kib, config.argon2_time_cost
+        )
+    })?;
+
+    Ok(Box::new(hasher))
+}
+
+pub fn init_password_hasher(config: &PasswordHashingConfig) -> Result<(), String> {
+    if PASSWORD_HASHER.get().is_some() {
+        return Ok(());
+    }
+
+    let password_hasher = load_password_hasher(config)?;
+    let _ = PASSWORD_HASHER.set(password_hasher);
+
+    Ok(())
+}
+
+// Falls back to the default Argon2id parameters when init_password_hasher wasn't called, like in tests
+pub fn get_password_hasher() -> &'static dyn PasswordHasher {
+    PASSWORD_HASHER
+        .get_or_init(get_default_password_hasher)
+        .as_ref()
+}
+
+// Verifies with the algorithm the stored hash was made with, whatever the configured hasher is
+pub fn verify_password_hash(password: &str, hash: &str) -> Result<bool, StatusCode> {
+    let hasher = get_password_hasher();
+
+    if hasher.is_own_hash(hash) {
+        return hasher.verify(password, hash);
+    }
+
+    let bcrypt_hasher = BcryptHasher::new(DEFAULT_COST);
+
+    if bcrypt_hasher.is_own_hash(hash) {
+        return bcrypt_hasher.verify(password, hash);
+    }
+
+    let argon2_hasher = Argon2idHasher::new(
+        ARGON2_DEFAULT_MEMORY_COST_KIB,
+        ARGON2_DEFAULT_TIME_COST,
+        ARGON2_PARALLELISM,
+    )?;
+
+    if argon2_hasher.is_own_hash(hash) {
+        return argon2_hasher.verify(password, hash);
+    }
+
+    Err(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub fn needs_password_rehash(hash: &str) -> bool {
+    get_password_hasher().needs_rehash(hash)
+}
+
+// Hashing is slow on purpose, so it runs on a blocking thread instead of stalling the runtime
+pub async fn verify_password(password: &str, hash: &str) -> Result<bool, StatusCode> {
+    let password = password.to_string();
+    let hash = hash.to_string();
+
+    tokio::task::spawn_blocking(move || verify_password_hash(&password, &hash))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+}
+
+// Checks a password against several stored hashes on one blocking thread, stops at the first match
+pub async fn verify_password_against_any(
+    password: &str,
+    hashes: Vec<String>,
+) -> Result<bool, StatusCode> {
+    let password = password.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        for hash in hashes {
+            if verify_password_hash(&password, &hash)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
 }
 
-pub fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
-    let hash = hash(password, DEFAULT_COST)?;
+pub async fn hash_password(password: &str) -> Result<String, StatusCode> {
+    let password = password.to_string();
 
-    Ok(hash)
+    tokio::task::spawn_blocking(move || get_password_hasher().hash(&password))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
 }
@@ -0,0 +1,81 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use sha1::{Digest, Sha1};
+
+use crate::models::config::models::Config;
+
+// Splits the uppercase SHA-1 of a password in the 5 character range prefix and the 35 character suffix
+pub fn format_breached_password_range(password: &str) -> (String, String) {
+    let password_hash: String = Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+
+    let (prefix, suffix) = password_hash.split_at(5);
+
+    (prefix.to_string(), suffix.to_string())
+}
+
+// A range file has a SUFFIX:COUNT line for every breached password with the prefix of the file
+pub fn is_suffix_in_range(range: &str, suffix: &str) -> bool {
+    range.lines().any(|line| {
+        let (line_suffix, count) = line.trim().split_once(':').unwrap_or((line.trim(), "1"));
+
+        // Padded ranges contain fake suffixes with a count of 0
+        line_suffix.eq_ignore_ascii_case(suffix) && count.trim() != "0"
+    })
+}
+
+fn get_range_paths(dataset_dir: &Path, prefix: &str) -> [PathBuf; 2] {
+    [
+        dataset_dir.join(format!("{}.txt", prefix)),
+        dataset_dir.join(prefix),
+    ]
+}
+
+// A configured dataset that can't be read would let every password through, so it's checked with the config
+pub fn check_breached_passwords_dir(dataset_dir: &Path) -> Result<(), String> {
+    let mut entries = fs::read_dir(dataset_dir)
+        .map_err(|err| format!("Error reading {}: {}", dataset_dir.display(), err))?;
+
+    match entries.next() {
+        Some(Ok(_)) => Ok(()),
+        Some(Err(err)) => Err(format!("Error reading {}: {}", dataset_dir.display(), err)),
+        None => Err(format!(
+            "{} doesn't contain any password ranges",
+            dataset_dir.display()
+        )),
+    }
+}
+
+pub async fn is_breached_password_in_dir(dataset_dir: &Path, password: &str) -> bool {
+    let (prefix, suffix) = format_breached_password_range(password);
+
+    for range_path in get_range_paths(dataset_dir, &prefix) {
+        if let Ok(range) = tokio::fs::read_to_string(&range_path).await {
+            return is_suffix_in_range(&range, &suffix);
+        }
+    }
+
+    // The full dataset has a file for every prefix, so a missing one means the dataset is broken
+    eprintln!(
+        "Breached password range {} is missing in {}",
+        prefix,
+        dataset_dir.display()
+    );
+
+    false
+}
+
+// Without a dataset no password counts as breached
+pub async fn is_breached_password(config: &Config, password: &str) -> bool {
+    let dataset_dir = match &config.breached_passwords_dir {
+        Some(dataset_dir) => Path::new(dataset_dir),
+        None => return false,
+    };
+
+    is_breached_password_in_dir(dataset_dir, password).await
+}
@@ -0,0 +1,110 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::ConnectInfo,
+    http::{Extensions, HeaderMap},
+};
+
+use crate::models::config::models::Config;
+
+// A proxy address or network in CIDR notation, like 10.0.0.1 or 10.0.0.0/8
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix_length: u32,
+}
+
+impl TrustedProxy {
+    pub fn parse(value: &str) -> Option<TrustedProxy> {
+        let (network, prefix_length) = match value.trim().split_once('/') {
+            Some((network, prefix_length)) => (network, Some(prefix_length)),
+            None => (value.trim(), None),
+        };
+
+        let network: IpAddr = network.parse().ok()?;
+        let max_prefix_length = if network.is_ipv4() { 32 } else { 128 };
+
+        let prefix_length = match prefix_length {
+            Some(prefix_length) => prefix_length.parse().ok()?,
+            None => max_prefix_length,
+        };
+
+        (prefix_length <= max_prefix_length).then_some(TrustedProxy {
+            network,
+            prefix_length,
+        })
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_length).unwrap_or(0);
+
+                u32::from(network) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_length).unwrap_or(0);
+
+                u128::from(network) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+// Only trusts X-Forwarded-For when the request came through a trusted proxy, and then takes the
+// right-most address that isn't one of our proxies, as everything left of it can be spoofed by the client
+pub fn resolve_client_ip(
+    peer_ip: Option<IpAddr>,
+    forwarded_for: Option<&str>,
+    trusted_proxies: &[TrustedProxy],
+) -> Option<IpAddr> {
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+
+    let peer_ip = peer_ip?;
+
+    if !is_trusted(&peer_ip) {
+        return Some(peer_ip);
+    }
+
+    let forwarded_ips: Vec<IpAddr> = match forwarded_for {
+        Some(forwarded_for) => forwarded_for
+            .split(',')
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect(),
+        None => return Some(peer_ip),
+    };
+
+    let client_ip = forwarded_ips
+        .iter()
+        .rev()
+        .find(|ip| !is_trusted(ip))
+        .or(forwarded_ips.first())
+        .copied()
+        .unwrap_or(peer_ip);
+
+    Some(client_ip)
+}
+
+// Without trusted proxies in the config, X-Forwarded-For is ignored
+pub fn get_client_ip(
+    config: &Config,
+    headers: &HeaderMap,
+    extensions: &Extensions,
+) -> Option<IpAddr> {
+    let peer_ip = extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+
+    let forwarded_for = headers
+        .get("x-forwarded-for")
+        .and_then(|forwarded_for| forwarded_for.to_str().ok());
+
+    // The entries were validated when the config was loaded
+    let trusted_proxies: Vec<TrustedProxy> = config
+        .trusted_proxies
+        .iter()
+        .filter_map(|trusted_proxy| TrustedProxy::parse(trusted_proxy))
+        .collect();
+
+    resolve_client_ip(peer_ip, forwarded_for, &trusted_proxies)
+}
@@ -0,0 +1,563 @@
+use std::{env, fmt, fs, net::SocketAddr, path::Path, str::FromStr};
+
+use http::HeaderValue;
+use url::Url;
+
+use crate::{
+    constants::{
+        auth::{
+            ARGON2_DEFAULT_MEMORY_COST_KIB, ARGON2_DEFAULT_TIME_COST,
+            DEFAULT_BEARER_EXPIRATION_SECONDS, DEFAULT_JWT_EXPIRATION_SECONDS,
+            DEFAULT_REFRESH_EXPIRATION_SECONDS,
+        },
+        config::{DEFAULT_BIND_ADDRESS, DEFAULT_COMPANY_NAME, MAILPIT_SMTP_HOST},
+        rate_limit::{
+            AUTH_RATE_LIMIT, AUTH_RATE_LIMIT_WINDOW_SECONDS, DEFAULT_RATE_LIMIT,
+            DEFAULT_RATE_LIMIT_WINDOW_SECONDS, OTC_VERIFY_RATE_LIMIT,
+            OTC_VERIFY_RATE_LIMIT_WINDOW_SECONDS, PASSWORD_RESET_REQUEST_RATE_LIMIT,
+            PASSWORD_RESET_REQUEST_RATE_LIMIT_WINDOW_SECONDS,
+        },
+    },
+    models::{
+        config::models::{
+            Config, EmailConfig, PasswordHashingConfig, PasswordPolicyConfig, RateLimitConfig,
+            RateLimitsConfig, SocialProviderConfig, TokenConfig,
+        },
+        password_policy::models::{CharacterSet, PasswordPolicy},
+    },
+    utils::{
+        auth::load_password_hasher,
+        breached_password::check_breached_passwords_dir,
+        client_ip::TrustedProxy,
+        database::get_database_kind,
+        password_policy::{load_password_policy, parse_character_sets},
+        social::is_known_social_provider,
+    },
+};
+
+const REDACTED: &str = "[redacted]";
+
+// Database and Redis URLs can carry a password, the user and password before the @ are left out
+fn redact_url_userinfo(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+
+    match rest[..authority_end].rfind('@') {
+        Some(userinfo_end) => format!("{}://{}{}", scheme, REDACTED, &rest[userinfo_end..]),
+        None => url.to_string(),
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind_address: DEFAULT_BIND_ADDRESS.to_string(),
+            client_base_url: String::new(),
+            company_name: DEFAULT_COMPANY_NAME.to_string(),
+            database_url: String::new(),
+            run_migrations: false,
+            redis_url: String::new(),
+            hash_secret_key: String::new(),
+            oauth_admin_emails: Vec::new(),
+            oidc_issuer_url: String::new(),
+            trusted_proxies: Vec::new(),
+            breached_passwords_dir: None,
+            email: Default::default(),
+            tokens: Default::default(),
+            jwt_keys: Default::default(),
+            password_hashing: Default::default(),
+            password_policy: Default::default(),
+            social_providers: Default::default(),
+            rate_limits: Default::default(),
+        }
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("bind_address", &self.bind_address)
+            .field("client_base_url", &self.client_base_url)
+            .field("company_name", &self.company_name)
+            .field("database_url", &redact_url_userinfo(&self.database_url))
+            .field("run_migrations", &self.run_migrations)
+            .field("redis_url", &redact_url_userinfo(&self.redis_url))
+            .field("hash_secret_key", &REDACTED)
+            .field("oauth_admin_emails", &self.oauth_admin_emails)
+            .field("oidc_issuer_url", &self.oidc_issuer_url)
+            .field("trusted_proxies", &self.trusted_proxies)
+            .field("breached_passwords_dir", &self.breached_passwords_dir)
+            .field("email", &self.email)
+            .field("tokens", &self.tokens)
+            .field("jwt_keys", &self.jwt_keys)
+            .field("password_hashing", &self.password_hashing)
+            .field("password_policy", &self.password_policy)
+            .field("social_providers", &self.social_providers)
+            .field("rate_limits", &self.rate_limits)
+            .finish()
+    }
+}
+
+impl fmt::Debug for EmailConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmailConfig")
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| REDACTED))
+            .field("smtp_host", &self.smtp_host)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SocialProviderConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SocialProviderConfig")
+            .field("client_id", &self.client_id)
+            .field("client_secret", &REDACTED)
+            .field("issuer_url", &self.issuer_url)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+impl Default for PasswordHashingConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: "argon2id".to_string(),
+            argon2_memory_cost_kib: ARGON2_DEFAULT_MEMORY_COST_KIB,
+            argon2_time_cost: ARGON2_DEFAULT_TIME_COST,
+            bcrypt_cost: bcrypt::DEFAULT_COST,
+        }
+    }
+}
+
+impl Default for PasswordPolicyConfig {
+    fn default() -> Self {
+        let default_policy = PasswordPolicy::default();
+
+        Self {
+            path: None,
+            min_length: default_policy.min_length,
+            max_length: default_policy.max_length,
+            required_character_sets: default_policy.required_character_sets,
+            allowed_character_sets: default_policy.allowed_character_sets,
+            min_score: default_policy.min_score,
+            reject_personal_info: default_policy.reject_personal_info,
+        }
+    }
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        Self {
+            bearer_expiration_seconds: DEFAULT_BEARER_EXPIRATION_SECONDS,
+            refresh_expiration_seconds: DEFAULT_REFRESH_EXPIRATION_SECONDS,
+            jwt_expiration_seconds: DEFAULT_JWT_EXPIRATION_SECONDS,
+        }
+    }
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            default: RateLimitConfig {
+                limit: DEFAULT_RATE_LIMIT,
+                window_seconds: DEFAULT_RATE_LIMIT_WINDOW_SECONDS,
+            },
+            auth: RateLimitConfig {
+                limit: AUTH_RATE_LIMIT,
+                window_seconds: AUTH_RATE_LIMIT_WINDOW_SECONDS,
+            },
+            otc_verify: RateLimitConfig {
+                limit: OTC_VERIFY_RATE_LIMIT,
+                window_seconds: OTC_VERIFY_RATE_LIMIT_WINDOW_SECONDS,
+            },
+            password_reset_request: RateLimitConfig {
+                limit: PASSWORD_RESET_REQUEST_RATE_LIMIT,
+                window_seconds: PASSWORD_RESET_REQUEST_RATE_LIMIT_WINDOW_SECONDS,
+            },
+        }
+    }
+}
+
+// Applies environment variables on top of the config, collecting every problem instead of stopping at the first
+struct ConfigOverrides<F: Fn(&str) -> Option<String>> {
+    get_variable: F,
+    errors: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> ConfigOverrides<F> {
+    // Reads NAME, or the contents of the file in NAME_FILE for Docker secrets. Empty values count as unset
+    fn get(&mut self, name: &str) -> Option<String> {
+        let file_name = format!("{}_FILE", name);
+        let value = (self.get_variable)(name).filter(|value| !value.is_empty());
+        let file_path = (self.get_variable)(&file_name).filter(|value| !value.is_empty());
+
+        match (value, file_path) {
+            (Some(_), Some(_)) => {
+                self.errors
+                    .push(format!("Only one of {} and {} can be set", name, file_name));
+
+                None
+            }
+            (Some(value), None) => Some(value),
+            (None, Some(file_path)) => match fs::read_to_string(&file_path) {
+                Ok(contents) => Some(contents.trim_end_matches(['\r', '\n']).to_string()),
+                Err(err) => {
+                    self.errors.push(format!(
+                        "Error reading {} {}: {}",
+                        file_name, file_path, err
+                    ));
+
+                    None
+                }
+            },
+            (None, None) => None,
+        }
+    }
+
+    fn set_string(&mut self, target: &mut String, name: &str) {
+        if let Some(value) = self.get(name) {
+            *target = value;
+        }
+    }
+
+    // Comma separated, surrounding whitespace and empty entries are dropped
+    fn set_list(&mut self, target: &mut Vec<String>, name: &str) {
+        if let Some(value) = self.get(name) {
+            *target = value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+    }
+
+    fn set_optional(&mut self, target: &mut Option<String>, name: &str) {
+        if let Some(value) = self.get(name) {
+            *target = Some(value);
+        }
+    }
+
+    // Comma separated, like lowercase,uppercase,number
+    fn set_character_sets(&mut self, target: &mut Vec<CharacterSet>, name: &str) {
+        if let Some(value) = self.get(name) {
+            match parse_character_sets(&value) {
+                Some(character_sets) => *target = character_sets,
+                None => self
+                    .errors
+                    .push(format!("{} has an unknown character set: {}", name, value)),
+            }
+        }
+    }
+
+    fn set_parsed<T: FromStr>(&mut self, target: &mut T, name: &str) {
+        if let Some(value) = self.get(name) {
+            match value.trim().parse() {
+                Ok(parsed) => *target = parsed,
+                Err(_) => self.errors.push(format!("{} must be a number", name)),
+            }
+        }
+    }
+
+    fn set_bool(&mut self, target: &mut bool, name: &str) {
+        if let Some(value) = self.get(name) {
+            match value.trim().to_lowercase().as_str() {
+                "true" | "1" => *target = true,
+                "false" | "0" => *target = false,
+                _ => self.errors.push(format!("{} must be true or false", name)),
+            }
+        }
+    }
+}
+
+fn validate_rate_limit(errors: &mut Vec<String>, name: &str, rate_limit: &RateLimitConfig) {
+    if rate_limit.limit <= 0 || rate_limit.window_seconds <= 0 {
+        errors.push(format!(
+            "rate_limits.{} needs a limit and window_seconds above 0",
+            name
+        ));
+    }
+}
+
+fn validate_social_provider(errors: &mut Vec<String>, name: &str, provider: &SocialProviderConfig) {
+    let variable_prefix = format!("SOCIAL_{}", name.to_uppercase());
+
+    if provider.client_id.is_empty() || provider.client_secret.is_empty() {
+        errors.push(format!(
+            "social_providers.{} needs a client_id and client_secret (or {}_CLIENT_ID and {}_CLIENT_SECRET)",
+            name, variable_prefix, variable_prefix
+        ));
+    }
+
+    if !is_known_social_provider(name) && provider.issuer_url.is_none() {
+        errors.push(format!(
+            "social_providers.{}.issuer_url (or {}_ISSUER_URL) is required",
+            name, variable_prefix
+        ));
+    }
+}
+
+// Returns every problem with the config, so they can all be fixed in one go
+pub fn validate_config(config: &Config) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let required_values = [
+        ("database_url", "DATABASE_URL", &config.database_url),
+        ("redis_url", "REDIS_URL", &config.redis_url),
+        (
+            "client_base_url",
+            "CLIENT_BASE_URL",
+            &config.client_base_url,
+        ),
+        (
+            "hash_secret_key",
+            "HASH_SECRET_KEY",
+            &config.hash_secret_key,
+        ),
+        (
+            "oidc_issuer_url",
+            "OIDC_ISSUER_URL",
+            &config.oidc_issuer_url,
+        ),
+        ("email.user", "EMAIL_USER", &config.email.user),
+        ("email.smtp_host", "SMTP_HOST", &config.email.smtp_host),
+        (
+            "jwt_keys.private_key_path",
+            "JWT_PRIVATE_KEY_PATH",
+            &config.jwt_keys.private_key_path,
+        ),
+        (
+            "jwt_keys.signing_key_id",
+            "JWT_SIGNING_KEY_ID",
+            &config.jwt_keys.signing_key_id,
+        ),
+        (
+            "jwt_keys.public_keys_dir",
+            "JWT_PUBLIC_KEYS_DIR",
+            &config.jwt_keys.public_keys_dir,
+        ),
+    ];
+
+    for (key, variable, value) in required_values {
+        if value.is_empty() {
+            errors.push(format!("{} (or {}) is required", key, variable));
+        }
+    }
+
+    if !config.database_url.is_empty() {
+        if let Err(err) = get_database_kind(&config.database_url) {
+            errors.push(err);
+        }
+    }
+
+    if config.bind_address.parse::<SocketAddr>().is_err() {
+        errors.push(format!(
+            "bind_address {} is not an IP address with a port",
+            config.bind_address
+        ));
+    }
+
+    // Used as CORS origin and in the links of emails
+    if !config.client_base_url.is_empty()
+        && (Url::parse(&config.client_base_url).is_err()
+            || config.client_base_url.parse::<HeaderValue>().is_err())
+    {
+        errors.push(format!(
+            "client_base_url {} is not a valid URL",
+            config.client_base_url
+        ));
+    }
+
+    if !config.oidc_issuer_url.is_empty() && Url::parse(&config.oidc_issuer_url).is_err() {
+        errors.push(format!(
+            "oidc_issuer_url {} is not a valid URL",
+            config.oidc_issuer_url
+        ));
+    }
+
+    for trusted_proxy in &config.trusted_proxies {
+        if TrustedProxy::parse(trusted_proxy).is_none() {
+            errors.push(format!(
+                "trusted_proxies entry {} is not an IP address or CIDR range",
+                trusted_proxy
+            ));
+        }
+    }
+
+    if config.email.smtp_host != MAILPIT_SMTP_HOST && config.email.password.is_none() {
+        errors.push("email.password (or EMAIL_PASSWORD) is required".to_string());
+    }
+
+    if config.tokens.bearer_expiration_seconds <= 0
+        || config.tokens.refresh_expiration_seconds <= 0
+        || config.tokens.jwt_expiration_seconds <= 0
+    {
+        errors.push("Token expirations need to be above 0 seconds".to_string());
+    }
+
+    if let Err(err) = load_password_hasher(&config.password_hashing) {
+        errors.push(err);
+    }
+
+    if let Err(err) = load_password_policy(&config.password_policy) {
+        errors.push(err);
+    }
+
+    // A configured dataset that can't be read would let every password through
+    if let Some(breached_passwords_dir) = &config.breached_passwords_dir {
+        if let Err(err) = check_breached_passwords_dir(Path::new(breached_passwords_dir)) {
+            errors.push(err);
+        }
+    }
+
+    for (name, provider) in &config.social_providers {
+        validate_social_provider(&mut errors, name, provider);
+    }
+
+    let rate_limits = &config.rate_limits;
+    validate_rate_limit(&mut errors, "default", &rate_limits.default);
+    validate_rate_limit(&mut errors, "auth", &rate_limits.auth);
+    validate_rate_limit(&mut errors, "otc_verify", &rate_limits.otc_verify);
+    validate_rate_limit(
+        &mut errors,
+        "password_reset_request",
+        &rate_limits.password_reset_request,
+    );
+
+    errors
+}
+
+// Starts from the TOML file in CONFIG_PATH when set, then environment variables (or their _FILE
+// variants) override single values. get_variable makes it testable without touching the environment
+pub fn load_config_from(
+    get_variable: impl Fn(&str) -> Option<String>,
+) -> Result<Config, Vec<String>> {
+    let mut config = match get_variable("CONFIG_PATH").filter(|path| !path.is_empty()) {
+        Some(config_path) => {
+            let config_toml = fs::read_to_string(&config_path)
+                .map_err(|err| vec![format!("Error reading {}: {}", config_path, err)])?;
+
+            toml::from_str(&config_toml)
+                .map_err(|err| vec![format!("Error parsing {}: {}", config_path, err)])?
+        }
+        None => Config::default(),
+    };
+
+    let mut overrides = ConfigOverrides {
+        get_variable,
+        errors: Vec::new(),
+    };
+
+    overrides.set_string(&mut config.bind_address, "BIND_ADDRESS");
+    overrides.set_string(&mut config.client_base_url, "CLIENT_BASE_URL");
+    overrides.set_string(&mut config.company_name, "COMPANY_NAME");
+    overrides.set_string(&mut config.database_url, "DATABASE_URL");
+    overrides.set_bool(&mut config.run_migrations, "RUN_MIGRATIONS");
+    overrides.set_string(&mut config.redis_url, "REDIS_URL");
+    overrides.set_string(&mut config.hash_secret_key, "HASH_SECRET_KEY");
+    overrides.set_list(&mut config.oauth_admin_emails, "OAUTH_ADMIN_EMAILS");
+    overrides.set_string(&mut config.oidc_issuer_url, "OIDC_ISSUER_URL");
+    overrides.set_list(&mut config.trusted_proxies, "TRUSTED_PROXIES");
+    overrides.set_optional(&mut config.breached_passwords_dir, "BREACHED_PASSWORDS_DIR");
+    overrides.set_string(&mut config.email.user, "EMAIL_USER");
+    overrides.set_string(&mut config.email.smtp_host, "SMTP_HOST");
+    if let Some(email_password) = overrides.get("EMAIL_PASSWORD") {
+        config.email.password = Some(email_password);
+    }
+    overrides.set_parsed(
+        &mut config.tokens.bearer_expiration_seconds,
+        "BEARER_EXPIRATION_SECONDS",
+    );
+    overrides.set_parsed(
+        &mut config.tokens.refresh_expiration_seconds,
+        "REFRESH_EXPIRATION_SECONDS",
+    );
+    overrides.set_parsed(
+        &mut config.tokens.jwt_expiration_seconds,
+        "JWT_EXPIRATION_SECONDS",
+    );
+
+    let jwt_keys = &mut config.jwt_keys;
+    overrides.set_string(&mut jwt_keys.private_key_path, "JWT_PRIVATE_KEY_PATH");
+    overrides.set_string(&mut jwt_keys.signing_key_id, "JWT_SIGNING_KEY_ID");
+    overrides.set_string(&mut jwt_keys.public_keys_dir, "JWT_PUBLIC_KEYS_DIR");
+
+    let password_hashing = &mut config.password_hashing;
+    overrides.set_string(&mut password_hashing.algorithm, "PASSWORD_HASH_ALGORITHM");
+    overrides.set_parsed(
+        &mut password_hashing.argon2_memory_cost_kib,
+        "ARGON2_MEMORY_COST",
+    );
+    overrides.set_parsed(&mut password_hashing.argon2_time_cost, "ARGON2_TIME_COST");
+    overrides.set_parsed(&mut password_hashing.bcrypt_cost, "BCRYPT_COST");
+
+    let password_policy = &mut config.password_policy;
+    overrides.set_optional(&mut password_policy.path, "PASSWORD_POLICY_PATH");
+    overrides.set_parsed(&mut password_policy.min_length, "PASSWORD_MIN_LENGTH");
+    overrides.set_parsed(&mut password_policy.max_length, "PASSWORD_MAX_LENGTH");
+    overrides.set_character_sets(
+        &mut password_policy.required_character_sets,
+        "PASSWORD_REQUIRED_CHARACTER_SETS",
+    );
+    overrides.set_character_sets(
+        &mut password_policy.allowed_character_sets,
+        "PASSWORD_ALLOWED_CHARACTER_SETS",
+    );
+    overrides.set_parsed(&mut password_policy.min_score, "PASSWORD_MIN_SCORE");
+    overrides.set_bool(
+        &mut password_policy.reject_personal_info,
+        "PASSWORD_REJECT_PERSONAL_INFO",
+    );
+
+    // SOCIAL_PROVIDERS replaces the providers of the config file, each one reads SOCIAL_<NAME>_<KEY>
+    if let Some(social_providers) = overrides.get("SOCIAL_PROVIDERS") {
+        let social_provider_names: Vec<String> = social_providers
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        config
+            .social_providers
+            .retain(|name, _| social_provider_names.contains(name));
+
+        for name in social_provider_names {
+            config.social_providers.entry(name).or_default();
+        }
+    }
+
+    for (name, provider) in config.social_providers.iter_mut() {
+        let variable_prefix = format!("SOCIAL_{}", name.to_uppercase());
+
+        overrides.set_string(
+            &mut provider.client_id,
+            &format!("{}_CLIENT_ID", variable_prefix),
+        );
+        overrides.set_string(
+            &mut provider.client_secret,
+            &format!("{}_CLIENT_SECRET", variable_prefix),
+        );
+        overrides.set_optional(
+            &mut provider.issuer_url,
+            &format!("{}_ISSUER_URL", variable_prefix),
+        );
+        overrides.set_optional(&mut provider.scopes, &format!("{}_SCOPES", variable_prefix));
+    }
+
+    let mut errors = overrides.errors;
+    errors.extend(validate_config(&config));
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(config)
+}
+
+pub fn load_config() -> Result<Config, Vec<String>> {
+    load_config_from(|name| env::var(name).ok())
+}
@@ -1,6 +1,6 @@
 use axum::{
     body::Body,
-    http::{header, HeaderValue, Response},
+    http::{header, HeaderMap, HeaderValue, Response},
 };
 use http::Request;
 
@@ -35,7 +35,12 @@ where
 }
 
 pub fn get_cookie(request: &Request<Body>, key: &str) -> Option<String> {
-    if let Some(cookie_header) = request.headers().get(header::COOKIE) {
+    get_header_cookie(request.headers(), key)
+}
+
+// For handlers that only get the headers and not the whole request
+pub fn get_header_cookie(headers: &HeaderMap, key: &str) -> Option<String> {
+    if let Some(cookie_header) = headers.get(header::COOKIE) {
         if let Ok(cookie_str) = cookie_header.to_str() {
             for cookie in cookie_str.split(';') {
                 let cookie = cookie.trim();
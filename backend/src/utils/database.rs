@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use sqlx::{
+    mysql::MySqlPoolOptions,
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    MySqlPool, PgPool, SqlitePool,
+};
+
+use crate::models::database::models::DatabaseKind;
+
+pub fn get_database_kind(database_url: &str) -> Result<DatabaseKind, String> {
+    let scheme = database_url
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .unwrap_or_default();
+
+    match scheme {
+        "mysql" | "mariadb" => Ok(DatabaseKind::MySql),
+        "postgres" | "postgresql" => Ok(DatabaseKind::Postgres),
+        "sqlite" => Ok(DatabaseKind::Sqlite),
+        _ => Err(format!(
+            "Unsupported database {}, use mysql://, postgres:// or sqlite://",
+            scheme
+        )),
+    }
+}
+
+// Where the scanner is in a query, a ? only is a placeholder in plain SQL
+enum QuerySection {
+    Sql,
+    // Until the closing quote, ' for strings and " for identifiers
+    Quoted(char),
+    LineComment,
+    BlockComment,
+}
+
+// Queries are written with ? placeholders like MySQL and SQLite, Postgres numbers them as $1, $2 and so on.
+// A ? in quotes or comments is left alone, and ?? stands for a single ?, like the jsonb ? operator of
+// a query that only runs on Postgres.
+pub fn format_postgres_query(query: &str) -> String {
+    let mut postgres_query = String::with_capacity(query.len());
+    let mut placeholder_count = 0;
+    let mut section = QuerySection::Sql;
+    let mut characters = query.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        let next_character = characters.peek().copied();
+
+        match section {
+            QuerySection::Sql => match (character, next_character) {
+                ('?', Some('?')) => {
+                    characters.next();
+                    postgres_query.push('?');
+                    continue;
+                }
+                ('?', _) => {
+                    placeholder_count += 1;
+                    postgres_query.push_str(&format!("${}", placeholder_count));
+                    continue;
+                }
+                ('\'' | '"', _) => section = QuerySection::Quoted(character),
+                ('-', Some('-')) => section = QuerySection::LineComment,
+                ('/', Some('*')) => section = QuerySection::BlockComment,
+                _ => (),
+            },
+            // A doubled quote ends the section and starts it again right away, which keeps it escaped
+            QuerySection::Quoted(quote) if character == quote => section = QuerySection::Sql,
+            QuerySection::LineComment if character == '\n' => section = QuerySection::Sql,
+            QuerySection::BlockComment if character == '*' && next_character == Some('/') => {
+                characters.next();
+                postgres_query.push_str("*/");
+                section = QuerySection::Sql;
+                continue;
+            }
+            _ => (),
+        }
+
+        postgres_query.push(character);
+    }
+
+    postgres_query
+}
+
+#[derive(Clone)]
+pub enum DatabasePool {
+    MySql(MySqlPool),
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+impl DatabasePool {
+    pub async fn connect(database_url: &str) -> Result<Self, String> {
+        let database_kind = get_database_kind(database_url)?;
+
+        let database_pool = match database_kind {
+            DatabaseKind::MySql => MySqlPoolOptions::new()
+                .connect(database_url)
+                .await
+                .map(DatabasePool::MySql),
+            DatabaseKind::Postgres => PgPoolOptions::new()
+                .connect(database_url)
+                .await
+                .map(DatabasePool::Postgres),
+            DatabaseKind::Sqlite => {
+                // Small installs start without a database file, so it's created on first start
+                let connect_options = SqliteConnectOptions::from_str(database_url)
+                    .map_err(|err| err.to_string())?
+                    .create_if_missing(true)
+                    .foreign_keys(true);
+
+                SqlitePoolOptions::new()
+                    .connect_with(connect_options)
+                    .await
+                    .map(DatabasePool::Sqlite)
+            }
+        };
+
+        database_pool.map_err(|err| err.to_string())
+    }
+
+    pub fn get_kind(&self) -> DatabaseKind {
+        match self {
+            DatabasePool::MySql(_) => DatabaseKind::MySql,
+            DatabasePool::Postgres(_) => DatabaseKind::Postgres,
+            DatabasePool::Sqlite(_) => DatabaseKind::Sqlite,
+        }
+    }
+}
+
+// Runs the same sqlx code against whichever pool is configured. The body gets the typed pool and a
+// function that turns a query with ? placeholders into the dialect of the pool. Bodies using ? go in
+// an async block, so errors stay inside the macro:
+//
+// query_database!(&self.db_pool, |pool, sql| {
+//     sqlx::query(&sql(DELETE_USER)).bind(id).execute(pool).await
+// })
+macro_rules! query_database {
+    ($db_pool:expr, |$pool:ident, $sql:ident| $body:expr) => {
+        match $db_pool {
+            $crate::utils::database::DatabasePool::MySql($pool) => {
+                let $sql = |query: &'static str| std::borrow::Cow::Borrowed(query);
+                $body
+            }
+            $crate::utils::database::DatabasePool::Postgres($pool) => {
+                let $sql = |query: &'static str| {
+                    std::borrow::Cow::<'static, str>::Owned(
+                        $crate::utils::database::format_postgres_query(query),
+                    )
+                };
+                $body
+            }
+            $crate::utils::database::DatabasePool::Sqlite($pool) => {
+                let $sql = |query: &'static str| std::borrow::Cow::Borrowed(query);
+                $body
+            }
+        }
+    };
+}
+
+pub(crate) use query_database;
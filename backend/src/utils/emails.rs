@@ -1,109 +1,64 @@
+use crate::constants::email::{CODE_IMAGE_PATH, SUCCESS_IMAGE_PATH};
+use crate::models::general::AppState;
 use crate::models::translations::Translations;
 use crate::templates::otc::{VERIFICATION_CODE_SUCCESS_TEMPLATE, VERIFICATION_CODE_TEMPLATE};
 use crate::templates::user::PASSWORD_RESET_CODE_TEMPLATE;
-use crate::utils::env::get_environment_variable;
+use crate::traits::mailer::OutgoingEmail;
+use crate::utils::errors::ServiceError;
 use crate::utils::templates::generate_template;
 use crate::utils::translations::get_translation_by_key;
-use axum::http::StatusCode;
-use lettre::message::{header, MultiPart, SinglePart};
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::transport::smtp::client::Tls;
-use lettre::{Message, SmtpTransport, Transport};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-
-pub async fn send_email_with_template(
-    recipient: &str,
-    subject: &str,
-    body: &str,
-    image_data: Vec<u8>,
-) -> Result<(), StatusCode> {
-    let env_email = match get_environment_variable("EMAIL_USER") {
-        Ok(env_email) => env_email,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
-
-    let env_smtp_host = match get_environment_variable("SMTP_HOST") {
-        Ok(env_email) => env_email,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
-
-    let from_header = match env_email.parse() {
-        Ok(header) => header,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
-
-    let image_contenttype_header = match header::ContentType::parse("image/png") {
-        Ok(header) => header,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
-
-    let email = Message::builder()
-        .from(from_header)
-        .reply_to(
-            recipient
-                .parse()
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
-        )
-        .to(recipient
-            .parse()
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?)
-        .subject(subject)
-        .multipart(
-            MultiPart::mixed()
-                .singlepart(
-                    SinglePart::builder()
-                        .header(header::ContentType::TEXT_HTML)
-                        .body(body.to_string()),
-                )
-                .singlepart(
-                    SinglePart::builder()
-                        .header(image_contenttype_header)
-                        .header(header::ContentDisposition::inline())
-                        .header(header::ContentId::from("cid_image".to_string()))
-                        .body(image_data),
-                ),
-        )
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let mailer = if env_smtp_host == "mailpit" {
-        SmtpTransport::relay(&env_smtp_host)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .port(1025)
-            .tls(Tls::None)
-            .build()
-    } else {
-        let env_password = match get_environment_variable("EMAIL_PASSWORD") {
-            Ok(env_password) => env_password,
-            Err(_) => return Err(StatusCode::BAD_REQUEST),
-        };
-
-        let creds = Credentials::new(env_email.clone(), env_password);
-
-        SmtpTransport::relay(&env_smtp_host)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .credentials(creds)
-            .build()
-    };
-
-    match mailer.send(&email) {
-        Ok(_) => Ok(()),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
-}
+use url::form_urlencoded;
 
 pub async fn send_otc_email(
+    state: &AppState,
+    translations: &Translations,
+    otc_type: &str, // ? can be confirm_account, update_account, delete_account, disable_two_factor, regenerate_recovery_codes or login
+    otc_code: &str,
+    email: &str,
+) -> Result<(), ServiceError> {
+    let client_base_url = &state.config.client_base_url;
+
+    // Codes are scoped to the email they were sent to, so the link carries it along
+    let otc_link = format!(
+        "{}/otc?otc={}&email={}",
+        client_base_url,
+        otc_code,
+        form_urlencoded::byte_serialize(email.as_bytes()).collect::<String>()
+    );
+
+    send_otc_email_with_link(state, translations, otc_type, otc_code, &otc_link, email).await
+}
+
+// Login codes only work from this link, the signature ties the code to the email it was sent to
+pub async fn send_magic_link_email(
+    state: &AppState,
     translations: &Translations,
-    otc_type: &str, // ? can be confirm_account, update_account or delete_account
     otc_code: &str,
+    signature: &str,
     email: &str,
-) -> Result<(), StatusCode> {
-    let client_base_url = match get_environment_variable("CLIENT_BASE_URL") {
-        Ok(client_base_url) => client_base_url,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
+) -> Result<(), ServiceError> {
+    let client_base_url = &state.config.client_base_url;
+
+    let otc_link = format!(
+        "{}/otc?otc={}&email={}&signature={}",
+        client_base_url,
+        otc_code,
+        form_urlencoded::byte_serialize(email.as_bytes()).collect::<String>(),
+        signature
+    );
+
+    send_otc_email_with_link(state, translations, "login", otc_code, &otc_link, email).await
+}
 
+async fn send_otc_email_with_link(
+    state: &AppState,
+    translations: &Translations,
+    otc_type: &str,
+    otc_code: &str,
+    otc_link: &str,
+    email: &str,
+) -> Result<(), ServiceError> {
     let mut template_variables: HashMap<&str, &str> = HashMap::new();
 
     let template_name = get_translation_by_key(
@@ -131,41 +86,39 @@ pub async fn send_otc_email(
         &format!("auth.emails.otc.{}.footer_note", &otc_type),
     );
 
-    let otc_link = format!("{}/otc?otc={}", client_base_url, otc_code);
-
     template_variables.insert("header_title", &header);
     template_variables.insert("code_description", &code_description);
     template_variables.insert("link_title", &link_description);
     template_variables.insert("footer_note", &footer_note);
     template_variables.insert("otc", otc_code);
-    template_variables.insert("otc_link", &otc_link);
+    template_variables.insert("otc_link", otc_link);
 
     let email_body = generate_template(
+        state.config,
         VERIFICATION_CODE_TEMPLATE,
         &template_name,
         template_variables,
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let mut image_file =
-        File::open("/app/src/static/images/code_image.png").expect("Image file not found");
-
-    let mut image_data = Vec::new();
-
-    image_file
-        .read_to_end(&mut image_data)
-        .expect("Failed to read image");
-
-    send_email_with_template(&email, &subject, &email_body, image_data).await?;
+    )?;
+
+    state
+        .mailer
+        .send_email(OutgoingEmail {
+            recipient: email.to_string(),
+            subject,
+            body: email_body,
+            image_path: CODE_IMAGE_PATH,
+        })
+        .await?;
 
     Ok(())
 }
 
 pub async fn send_otc_success_email(
+    state: &AppState,
     translations: &Translations,
-    otc_type: &str, // ? can be confirm_account, update_account or delete_account
+    otc_type: &str, // ? can be confirm_account, update_account, delete_account, disable_two_factor, regenerate_recovery_codes or login
     email: &str,
-) -> Result<(), StatusCode> {
+) -> Result<(), ServiceError> {
     let mut template_variables: HashMap<&str, &str> = HashMap::new();
 
     let template_name = get_translation_by_key(
@@ -192,35 +145,32 @@ pub async fn send_otc_success_email(
     template_variables.insert("footer_note", &footer_note);
 
     let email_body = generate_template(
+        state.config,
         VERIFICATION_CODE_SUCCESS_TEMPLATE,
         &template_name,
         template_variables,
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let mut image_file =
-        File::open("/app/src/static/images/success_image.png").expect("Image file not found");
-
-    let mut image_data = Vec::new();
-
-    image_file
-        .read_to_end(&mut image_data)
-        .expect("Failed to read image");
-
-    send_email_with_template(&email, &subject, &email_body, image_data).await?;
+    )?;
+
+    state
+        .mailer
+        .send_email(OutgoingEmail {
+            recipient: email.to_string(),
+            subject,
+            body: email_body,
+            image_path: SUCCESS_IMAGE_PATH,
+        })
+        .await?;
 
     Ok(())
 }
 
 pub async fn send_password_reset_email(
+    state: &AppState,
     translations: &Translations,
     reset_password_code: &str,
     email: &str,
-) -> Result<(), StatusCode> {
-    let client_base_url = match get_environment_variable("CLIENT_BASE_URL") {
-        Ok(client_base_url) => client_base_url,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
+) -> Result<(), ServiceError> {
+    let client_base_url = &state.config.client_base_url;
 
     let mut template_variables: HashMap<&str, &str> = HashMap::new();
 
@@ -253,22 +203,21 @@ pub async fn send_password_reset_email(
     template_variables.insert("password_reset_link", &password_reset_link);
 
     let email_body = generate_template(
+        state.config,
         PASSWORD_RESET_CODE_TEMPLATE,
         &template_name,
         template_variables,
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let mut image_file =
-        File::open("/app/src/static/images/code_image.png").expect("Image file not found");
-
-    let mut image_data = Vec::new();
-
-    image_file
-        .read_to_end(&mut image_data)
-        .expect("Failed to read image");
-
-    send_email_with_template(&email, &subject, &email_body, image_data).await?;
+    )?;
+
+    state
+        .mailer
+        .send_email(OutgoingEmail {
+            recipient: email.to_string(),
+            subject,
+            body: email_body,
+            image_path: CODE_IMAGE_PATH,
+        })
+        .await?;
 
     Ok(())
 }
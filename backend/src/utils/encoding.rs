@@ -0,0 +1,14 @@
+use axum::http::StatusCode;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+// Unpadded base64url, used for tokens, JWKs and WebAuthn values
+pub fn encode_base64url(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Padding is accepted as well, some clients send it
+pub fn decode_base64url(value: &str) -> Result<Vec<u8>, StatusCode> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| StatusCode::BAD_REQUEST)
+}
@@ -0,0 +1,166 @@
+use std::{error::Error, fmt};
+
+use http::StatusCode;
+
+// What went wrong below the services, keeping the underlying error so the cause can be logged
+#[derive(Debug)]
+pub enum ServiceError {
+    Database(sqlx::Error),
+    Redis(redis::RedisError),
+    Email(Box<dyn Error + Send + Sync>),
+    Template(tera::Error),
+    // Signing a token failed, like with a broken key
+    JwtEncode(jsonwebtoken::errors::Error),
+    Serialization(serde_json::Error),
+    Io(std::io::Error),
+    Configuration(String),
+    Hashing(String),
+    // Expected outcomes, carrying nothing to log
+    NotFound,
+    Unauthorized,
+    // A token that is malformed, expired or signed with an unknown key
+    JwtDecode(jsonwebtoken::errors::Error),
+    Validation(&'static str),
+}
+
+impl ServiceError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ServiceError::NotFound => StatusCode::NOT_FOUND,
+            ServiceError::JwtDecode(_) | ServiceError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ServiceError::Validation(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn translation_key(&self) -> &'static str {
+        match self {
+            ServiceError::Email(_) => "general.errors.email_not_sent",
+            ServiceError::NotFound => "general.errors.not_found",
+            ServiceError::JwtDecode(_) | ServiceError::Unauthorized => {
+                "general.errors.unauthorized"
+            }
+            ServiceError::Validation(translation_key) => translation_key,
+            _ => "general.errors.internal_error",
+        }
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        self.status_code().is_server_error()
+    }
+
+    // Server errors are logged with their whole source chain, the response only gets the translated message
+    pub fn log(&self) {
+        if !self.is_server_error() {
+            return;
+        }
+
+        let mut message = self.to_string();
+        let mut source = self.source();
+
+        while let Some(cause) = source {
+            message.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+
+        eprintln!("Service error: {}", message);
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::Database(_) => write!(f, "database query failed"),
+            ServiceError::Redis(_) => write!(f, "redis command failed"),
+            ServiceError::Email(_) => write!(f, "sending email failed"),
+            ServiceError::Template(_) => write!(f, "rendering template failed"),
+            ServiceError::JwtEncode(_) => write!(f, "signing jwt failed"),
+            ServiceError::Serialization(_) => write!(f, "(de)serializing json failed"),
+            ServiceError::Io(_) => write!(f, "reading file failed"),
+            ServiceError::Configuration(message) => write!(f, "invalid configuration: {}", message),
+            ServiceError::Hashing(message) => write!(f, "hashing failed: {}", message),
+            ServiceError::NotFound => write!(f, "not found"),
+            ServiceError::Unauthorized => write!(f, "unauthorized"),
+            ServiceError::JwtDecode(_) => write!(f, "invalid jwt"),
+            ServiceError::Validation(translation_key) => {
+                write!(f, "validation failed: {}", translation_key)
+            }
+        }
+    }
+}
+
+impl Error for ServiceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServiceError::Database(error) => Some(error),
+            ServiceError::Redis(error) => Some(error),
+            ServiceError::Email(error) => Some(error.as_ref()),
+            ServiceError::Template(error) => Some(error),
+            ServiceError::JwtEncode(error) => Some(error),
+            ServiceError::JwtDecode(error) => Some(error),
+            ServiceError::Serialization(error) => Some(error),
+            ServiceError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for ServiceError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => ServiceError::NotFound,
+            error => ServiceError::Database(error),
+        }
+    }
+}
+
+impl From<redis::RedisError> for ServiceError {
+    fn from(error: redis::RedisError) -> Self {
+        ServiceError::Redis(error)
+    }
+}
+
+impl From<lettre::error::Error> for ServiceError {
+    fn from(error: lettre::error::Error) -> Self {
+        ServiceError::Email(Box::new(error))
+    }
+}
+
+impl From<lettre::address::AddressError> for ServiceError {
+    fn from(error: lettre::address::AddressError) -> Self {
+        ServiceError::Email(Box::new(error))
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for ServiceError {
+    fn from(error: lettre::transport::smtp::Error) -> Self {
+        ServiceError::Email(Box::new(error))
+    }
+}
+
+impl From<tera::Error> for ServiceError {
+    fn from(error: tera::Error) -> Self {
+        ServiceError::Template(error)
+    }
+}
+
+impl From<serde_json::Error> for ServiceError {
+    fn from(error: serde_json::Error) -> Self {
+        ServiceError::Serialization(error)
+    }
+}
+
+impl From<std::io::Error> for ServiceError {
+    fn from(error: std::io::Error) -> Self {
+        ServiceError::Io(error)
+    }
+}
+
+// Lets helpers that still return a bare StatusCode call the typed ones with ?
+impl From<ServiceError> for StatusCode {
+    fn from(error: ServiceError) -> Self {
+        error.log();
+
+        error.status_code()
+    }
+}
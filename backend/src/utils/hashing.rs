@@ -0,0 +1,27 @@
+use crate::{models::config::models::Config, utils::errors::ServiceError};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+// HMAC-SHA256 keyed with a server secret, for high-entropy values that need a fast lookup by hash
+pub fn hash_with_secret(config: &Config, value: &str) -> Result<String, ServiceError> {
+    let hash_secret = &config.hash_secret_key;
+
+    if hash_secret.is_empty() {
+        return Err(ServiceError::Configuration(
+            "hash_secret_key is not set".to_string(),
+        ));
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(hash_secret.as_bytes())
+        .map_err(|error| ServiceError::Hashing(error.to_string()))?;
+    mac.update(value.as_bytes());
+
+    let hash = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Ok(hash)
+}
@@ -0,0 +1,20 @@
+use std::{sync::OnceLock, time::Duration};
+
+use crate::constants::social::SOCIAL_LOGIN_HTTP_TIMEOUT_SECONDS;
+
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+// Shared client for calls to external services, so connections are pooled across requests
+pub fn get_http_client() -> &'static reqwest::Client {
+    HTTP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(SOCIAL_LOGIN_HTTP_TIMEOUT_SECONDS))
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .unwrap_or_default()
+    })
+}
@@ -1,86 +1,271 @@
+use crate::constants::auth::{JWT_ID_LENGTH, REFRESH_TOKEN_BYTES};
+use crate::constants::oauth::OAUTH_ACCESS_TOKEN_EXPIRATION_SECONDS;
 use crate::models::auth::models::JwtClaims;
-use crate::utils::env::get_environment_variable;
-use axum::http::StatusCode;
+use crate::models::config::models::Config;
+use crate::models::general::AppState;
+use crate::utils::encoding::encode_base64url;
+use crate::utils::errors::ServiceError;
+use crate::utils::hashing::hash_with_secret;
+use crate::utils::jwt_keys::{get_jwt_keys, JwtKeys};
+use crate::utils::redis::{get_token, set_token};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
-
-pub fn encode_jwt(id: &i32, name: &str, email: &str) -> Result<String, StatusCode> {
-    let jwt_secret = match get_environment_variable("JWT_SECRET_KEY") {
-        Ok(jwt_secret) => jwt_secret,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
+use jsonwebtoken::{decode, decode_header, encode, Header, TokenData, Validation};
+use rand::{distributions::Alphanumeric, rngs::OsRng, thread_rng, Rng, RngCore};
+use serde::Serialize;
 
+fn build_jwt_claims(id: &i32, name: &str, email: &str, expiration_seconds: i64) -> JwtClaims {
     let now = Utc::now();
-    let expire = Duration::hours(24);
+    let expire = Duration::seconds(expiration_seconds);
     let exp = (now + expire).timestamp() as usize;
     let iat = now.timestamp() as usize;
 
-    let claims = JwtClaims {
+    JwtClaims {
         exp,
         iat,
+        iat_ms: Some(now.timestamp_millis()),
         id: *id,
         name: name.to_string(),
         email: email.to_string(),
-    };
+        sid: None,
+        jti: generate_jwt_id(),
+        aud: None,
+        scope: None,
+    }
+}
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(jwt_secret.as_ref()),
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+pub fn encode_jwt(
+    config: &Config,
+    id: &i32,
+    name: &str,
+    email: &str,
+    session_id: Option<&str>,
+) -> Result<String, ServiceError> {
+    let jwt_keys = get_jwt_keys()?;
+
+    let mut claims = build_jwt_claims(id, name, email, config.tokens.jwt_expiration_seconds);
+    claims.sid = session_id.map(str::to_string);
+
+    sign_jwt_claims(jwt_keys, &claims)
 }
 
-pub fn decode_jwt(jwt: &str) -> Result<TokenData<JwtClaims>, StatusCode> {
-    let jwt_secret = match get_environment_variable("JWT_SECRET_KEY") {
-        Ok(jwt_secret) => jwt_secret,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
+// Access tokens for OAuth clients carry the client as audience, which our own session checks reject
+pub fn encode_access_token(
+    id: &i32,
+    name: &str,
+    email: &str,
+    client_id: &str,
+    scope: &str,
+) -> Result<String, ServiceError> {
+    let jwt_keys = get_jwt_keys()?;
 
-    let result: Result<TokenData<JwtClaims>, StatusCode> = decode(
-        &jwt,
-        &DecodingKey::from_secret(jwt_secret.as_ref()),
-        &Validation::default(),
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+    let mut claims = build_jwt_claims(id, name, email, OAUTH_ACCESS_TOKEN_EXPIRATION_SECONDS);
+    claims.aud = Some(client_id.to_string());
+    claims.scope = Some(scope.to_string());
+
+    sign_jwt_claims(jwt_keys, &claims)
+}
+
+// Signs with the current signing key and names it in the kid header, so verifiers know which key to use
+pub fn sign_jwt_claims<T: Serialize>(
+    jwt_keys: &JwtKeys,
+    claims: &T,
+) -> Result<String, ServiceError> {
+    let mut header = Header::new(jwt_keys.signing_key.algorithm);
+    header.kid = Some(jwt_keys.signing_key.kid.clone());
+
+    let jwt = encode(&header, claims, &jwt_keys.signing_key.encoding_key)
+        .map_err(ServiceError::JwtEncode)?;
+
+    Ok(jwt)
+}
+
+pub fn decode_jwt(jwt: &str) -> Result<TokenData<JwtClaims>, ServiceError> {
+    let jwt_keys = get_jwt_keys()?;
 
-    result
+    decode_jwt_with_keys(jwt_keys, jwt)
 }
 
-pub fn verify_jwt(jwt: &str, expected_id: &i32) -> Result<JwtClaims, StatusCode> {
-    let jwt_secret = match get_environment_variable("JWT_SECRET_KEY") {
-        Ok(jwt_secret) => jwt_secret,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
+// Who a token has to be issued to, session tokens have no audience while access tokens name their client
+enum Audience<'a> {
+    Session,
+    AnyClient,
+    Client(&'a str),
+}
+
+// Only accepts tokens whose kid is one of the active verification keys, with that key's algorithm
+fn decode_jwt_with_validation(
+    jwt_keys: &JwtKeys,
+    jwt: &str,
+    audience: Audience,
+) -> Result<TokenData<JwtClaims>, ServiceError> {
+    let header = decode_header(jwt).map_err(ServiceError::JwtDecode)?;
+
+    let verification_key = header
+        .kid
+        .as_deref()
+        .and_then(|kid| jwt_keys.get_verification_key(kid))
+        .ok_or(ServiceError::Unauthorized)?;
+
+    let mut validation = Validation::new(verification_key.algorithm);
+
+    match audience {
+        Audience::Session => {}
+        Audience::AnyClient => {
+            validation.validate_aud = false;
+            validation.set_required_spec_claims(&["exp", "aud"]);
+        }
+        Audience::Client(client_id) => {
+            validation.set_audience(&[client_id]);
+            validation.set_required_spec_claims(&["exp", "aud"]);
+        }
+    }
+
+    let token_data = decode::<JwtClaims>(jwt, &verification_key.decoding_key, &validation)
+        .map_err(ServiceError::JwtDecode)?;
+
+    Ok(token_data)
+}
+
+// Session tokens never have an audience, so OAuth access tokens are rejected here
+pub fn decode_jwt_with_keys(
+    jwt_keys: &JwtKeys,
+    jwt: &str,
+) -> Result<TokenData<JwtClaims>, ServiceError> {
+    decode_jwt_with_validation(jwt_keys, jwt, Audience::Session)
+}
+
+// Without a client id, an access token issued to any client is accepted
+pub fn decode_access_token_with_keys(
+    jwt_keys: &JwtKeys,
+    jwt: &str,
+    client_id: Option<&str>,
+) -> Result<TokenData<JwtClaims>, ServiceError> {
+    let audience = match client_id {
+        Some(client_id) => Audience::Client(client_id),
+        None => Audience::AnyClient,
     };
 
-    let token_data = decode::<JwtClaims>(
-        jwt,
-        &DecodingKey::from_secret(jwt_secret.as_ref()),
-        &Validation::default(),
-    )
-    .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    decode_jwt_with_validation(jwt_keys, jwt, audience)
+}
+
+pub fn decode_access_token(
+    jwt: &str,
+    client_id: Option<&str>,
+) -> Result<TokenData<JwtClaims>, ServiceError> {
+    let jwt_keys = get_jwt_keys()?;
+
+    decode_access_token_with_keys(jwt_keys, jwt, client_id)
+}
+
+pub fn verify_jwt(jwt: &str, expected_id: &i32) -> Result<JwtClaims, ServiceError> {
+    let token_data = decode_jwt(jwt)?;
 
-    if token_data.claims.id != expected_id.clone() {
-        return Err(StatusCode::UNAUTHORIZED);
+    if token_data.claims.id != *expected_id {
+        return Err(ServiceError::Unauthorized);
     }
 
     Ok(token_data.claims)
 }
 
 pub fn generate_refresh_token() -> String {
-    let token: String = thread_rng()
+    let mut token = [0u8; REFRESH_TOKEN_BYTES];
+    OsRng.fill_bytes(&mut token);
+
+    encode_base64url(&token)
+}
+
+// Only a keyed hash of the refresh token is stored, so reading Redis isn't enough to hijack a session
+pub fn format_refresh_token_key(
+    config: &Config,
+    refresh_token_value: &str,
+) -> Result<String, ServiceError> {
+    let token_hash = hash_with_secret(config, &format!("refresh:{}", refresh_token_value))?;
+
+    Ok(format!("refresh:{}", token_hash))
+}
+
+// Refresh tokens that were already exchanged keep a marker, so replaying one can be detected
+pub fn format_rotated_refresh_token_key(
+    config: &Config,
+    refresh_token_value: &str,
+) -> Result<String, ServiceError> {
+    let token_hash = hash_with_secret(config, &format!("refresh:{}", refresh_token_value))?;
+
+    Ok(format!("refresh-rotated:{}", token_hash))
+}
+
+pub fn generate_jwt_id() -> String {
+    let jwt_id: String = thread_rng()
         .sample_iter(&Alphanumeric)
-        .take(12)
+        .take(JWT_ID_LENGTH)
         .map(char::from)
-        .map(|c| c.to_ascii_uppercase())
         .collect();
 
-    token
+    jwt_id
+}
+
+pub fn format_revoked_jwt_key(jwt_id: &str) -> String {
+    let token_key = format!("jwt-revoked:{}", jwt_id);
+
+    token_key
 }
 
-pub fn format_refresh_token_key(refresh_token_value: &str) -> String {
-    let token_key = format!("refresh:{}", refresh_token_value);
+pub fn format_jwt_watermark_key(user_id: &i32) -> String {
+    let token_key = format!("jwt-not-before:{}", user_id);
 
     token_key
 }
+
+// Denylists a single JWT until it would have expired anyway
+pub async fn revoke_jwt(state: &AppState, claims: &JwtClaims) -> Result<(), ServiceError> {
+    let remaining_seconds = claims.exp as i64 - Utc::now().timestamp();
+
+    // Tokens issued before the jti claim existed can't be denylisted on their own, they expire soon enough
+    if remaining_seconds <= 0 || claims.jti.is_empty() {
+        return Ok(());
+    }
+
+    set_token(
+        state,
+        &format_revoked_jwt_key(&claims.jti),
+        &claims.id.to_string(),
+        remaining_seconds as i32,
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Invalidates every JWT of a user issued up to now, for when the credentials or the account itself change
+pub async fn revoke_user_jwts(state: &AppState, user_id: &i32) -> Result<(), ServiceError> {
+    set_token(
+        state,
+        &format_jwt_watermark_key(user_id),
+        &Utc::now().timestamp_millis().to_string(),
+        state.config.tokens.jwt_expiration_seconds as i32,
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn is_jwt_revoked(state: &AppState, claims: &JwtClaims) -> Result<bool, ServiceError> {
+    let is_denylisted = !claims.jti.is_empty()
+        && get_token(state, &format_revoked_jwt_key(&claims.jti))
+            .await?
+            .is_some();
+
+    if is_denylisted {
+        return Ok(true);
+    }
+
+    let watermark: Option<i64> = get_token(state, &format_jwt_watermark_key(&claims.id))
+        .await?
+        .and_then(|timestamp| timestamp.parse().ok());
+
+    // Older tokens only have iat, they count from the start of their second so the same second is revoked too
+    let issued_at = claims.iat_ms.unwrap_or(claims.iat as i64 * 1000);
+
+    // A token issued right after the revocation, like the new session of a password change, stays valid
+    Ok(watermark.is_some_and(|not_before| issued_at < not_before))
+}
@@ -0,0 +1,210 @@
+use std::{fs, path::Path, sync::OnceLock};
+
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use rsa::{pkcs1::DecodeRsaPublicKey, traits::PublicKeyParts, RsaPublicKey};
+use spki::{der::DecodePem, ObjectIdentifier, SubjectPublicKeyInfoOwned};
+
+use crate::{
+    models::{
+        config::models::JwtKeysConfig,
+        jwks::models::{Jwk, JwkSet},
+    },
+    utils::{encoding::encode_base64url, errors::ServiceError},
+};
+
+// Algorithm identifiers of the supported public keys, see RFC 8017 and RFC 8410
+const RSA_ENCRYPTION_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+const PUBLIC_KEY_EXTENSION: &str = "pem";
+
+pub struct JwtSigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding_key: EncodingKey,
+}
+
+pub struct JwtVerificationKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub decoding_key: DecodingKey,
+    pub jwk: Jwk,
+}
+
+// The current signing key and every key tokens may still be signed with, so keys can be rotated without logging anyone out
+pub struct JwtKeys {
+    pub signing_key: JwtSigningKey,
+    pub verification_keys: Vec<JwtVerificationKey>,
+}
+
+impl JwtKeys {
+    pub fn get_verification_key(&self, kid: &str) -> Option<&JwtVerificationKey> {
+        self.verification_keys.iter().find(|key| key.kid == kid)
+    }
+
+    pub fn get_jwk_set(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .verification_keys
+                .iter()
+                .map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+}
+
+static JWT_KEYS: OnceLock<JwtKeys> = OnceLock::new();
+
+// Parses an SPKI public key PEM into a verification key, the algorithm follows from the key type
+pub fn parse_public_key_pem(kid: &str, pem: &str) -> Result<JwtVerificationKey, String> {
+    let public_key = SubjectPublicKeyInfoOwned::from_pem(pem)
+        .map_err(|err| format!("Invalid public key {}: {}", kid, err))?;
+
+    let key_bytes = public_key.subject_public_key.raw_bytes();
+
+    if public_key.algorithm.oid == RSA_ENCRYPTION_OID {
+        let rsa_public_key = RsaPublicKey::from_pkcs1_der(key_bytes)
+            .map_err(|err| format!("Invalid RSA public key {}: {}", kid, err))?;
+
+        let n = rsa_public_key.n().to_bytes_be();
+        let e = rsa_public_key.e().to_bytes_be();
+
+        return Ok(JwtVerificationKey {
+            kid: kid.to_string(),
+            algorithm: Algorithm::RS256,
+            decoding_key: DecodingKey::from_rsa_raw_components(&n, &e),
+            jwk: Jwk {
+                kty: "RSA".to_string(),
+                kid: kid.to_string(),
+                alg: "RS256".to_string(),
+                key_use: "sig".to_string(),
+                n: Some(encode_base64url(&n)),
+                e: Some(encode_base64url(&e)),
+                crv: None,
+                x: None,
+            },
+        });
+    }
+
+    if public_key.algorithm.oid == ED25519_OID {
+        return Ok(JwtVerificationKey {
+            kid: kid.to_string(),
+            algorithm: Algorithm::EdDSA,
+            decoding_key: DecodingKey::from_ed_der(key_bytes),
+            jwk: Jwk {
+                kty: "OKP".to_string(),
+                kid: kid.to_string(),
+                alg: "EdDSA".to_string(),
+                key_use: "sig".to_string(),
+                n: None,
+                e: None,
+                crv: Some("Ed25519".to_string()),
+                x: Some(encode_base64url(key_bytes)),
+            },
+        });
+    }
+
+    Err(format!(
+        "Unsupported public key {}, only RSA and Ed25519 keys can be used",
+        kid
+    ))
+}
+
+// Every <kid>.pem file in the directory is an active verification key
+pub fn load_verification_keys(public_keys_dir: &Path) -> Result<Vec<JwtVerificationKey>, String> {
+    let entries = fs::read_dir(public_keys_dir).map_err(|err| {
+        format!(
+            "Error reading JWT public keys from {}: {}",
+            public_keys_dir.display(),
+            err
+        )
+    })?;
+
+    let mut verification_keys = Vec::new();
+
+    for entry in entries {
+        let path = entry.map_err(|err| err.to_string())?.path();
+
+        if path.extension().and_then(|extension| extension.to_str()) != Some(PUBLIC_KEY_EXTENSION) {
+            continue;
+        }
+
+        let kid = match path.file_stem().and_then(|file_stem| file_stem.to_str()) {
+            Some(kid) => kid.to_string(),
+            None => continue,
+        };
+
+        let pem = fs::read_to_string(&path)
+            .map_err(|err| format!("Error reading {}: {}", path.display(), err))?;
+
+        verification_keys.push(parse_public_key_pem(&kid, &pem)?);
+    }
+
+    verification_keys.sort_by(|a, b| a.kid.cmp(&b.kid));
+
+    Ok(verification_keys)
+}
+
+pub fn load_jwt_keys(
+    private_key_path: &Path,
+    signing_kid: &str,
+    public_keys_dir: &Path,
+) -> Result<JwtKeys, String> {
+    let verification_keys = load_verification_keys(public_keys_dir)?;
+
+    let algorithm = verification_keys
+        .iter()
+        .find(|key| key.kid == signing_kid)
+        .map(|key| key.algorithm)
+        .ok_or_else(|| {
+            format!(
+                "Missing public key {}.{} for the signing key",
+                signing_kid, PUBLIC_KEY_EXTENSION
+            )
+        })?;
+
+    let private_key_pem = fs::read(private_key_path).map_err(|err| {
+        format!(
+            "Error reading JWT private key from {}: {}",
+            private_key_path.display(),
+            err
+        )
+    })?;
+
+    let encoding_key = match algorithm {
+        Algorithm::EdDSA => EncodingKey::from_ed_pem(&private_key_pem),
+        _ => EncodingKey::from_rsa_pem(&private_key_pem),
+    }
+    .map_err(|err| format!("Invalid JWT private key: {}", err))?;
+
+    Ok(JwtKeys {
+        signing_key: JwtSigningKey {
+            kid: signing_kid.to_string(),
+            algorithm,
+            encoding_key,
+        },
+        verification_keys,
+    })
+}
+
+// Loads the keys once at startup, so a misconfiguration stops the server instead of failing every request
+pub fn init_jwt_keys(config: &JwtKeysConfig) -> Result<(), String> {
+    if JWT_KEYS.get().is_some() {
+        return Ok(());
+    }
+
+    let jwt_keys = load_jwt_keys(
+        Path::new(&config.private_key_path),
+        &config.signing_key_id,
+        Path::new(&config.public_keys_dir),
+    )?;
+    let _ = JWT_KEYS.set(jwt_keys);
+
+    Ok(())
+}
+
+pub fn get_jwt_keys() -> Result<&'static JwtKeys, ServiceError> {
+    JWT_KEYS
+        .get()
+        .ok_or_else(|| ServiceError::Configuration("JWT keys are not loaded".to_string()))
+}
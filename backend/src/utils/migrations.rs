@@ -0,0 +1,161 @@
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    Database, Pool,
+};
+
+use crate::{
+    models::{
+        database::models::DatabaseKind,
+        migration::models::{ApiCommand, MigrationInfo, MigrationStatus},
+    },
+    utils::database::{query_database, DatabasePool},
+};
+
+// Embedded at compile time, so the binary carries the schema it was built for
+static MYSQL_MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+pub fn get_migrator(database_kind: DatabaseKind) -> &'static Migrator {
+    match database_kind {
+        DatabaseKind::MySql => &MYSQL_MIGRATOR,
+        DatabaseKind::Postgres => &POSTGRES_MIGRATOR,
+        DatabaseKind::Sqlite => &SQLITE_MIGRATOR,
+    }
+}
+
+// `api` serves the API, `api migrate up` applies pending migrations and `api migrate status` lists them
+pub fn parse_api_command(args: &[String]) -> Result<ApiCommand, String> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match args.as_slice() {
+        [] => Ok(ApiCommand::Serve),
+        ["migrate", "up"] => Ok(ApiCommand::MigrateUp),
+        ["migrate", "status"] => Ok(ApiCommand::MigrateStatus),
+        _ => Err(format!(
+            "Unknown command `{}`, use `api`, `api migrate up` or `api migrate status`",
+            args.join(" ")
+        )),
+    }
+}
+
+pub fn get_migration_status_from(migrator: &Migrator, applied_versions: &[i64]) -> MigrationStatus {
+    let migrations = migrator
+        .iter()
+        .map(|migration| MigrationInfo {
+            version: migration.version,
+            description: migration.description.to_string(),
+            is_applied: applied_versions.contains(&migration.version),
+        })
+        .collect::<Vec<_>>();
+
+    let unknown_versions = applied_versions
+        .iter()
+        .filter(|version| {
+            !migrations
+                .iter()
+                .any(|migration| migration.version == **version)
+        })
+        .copied()
+        .collect();
+
+    MigrationStatus {
+        migrations,
+        unknown_versions,
+    }
+}
+
+// An older binary would run against columns and tables it doesn't know, so it refuses to start
+pub fn check_schema_version(status: &MigrationStatus) -> Result<(), String> {
+    let latest_known_version = status
+        .migrations
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or_default();
+
+    let newer_version = status
+        .unknown_versions
+        .iter()
+        .copied()
+        .filter(|version| *version > latest_known_version)
+        .max();
+
+    match newer_version {
+        Some(version) => Err(format!(
+            "Database schema is at version {}, newer than version {} this binary knows about",
+            version, latest_known_version
+        )),
+        None => Ok(()),
+    }
+}
+
+pub fn format_migration_status(status: &MigrationStatus) -> String {
+    let mut lines = status
+        .migrations
+        .iter()
+        .map(|migration| {
+            let state = if migration.is_applied {
+                "applied"
+            } else {
+                "pending"
+            };
+
+            format!("{} {} {}", state, migration.version, migration.description)
+        })
+        .collect::<Vec<_>>();
+
+    lines.extend(
+        status
+            .unknown_versions
+            .iter()
+            .map(|version| format!("unknown {}", version)),
+    );
+
+    lines.join("\n")
+}
+
+async fn get_applied_versions<DB>(pool: &Pool<DB>) -> Result<Vec<i64>, MigrateError>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let mut connection = pool.acquire().await?;
+
+    // Creates _sqlx_migrations on a fresh database, the same table the sqlx CLI uses
+    connection.ensure_migrations_table().await?;
+
+    let applied_migrations = connection.list_applied_migrations().await?;
+
+    Ok(applied_migrations
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect())
+}
+
+pub async fn get_migration_status(db_pool: &DatabasePool) -> Result<MigrationStatus, String> {
+    let applied_versions = query_database!(db_pool, |pool, _sql| get_applied_versions(pool).await)
+        .map_err(|err| err.to_string())?;
+
+    Ok(get_migration_status_from(
+        get_migrator(db_pool.get_kind()),
+        &applied_versions,
+    ))
+}
+
+// Applies the pending migrations and returns them
+pub async fn run_migrations(db_pool: &DatabasePool) -> Result<Vec<MigrationInfo>, String> {
+    let status = get_migration_status(db_pool).await?;
+    check_schema_version(&status)?;
+
+    let migrator = get_migrator(db_pool.get_kind());
+
+    query_database!(db_pool, |pool, _sql| migrator.run(pool).await)
+        .map_err(|err| err.to_string())?;
+
+    Ok(status
+        .migrations
+        .into_iter()
+        .filter(|migration| !migration.is_applied)
+        .collect())
+}
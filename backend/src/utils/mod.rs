@@ -1,13 +1,38 @@
 pub mod auth;
+pub mod breached_password;
+pub mod client_ip;
+pub mod config;
 pub mod cookie;
+pub mod database;
 pub mod dates;
 pub mod emails;
-pub mod env;
+pub mod encoding;
+pub mod errors;
+pub mod hashing;
+pub mod http;
 pub mod jwt;
+pub mod jwt_keys;
+pub mod migrations;
+pub mod oauth;
+pub mod oidc;
 pub mod otc;
+pub mod passkey;
+pub mod password_history;
+pub mod password_policy;
+pub mod rate_limit;
+pub mod recovery_codes;
 pub mod redis;
+pub mod redis_pool;
 pub mod responses;
+pub mod routes;
+pub mod security_event;
+pub mod session;
+pub mod social;
 pub mod templates;
+pub mod throttle;
+pub mod totp;
 pub mod translations;
+pub mod two_factor;
 pub mod user;
 pub mod validation;
+pub mod webauthn;
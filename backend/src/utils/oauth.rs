@@ -0,0 +1,334 @@
+use axum::http::StatusCode;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use rand::{distributions::Alphanumeric, thread_rng, Rng, RngCore};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    constants::oauth::{
+        OAUTH_CLIENT_ID_LENGTH, OAUTH_CODE_VERIFIER_MAX_LENGTH, OAUTH_CODE_VERIFIER_MIN_LENGTH,
+        OAUTH_CONSENT_EXPIRATION_SECONDS, OAUTH_REFRESH_TOKEN_EXPIRATION_SECONDS,
+    },
+    models::{
+        config::models::Config,
+        general::AppState,
+        oauth::models::{OAuthClient, OAuthConsentPayload},
+    },
+    utils::{
+        encoding::encode_base64url,
+        hashing::hash_with_secret,
+        redis::{get_token, set_token},
+    },
+};
+
+impl OAuthClient {
+    pub fn get_redirect_uris(&self) -> Vec<String> {
+        self.redirect_uris
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub fn get_scopes(&self) -> Vec<String> {
+        self.scopes.split_whitespace().map(str::to_string).collect()
+    }
+
+    pub fn is_confidential(&self) -> bool {
+        self.client_secret_hash.is_some()
+    }
+}
+
+pub fn generate_oauth_client_id() -> String {
+    let client_id: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(OAUTH_CLIENT_ID_LENGTH)
+        .map(char::from)
+        .collect();
+
+    client_id
+}
+
+// Random base64url value, used for client secrets, authorization codes and refresh tokens
+pub fn generate_oauth_token(bytes: usize) -> String {
+    let mut token = vec![0u8; bytes];
+    thread_rng().fill_bytes(&mut token);
+
+    encode_base64url(&token)
+}
+
+pub fn format_authorization_code_key(code: &str) -> String {
+    let code_key = format!("oauth-code:{}", code);
+
+    code_key
+}
+
+// Only a keyed hash of the refresh token is stored, like for session refresh tokens
+pub fn format_oauth_refresh_token_key(
+    config: &Config,
+    refresh_token: &str,
+) -> Result<String, StatusCode> {
+    let token_hash = hash_with_secret(config, &format!("oauth-refresh:{}", refresh_token))?;
+
+    Ok(format!("oauth-refresh:{}", token_hash))
+}
+
+pub fn format_oauth_consent_key(user_id: &i32, client_id: &str) -> String {
+    let consent_key = format!("oauth-consent:{}:{}", user_id, client_id);
+
+    consent_key
+}
+
+pub fn format_oauth_grants_watermark_key(user_id: &i32) -> String {
+    let token_key = format!("oauth-not-before:{}", user_id);
+
+    token_key
+}
+
+// Ends every consent, authorization code and refresh token of a user, access tokens are covered by the JWT watermark
+pub async fn revoke_oauth_grants(state: &AppState, user_id: &i32) -> Result<(), StatusCode> {
+    set_token(
+        state,
+        &format_oauth_grants_watermark_key(user_id),
+        &Utc::now().timestamp_millis().to_string(),
+        OAUTH_REFRESH_TOKEN_EXPIRATION_SECONDS,
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Timestamps are in milliseconds, a grant from the same millisecond as the revocation is refused too,
+// a new consent takes the user far longer than that
+pub async fn is_oauth_grant_revoked(
+    state: &AppState,
+    user_id: &i32,
+    issued_at: i64,
+) -> Result<bool, StatusCode> {
+    let watermark: Option<i64> = get_token(state, &format_oauth_grants_watermark_key(user_id))
+        .await?
+        .and_then(|timestamp| timestamp.parse().ok());
+
+    Ok(watermark.is_some_and(|not_before| issued_at <= not_before))
+}
+
+// Consent is remembered per user and client, a later approval adds its scopes to the earlier ones
+pub async fn store_oauth_consent(
+    state: &AppState,
+    user_id: &i32,
+    client_id: &str,
+    scope: &str,
+) -> Result<(), StatusCode> {
+    let mut scopes: Vec<String> = get_oauth_consent(state, user_id, client_id)
+        .await?
+        .map(|consent| {
+            consent
+                .scope
+                .split_whitespace()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for scope in scope.split_whitespace() {
+        if !scopes.iter().any(|granted| granted == scope) {
+            scopes.push(scope.to_string());
+        }
+    }
+
+    let consent_payload = serde_json::to_string(&OAuthConsentPayload {
+        scope: scopes.join(" "),
+        granted_at: Utc::now().timestamp_millis(),
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_token(
+        state,
+        &format_oauth_consent_key(user_id, client_id),
+        &consent_payload,
+        OAUTH_CONSENT_EXPIRATION_SECONDS,
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn get_oauth_consent(
+    state: &AppState,
+    user_id: &i32,
+    client_id: &str,
+) -> Result<Option<OAuthConsentPayload>, StatusCode> {
+    let consent = get_token(state, &format_oauth_consent_key(user_id, client_id))
+        .await?
+        .and_then(|json| serde_json::from_str::<OAuthConsentPayload>(&json).ok());
+
+    let consent = match consent {
+        Some(consent) if !is_oauth_grant_revoked(state, user_id, consent.granted_at).await? => {
+            Some(consent)
+        }
+        _ => None,
+    };
+
+    Ok(consent)
+}
+
+// True when the user already approved every requested scope for the client
+pub async fn has_oauth_consent(
+    state: &AppState,
+    user_id: &i32,
+    client_id: &str,
+    scope: &str,
+) -> Result<bool, StatusCode> {
+    let consent = match get_oauth_consent(state, user_id, client_id).await? {
+        Some(consent) => consent,
+        None => return Ok(false),
+    };
+
+    let consented_scopes: Vec<&str> = consent.scope.split_whitespace().collect();
+
+    Ok(scope
+        .split_whitespace()
+        .all(|scope| consented_scopes.contains(&scope)))
+}
+
+pub fn is_oauth_admin(state: &AppState, email: &str) -> bool {
+    state
+        .config
+        .oauth_admin_emails
+        .iter()
+        .any(|admin_email| admin_email.eq_ignore_ascii_case(email))
+}
+
+// Redirect URIs are matched exactly, plain http is only allowed for loopback redirects of native apps
+pub fn is_valid_redirect_uri(redirect_uri: &str) -> bool {
+    let url = match Url::parse(redirect_uri) {
+        Ok(url) => url,
+        Err(_) => return false,
+    };
+
+    if url.fragment().is_some() || redirect_uri.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    match url.scheme() {
+        "https" => url.host().is_some(),
+        "http" => matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]")),
+        _ => false,
+    }
+}
+
+pub fn format_redirect_uri(
+    redirect_uri: &str,
+    params: &[(&str, &str)],
+) -> Result<String, StatusCode> {
+    let mut url = Url::parse(redirect_uri).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    url.query_pairs_mut().extend_pairs(params);
+
+    Ok(url.to_string())
+}
+
+// Returns the granted scope, every requested scope has to be allowed for the client and defaults to all of them
+pub fn resolve_scope(requested_scope: Option<&str>, allowed_scopes: &[String]) -> Option<String> {
+    let requested_scopes: Vec<&str> = match requested_scope {
+        Some(scope) if !scope.trim().is_empty() => scope.split_whitespace().collect(),
+        _ => return Some(allowed_scopes.join(" ")),
+    };
+
+    if !requested_scopes
+        .iter()
+        .all(|scope| allowed_scopes.iter().any(|allowed| allowed == scope))
+    {
+        return None;
+    }
+
+    let mut granted_scopes: Vec<&str> = Vec::new();
+
+    for scope in requested_scopes {
+        if !granted_scopes.contains(&scope) {
+            granted_scopes.push(scope);
+        }
+    }
+
+    Some(granted_scopes.join(" "))
+}
+
+pub fn create_pkce_challenge(code_verifier: &str) -> String {
+    encode_base64url(&Sha256::digest(code_verifier.as_bytes()))
+}
+
+// S256 only, see RFC 7636 section 4.6
+pub fn verify_pkce_challenge(code_verifier: &str, code_challenge: &str) -> bool {
+    let is_valid_verifier = (OAUTH_CODE_VERIFIER_MIN_LENGTH..=OAUTH_CODE_VERIFIER_MAX_LENGTH)
+        .contains(&code_verifier.len())
+        && code_verifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~'));
+
+    is_valid_verifier && create_pkce_challenge(code_verifier) == code_challenge
+}
+
+// Client credentials sent with HTTP Basic authentication, see RFC 6749 section 2.3.1
+pub fn parse_basic_authorization(authorization: &str) -> Option<(String, String)> {
+    let credentials = authorization.strip_prefix("Basic ")?;
+    let credentials = STANDARD.decode(credentials.trim()).ok()?;
+    let credentials = String::from_utf8(credentials).ok()?;
+
+    let (client_id, client_secret) = credentials.split_once(':')?;
+
+    Some((client_id.to_string(), client_secret.to_string()))
+}
+
+pub fn hash_client_secret(config: &Config, client_secret: &str) -> Result<String, StatusCode> {
+    Ok(hash_with_secret(config, client_secret)?)
+}
+
+// Confidential clients have to present their secret, public clients are bound by PKCE alone
+pub fn authenticate_oauth_client(
+    config: &Config,
+    client: &OAuthClient,
+    client_secret: Option<&str>,
+) -> Result<bool, StatusCode> {
+    let client_secret_hash = match &client.client_secret_hash {
+        Some(client_secret_hash) => client_secret_hash,
+        None => return Ok(client_secret.is_none()),
+    };
+
+    let is_authenticated = match client_secret {
+        Some(client_secret) => hash_client_secret(config, client_secret)? == *client_secret_hash,
+        None => false,
+    };
+
+    Ok(is_authenticated)
+}
+
+pub async fn create_oauth_client(
+    state: &AppState,
+    client: &OAuthClient,
+    owner_id: &i32,
+) -> Result<u64, StatusCode> {
+    Ok(state
+        .oauth_clients
+        .create_oauth_client(client, owner_id)
+        .await?)
+}
+
+pub async fn get_oauth_client_by_client_id(
+    state: &AppState,
+    client_id: &str,
+) -> Result<Option<OAuthClient>, StatusCode> {
+    Ok(state
+        .oauth_clients
+        .get_oauth_client_by_client_id(client_id)
+        .await?)
+}
+
+pub async fn get_oauth_clients_by_owner_id(
+    state: &AppState,
+    owner_id: &i32,
+) -> Result<Vec<OAuthClient>, StatusCode> {
+    Ok(state
+        .oauth_clients
+        .get_oauth_clients_by_owner_id(owner_id)
+        .await?)
+}
@@ -0,0 +1,62 @@
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+
+use crate::{
+    constants::oauth::OIDC_ID_TOKEN_EXPIRATION_SECONDS,
+    models::{
+        config::models::Config,
+        oidc::models::{IdTokenClaims, OidcUserClaims},
+        user::aliases::{Email, Id, IsConfirmed, Name, Phone},
+    },
+    utils::{jwt::sign_jwt_claims, jwt_keys::get_jwt_keys},
+};
+
+// Public base URL of the API, used as iss in ID tokens and as base of the discovery document
+pub fn get_issuer_url(config: &Config) -> String {
+    config.oidc_issuer_url.trim_end_matches('/').to_string()
+}
+
+pub fn has_scope(granted_scope: &str, scope: &str) -> bool {
+    granted_scope
+        .split_whitespace()
+        .any(|granted| granted == scope)
+}
+
+// Maps the profile, email and phone scopes to their standard claims, see OpenID Connect Core section 5.4
+pub fn format_oidc_user_claims(
+    (id, name, email, phone, is_confirmed): (Id, Name, Email, Option<Phone>, IsConfirmed),
+    granted_scope: &str,
+) -> OidcUserClaims {
+    let has_email_scope = has_scope(granted_scope, "email");
+
+    OidcUserClaims {
+        sub: id.to_string(),
+        name: has_scope(granted_scope, "profile").then_some(name),
+        email: has_email_scope.then_some(email),
+        email_verified: has_email_scope.then_some(is_confirmed),
+        phone_number: phone.filter(|_| has_scope(granted_scope, "phone")),
+    }
+}
+
+pub fn encode_id_token(
+    issuer_url: &str,
+    client_id: &str,
+    nonce: Option<&str>,
+    user: OidcUserClaims,
+) -> Result<String, StatusCode> {
+    let jwt_keys = get_jwt_keys()?;
+
+    let now = Utc::now();
+    let expire = Duration::seconds(OIDC_ID_TOKEN_EXPIRATION_SECONDS);
+
+    let claims = IdTokenClaims {
+        iss: issuer_url.to_string(),
+        aud: client_id.to_string(),
+        exp: (now + expire).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        nonce: nonce.map(str::to_string),
+        user,
+    };
+
+    Ok(sign_jwt_claims(jwt_keys, &claims)?)
+}
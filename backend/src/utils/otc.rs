@@ -1,18 +1,164 @@
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use axum::http::StatusCode;
+use rand::{rngs::OsRng, Rng};
+use subtle::ConstantTimeEq;
 
-pub fn format_otc_key(otc: &str) -> String {
-    let otc_key = format!("otc:{}", otc);
+use crate::{
+    constants::otc::{OTC_CHARACTERS, OTC_EXPIRATION_SECONDS, OTC_LENGTH, OTC_MAX_ATTEMPTS},
+    models::{config::models::Config, general::AppState, otc::models::OtcPayload},
+    utils::{
+        hashing::hash_with_secret,
+        redis::{
+            add_to_set, get_set_members, increment_counter, remove_from_set, remove_token,
+            set_token, take_token,
+        },
+    },
+};
 
-    otc_key
+// Codes are scoped to the email they were sent to and only stored hashed, a code alone doesn't find anything
+pub fn format_otc_key(config: &Config, email: &str, otc: &str) -> Result<String, StatusCode> {
+    let otc_hash = hash_with_secret(
+        config,
+        &format!("otc:{}:{}", email.to_lowercase(), otc.trim().to_uppercase()),
+    )?;
+
+    Ok(format!("otc:{}", otc_hash))
+}
+
+// Wrong codes are counted per email, whoever enters them, so spreading guesses over IPs doesn't help
+pub fn format_otc_attempts_key(config: &Config, email: &str) -> Result<String, StatusCode> {
+    let email_hash = hash_with_secret(config, &format!("otc-attempts:{}", email.to_lowercase()))?;
+
+    Ok(format!("otc-attempts:{}", email_hash))
+}
+
+// The pending codes of an email, so they can be burned without knowing the codes themselves
+pub fn format_otc_codes_key(config: &Config, email: &str) -> Result<String, StatusCode> {
+    let email_hash = hash_with_secret(config, &format!("otc-codes:{}", email.to_lowercase()))?;
+
+    Ok(format!("otc-codes:{}", email_hash))
+}
+
+// Binds a login code to the email it was sent to, the signature can't be built from a guessed code
+pub fn sign_login_otc(config: &Config, otc: &str, email: &str) -> Result<String, StatusCode> {
+    Ok(hash_with_secret(
+        config,
+        &format!(
+            "login:{}:{}",
+            otc.trim().to_uppercase(),
+            email.to_lowercase()
+        ),
+    )?)
+}
+
+// A short code could be guessed within the attempt limit of many emails, so logging in takes the signature of
+// the link from the email as well. Compared in constant time to not leak how much of a guess matched.
+pub fn is_valid_login_otc(
+    config: &Config,
+    otc: &str,
+    email: &str,
+    signature: Option<&str>,
+) -> Result<bool, StatusCode> {
+    let signature = match signature {
+        Some(signature) => signature,
+        None => return Ok(false),
+    };
+
+    let expected_signature = sign_login_otc(config, otc, email)?;
+
+    Ok(expected_signature
+        .as_bytes()
+        .ct_eq(signature.as_bytes())
+        .into())
 }
 
 pub fn create_otc() -> String {
-    let otc: String = thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(6)
-        .map(char::from)
-        .map(|character| character.to_ascii_uppercase())
+    let otc: String = (0..OTC_LENGTH)
+        .map(|_| char::from(OTC_CHARACTERS[OsRng.gen_range(0..OTC_CHARACTERS.len())]))
         .collect();
 
     otc
 }
+
+// Creates a code for the payload, the returned plain code is only sent to the email and never stored.
+// A new code starts with a clean slate of attempts.
+pub async fn store_otc(
+    state: &AppState,
+    email: &str,
+    payload: &OtcPayload,
+) -> Result<String, StatusCode> {
+    let otc = create_otc();
+    let otc_key = format_otc_key(state.config, email, &otc)?;
+
+    let payload_json =
+        serde_json::to_string(payload).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_token(state, &otc_key, &payload_json, OTC_EXPIRATION_SECONDS).await?;
+
+    add_to_set(
+        state,
+        &format_otc_codes_key(state.config, email)?,
+        &otc_key,
+        OTC_EXPIRATION_SECONDS,
+    )
+    .await?;
+
+    remove_token(state, &format_otc_attempts_key(state.config, email)?).await?;
+
+    Ok(otc)
+}
+
+// Removes every pending code of the email, they can't be guessed any further
+async fn burn_otcs(state: &AppState, email: &str) -> Result<(), StatusCode> {
+    let codes_key = format_otc_codes_key(state.config, email)?;
+
+    for otc_key in get_set_members(state, &codes_key).await? {
+        remove_token(state, &otc_key).await?;
+    }
+
+    remove_token(state, &codes_key).await?;
+
+    Ok(())
+}
+
+// Redeems the code sent to the email, taking it in one step so it can't be used twice. Every attempt is counted before the lookup, so parallel guesses
+// can't all get in under the limit. After OTC_MAX_ATTEMPTS wrong codes the pending codes are burned and
+// TOO_MANY_REQUESTS is returned until a new code is sent.
+pub async fn take_otc_payload(
+    state: &AppState,
+    email: &str,
+    otc: &str,
+) -> Result<Option<OtcPayload>, StatusCode> {
+    let attempts = increment_counter(
+        state,
+        &format_otc_attempts_key(state.config, email)?,
+        OTC_EXPIRATION_SECONDS,
+    )
+    .await?;
+
+    if attempts > OTC_MAX_ATTEMPTS {
+        burn_otcs(state, email).await?;
+
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let otc_key = format_otc_key(state.config, email, otc)?;
+
+    let payload_json = match take_token(state, &otc_key).await? {
+        Some(payload_json) => payload_json,
+        None => {
+            // The last wrong code burns them right away, not only on the attempt after it
+            if attempts == OTC_MAX_ATTEMPTS {
+                burn_otcs(state, email).await?;
+            }
+
+            return Ok(None);
+        }
+    };
+
+    remove_from_set(state, &format_otc_codes_key(state.config, email)?, &otc_key).await?;
+
+    let payload =
+        serde_json::from_str(&payload_json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Some(payload))
+}
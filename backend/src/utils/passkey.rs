@@ -0,0 +1,54 @@
+use crate::models::general::AppState;
+use axum::http::StatusCode;
+
+pub async fn create_passkey(
+    state: &AppState,
+    user_id: &i32,
+    credential_id: &str,
+    public_key: &str,
+    sign_count: u32,
+) -> Result<u64, StatusCode> {
+    Ok(state
+        .passkeys
+        .create_passkey(user_id, credential_id, public_key, sign_count)
+        .await?)
+}
+
+// Returns the user id, public key and sign count, an unknown credential can't log in
+pub async fn get_passkey_by_credential_id(
+    state: &AppState,
+    credential_id: &str,
+) -> Result<(i32, String, u32), StatusCode> {
+    let passkey = state
+        .passkeys
+        .get_passkey_by_credential_id(credential_id)
+        .await
+        .map_err(|error| {
+            error.log();
+
+            StatusCode::UNAUTHORIZED
+        });
+
+    passkey
+}
+
+pub async fn get_passkey_credential_ids_by_user_id(
+    state: &AppState,
+    user_id: &i32,
+) -> Result<Vec<String>, StatusCode> {
+    Ok(state
+        .passkeys
+        .get_passkey_credential_ids_by_user_id(user_id)
+        .await?)
+}
+
+pub async fn update_passkey_sign_count(
+    state: &AppState,
+    credential_id: &str,
+    sign_count: u32,
+) -> Result<u64, StatusCode> {
+    Ok(state
+        .passkeys
+        .update_passkey_sign_count(credential_id, sign_count)
+        .await?)
+}
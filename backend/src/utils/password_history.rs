@@ -0,0 +1,34 @@
+use axum::http::StatusCode;
+
+use crate::{
+    constants::password_policy::PASSWORD_HISTORY_SIZE, models::general::AppState,
+    utils::auth::verify_password_against_any,
+};
+
+// Compares against the current password and the previous ones still in the history, all on one blocking thread
+pub async fn is_password_reused(
+    state: &AppState,
+    user_id: &i32,
+    password: &str,
+) -> Result<bool, StatusCode> {
+    let password_hashes = state
+        .users
+        .get_password_hashes(user_id, PASSWORD_HISTORY_SIZE)
+        .await?;
+
+    verify_password_against_any(password, password_hashes).await
+}
+
+// Moves the current hash to the history before replacing it, and prunes entries beyond the history size
+pub async fn change_user_password(
+    state: &AppState,
+    user_id: &i32,
+    password_hash: &str,
+) -> Result<(), StatusCode> {
+    state
+        .users
+        .change_user_password(user_id, password_hash, PASSWORD_HISTORY_SIZE)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,308 @@
+use std::{fs, sync::OnceLock};
+
+use crate::{
+    constants::password_policy::{
+        COMMON_PASSWORD_WORDS, PASSWORD_DEFAULT_MAX_LENGTH, PASSWORD_DEFAULT_MIN_LENGTH,
+        PASSWORD_DEFAULT_MIN_SCORE, PASSWORD_PERSONAL_INFO_MIN_LENGTH,
+    },
+    models::{
+        config::models::PasswordPolicyConfig,
+        password_policy::models::{CharacterSet, PasswordPolicy},
+    },
+};
+
+static PASSWORD_POLICY: OnceLock<PasswordPolicy> = OnceLock::new();
+
+const ALL_CHARACTER_SETS: [CharacterSet; 6] = [
+    CharacterSet::Lowercase,
+    CharacterSet::Uppercase,
+    CharacterSet::Number,
+    CharacterSet::Space,
+    CharacterSet::Symbol,
+    CharacterSet::Letter,
+];
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: PASSWORD_DEFAULT_MIN_LENGTH,
+            max_length: PASSWORD_DEFAULT_MAX_LENGTH,
+            required_character_sets: Vec::new(),
+            allowed_character_sets: ALL_CHARACTER_SETS.to_vec(),
+            min_score: PASSWORD_DEFAULT_MIN_SCORE,
+            reject_personal_info: true,
+        }
+    }
+}
+
+// Control characters are never allowed, they can't be typed reliably
+pub fn get_character_set(character: char) -> Option<CharacterSet> {
+    if character.is_control() {
+        None
+    } else if character.is_lowercase() {
+        Some(CharacterSet::Lowercase)
+    } else if character.is_uppercase() {
+        Some(CharacterSet::Uppercase)
+    } else if character.is_numeric() {
+        Some(CharacterSet::Number)
+    } else if character.is_whitespace() {
+        Some(CharacterSet::Space)
+    } else if character.is_alphabetic() {
+        Some(CharacterSet::Letter)
+    } else {
+        Some(CharacterSet::Symbol)
+    }
+}
+
+fn get_character_set_size(character_set: CharacterSet) -> f64 {
+    match character_set {
+        CharacterSet::Lowercase | CharacterSet::Uppercase => 26.0,
+        CharacterSet::Number => 10.0,
+        CharacterSet::Space => 1.0,
+        CharacterSet::Symbol => 33.0,
+        CharacterSet::Letter => 100.0,
+    }
+}
+
+// Splits the name and the local part of the email in the parts someone would put in a password, like "john"
+// and "doe" of john.doe@example.com. The domain is left out, it's shared by everyone at the same provider
+fn get_personal_info_words(user_inputs: &[&str]) -> Vec<String> {
+    user_inputs
+        .iter()
+        .flat_map(|input| {
+            let input = input.to_lowercase();
+
+            let (input, local_part) = match input.split_once('@') {
+                Some((local_part, _)) => (local_part.to_string(), Some(local_part.to_string())),
+                None => (input, None),
+            };
+
+            input
+                .split(|character: char| !character.is_alphanumeric())
+                .map(str::to_string)
+                .chain(local_part)
+                .collect::<Vec<String>>()
+        })
+        .filter(|word| word.chars().count() >= PASSWORD_PERSONAL_INFO_MIN_LENGTH)
+        .collect()
+}
+
+fn mark_word_matches(characters: &[char], word: &str, is_predictable: &mut [bool]) {
+    let word: Vec<char> = word.chars().collect();
+
+    if word.is_empty() || word.len() > characters.len() {
+        return;
+    }
+
+    for start in 0..=characters.len() - word.len() {
+        if characters[start..start + word.len()] == word[..] {
+            is_predictable[start..start + word.len()].fill(true);
+        }
+    }
+}
+
+// A simplified zxcvbn: characters of common words, personal info, repeats and sequences like "abc" or "321"
+// only count as one bit, every other character as a random pick from the character sets in the password
+pub fn estimate_password_score(password: &str, user_inputs: &[&str]) -> u8 {
+    let characters: Vec<char> = password.to_lowercase().chars().collect();
+    let mut is_predictable = vec![false; characters.len()];
+
+    for word in COMMON_PASSWORD_WORDS {
+        mark_word_matches(&characters, word, &mut is_predictable);
+    }
+
+    for word in get_personal_info_words(user_inputs) {
+        mark_word_matches(&characters, &word, &mut is_predictable);
+    }
+
+    for index in 1..characters.len() {
+        let step = characters[index] as i64 - characters[index - 1] as i64;
+
+        if step == 0 {
+            is_predictable[index] = true;
+        }
+
+        if index >= 2 && step.abs() == 1 {
+            let previous_step = characters[index - 1] as i64 - characters[index - 2] as i64;
+
+            if step == previous_step {
+                is_predictable[index - 1] = true;
+                is_predictable[index] = true;
+            }
+        }
+    }
+
+    let mut character_sets: Vec<CharacterSet> =
+        password.chars().filter_map(get_character_set).collect();
+    character_sets.sort_by_key(|character_set| *character_set as u8);
+    character_sets.dedup();
+
+    let pool_size: f64 = character_sets
+        .into_iter()
+        .map(get_character_set_size)
+        .sum::<f64>()
+        .max(2.0);
+
+    let bits: f64 = is_predictable
+        .iter()
+        .map(|is_predictable| {
+            if *is_predictable {
+                1.0
+            } else {
+                pool_size.log2()
+            }
+        })
+        .sum();
+
+    // Same thresholds as zxcvbn, on the estimated number of guesses
+    match bits * 2f64.log10() {
+        guesses if guesses < 3.0 => 0,
+        guesses if guesses < 6.0 => 1,
+        guesses if guesses < 8.0 => 2,
+        guesses if guesses < 10.0 => 3,
+        _ => 4,
+    }
+}
+
+fn get_missing_character_set_key(character_set: CharacterSet) -> &'static str {
+    match character_set {
+        CharacterSet::Lowercase => "auth.errors.password_missing_lowercase",
+        CharacterSet::Uppercase => "auth.errors.password_missing_uppercase",
+        CharacterSet::Number => "auth.errors.password_missing_number",
+        CharacterSet::Space => "auth.errors.password_missing_space",
+        CharacterSet::Symbol => "auth.errors.password_missing_symbol",
+        CharacterSet::Letter => "auth.errors.password_missing_letter",
+    }
+}
+
+// Returns the translation key of the first rule of the policy the password breaks
+pub fn get_password_policy_feedback(
+    password: &str,
+    policy: &PasswordPolicy,
+    user_inputs: &[&str],
+) -> Option<&'static str> {
+    let length = password.chars().count();
+
+    if length < policy.min_length {
+        return Some("auth.errors.password_too_short");
+    }
+
+    if length > policy.max_length {
+        return Some("auth.errors.password_too_long");
+    }
+
+    let is_allowed = |character: char| {
+        get_character_set(character)
+            .is_some_and(|character_set| policy.allowed_character_sets.contains(&character_set))
+    };
+
+    if !password.chars().all(is_allowed) {
+        return Some("auth.errors.password_invalid_characters");
+    }
+
+    for required_character_set in &policy.required_character_sets {
+        if !password
+            .chars()
+            .any(|character| get_character_set(character) == Some(*required_character_set))
+        {
+            return Some(get_missing_character_set_key(*required_character_set));
+        }
+    }
+
+    if policy.reject_personal_info {
+        let lowercase_password = password.to_lowercase();
+
+        if get_personal_info_words(user_inputs)
+            .iter()
+            .any(|word| lowercase_password.contains(word.as_str()))
+        {
+            return Some("auth.errors.password_contains_personal_info");
+        }
+    }
+
+    if estimate_password_score(password, user_inputs) < policy.min_score {
+        return Some("auth.errors.password_too_weak");
+    }
+
+    None
+}
+
+pub fn parse_character_sets(value: &str) -> Option<Vec<CharacterSet>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| serde_json::from_value(serde_json::Value::String(value.to_lowercase())).ok())
+        .collect()
+}
+
+// A policy no password can meet would lock everyone out of registering
+pub fn validate_password_policy(policy: &PasswordPolicy) -> Result<(), String> {
+    if policy.min_length > policy.max_length {
+        return Err(format!(
+            "Password min length {} is above the max length {}",
+            policy.min_length, policy.max_length
+        ));
+    }
+
+    if policy.min_score > 4 {
+        return Err(format!(
+            "Password min score {} is above the highest score 4",
+            policy.min_score
+        ));
+    }
+
+    if let Some(character_set) = policy
+        .required_character_sets
+        .iter()
+        .find(|character_set| !policy.allowed_character_sets.contains(character_set))
+    {
+        return Err(format!(
+            "Required character set {:?} isn't allowed",
+            character_set
+        ));
+    }
+
+    Ok(())
+}
+
+// The path points at a JSON file in the format of the public endpoint, which replaces the other rules
+pub fn load_password_policy(config: &PasswordPolicyConfig) -> Result<PasswordPolicy, String> {
+    let policy = match &config.path {
+        Some(policy_path) => {
+            let policy_json = fs::read_to_string(policy_path)
+                .map_err(|err| format!("Error reading {}: {}", policy_path, err))?;
+
+            serde_json::from_str(&policy_json)
+                .map_err(|err| format!("Error parsing {}: {}", policy_path, err))?
+        }
+        None => PasswordPolicy {
+            min_length: config.min_length,
+            max_length: config.max_length,
+            required_character_sets: config.required_character_sets.clone(),
+            allowed_character_sets: config.allowed_character_sets.clone(),
+            min_score: config.min_score,
+            reject_personal_info: config.reject_personal_info,
+        },
+    };
+
+    validate_password_policy(&policy)?;
+
+    Ok(policy)
+}
+
+pub fn init_password_policy(config: &PasswordPolicyConfig) -> Result<(), String> {
+    if PASSWORD_POLICY.get().is_some() {
+        return Ok(());
+    }
+
+    let password_policy = load_password_policy(config)?;
+    let _ = PASSWORD_POLICY.set(password_policy);
+
+    Ok(())
+}
+
+// Falls back to the default policy when init_password_policy wasn't called, like in tests
+pub fn get_password_policy() -> &'static PasswordPolicy {
+    PASSWORD_POLICY.get_or_init(PasswordPolicy::default)
+}
@@ -0,0 +1,117 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+
+use crate::{
+    models::{config::models::RateLimitsConfig, general::AppState},
+    utils::redis::{get_token, increment_counter},
+};
+
+pub struct RateLimitPolicy {
+    pub name: &'static str,
+    pub limit: i64,
+    pub window_seconds: i64,
+    // Authenticated requests get a bucket per user instead of per IP, so users behind one NAT don't share it
+    pub key_by_user: bool,
+}
+
+pub struct RateLimitStatus {
+    pub limit: i64,
+    pub remaining: i64,
+    pub reset_seconds: i64,
+    pub window_seconds: i64,
+    pub is_limited: bool,
+}
+
+pub fn get_rate_limit_policy(rate_limits: &RateLimitsConfig, path: &str) -> RateLimitPolicy {
+    // Matched by path prefix, the first match wins so more specific routes come first
+    let route_rate_limits = [
+        (
+            "/api/user/reset-password/request",
+            "password-reset-request",
+            &rate_limits.password_reset_request,
+        ),
+        ("/api/otc/verify", "otc-verify", &rate_limits.otc_verify),
+        ("/api/auth", "auth", &rate_limits.auth),
+    ];
+
+    let route_rate_limit = route_rate_limits.into_iter().find(|(prefix, _, _)| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+
+    match route_rate_limit {
+        Some((_, name, rate_limit)) => RateLimitPolicy {
+            name,
+            limit: rate_limit.limit,
+            window_seconds: rate_limit.window_seconds,
+            key_by_user: false,
+        },
+        None => RateLimitPolicy {
+            name: "default",
+            limit: rate_limits.default.limit,
+            window_seconds: rate_limits.default.window_seconds,
+            key_by_user: true,
+        },
+    }
+}
+
+pub fn format_rate_limit_key(policy_name: &str, client_key: &str, window_index: i64) -> String {
+    let rate_limit_key = format!("rate-limit:{}:{}:{}", policy_name, client_key, window_index);
+
+    rate_limit_key
+}
+
+// Sliding window approximation: the previous window counts for the part of it still inside the sliding window
+pub fn get_sliding_window_count(
+    previous_count: i64,
+    current_count: i64,
+    elapsed_seconds: i64,
+    window_seconds: i64,
+) -> i64 {
+    let previous_weight = (window_seconds - elapsed_seconds).max(0);
+
+    previous_count * previous_weight / window_seconds + current_count
+}
+
+// Counts this request for the client and returns whether it is over the limit of the policy
+pub async fn check_rate_limit(
+    state: &AppState,
+    policy: &RateLimitPolicy,
+    client_key: &str,
+) -> Result<RateLimitStatus, StatusCode> {
+    let now = Utc::now().timestamp();
+    let window_index = now / policy.window_seconds;
+    let elapsed_seconds = now % policy.window_seconds;
+
+    let current_count = increment_counter(
+        state,
+        &format_rate_limit_key(policy.name, client_key, window_index),
+        (policy.window_seconds * 2) as i32,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let previous_count: i64 = get_token(
+        state,
+        &format_rate_limit_key(policy.name, client_key, window_index - 1),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .and_then(|count| count.parse().ok())
+    .unwrap_or(0);
+
+    let count = get_sliding_window_count(
+        previous_count,
+        current_count,
+        elapsed_seconds,
+        policy.window_seconds,
+    );
+
+    Ok(RateLimitStatus {
+        limit: policy.limit,
+        remaining: (policy.limit - count).max(0),
+        reset_seconds: policy.window_seconds - elapsed_seconds,
+        window_seconds: policy.window_seconds,
+        is_limited: count > policy.limit,
+    })
+}
@@ -0,0 +1,116 @@
+use crate::{
+    constants::two_factor::{
+        RECOVERY_CODES_REGENERATION_EXPIRATION_SECONDS, RECOVERY_CODE_ALPHABET,
+        RECOVERY_CODE_COUNT, RECOVERY_CODE_LENGTH,
+    },
+    models::{config::models::Config, general::AppState},
+    utils::{
+        hashing::hash_with_secret,
+        redis::{set_token, take_token},
+    },
+};
+use axum::http::StatusCode;
+use rand::{thread_rng, Rng};
+
+pub fn format_recovery_codes_regeneration_key(user_id: &i32) -> String {
+    let regeneration_key = format!("recovery-codes-regeneration:{}", user_id);
+
+    regeneration_key
+}
+
+// Codes are shown as two dash separated halves, e.g. ABCDE-23456
+pub fn generate_recovery_code() -> String {
+    let mut rng = thread_rng();
+
+    let code: String = (0..RECOVERY_CODE_LENGTH)
+        .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+        .collect();
+
+    let (first_half, second_half) = code.split_at(RECOVERY_CODE_LENGTH / 2);
+
+    format!("{}-{}", first_half, second_half)
+}
+
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|character| character.is_ascii_alphanumeric())
+        .map(|character| character.to_ascii_uppercase())
+        .collect()
+}
+
+pub fn hash_recovery_code(config: &Config, code: &str) -> Result<String, StatusCode> {
+    Ok(hash_with_secret(config, &normalize_recovery_code(code))?)
+}
+
+// Replaces any existing codes of the user and returns the new ones in plain text, they are only stored hashed
+pub async fn replace_recovery_codes(
+    state: &AppState,
+    user_id: &i32,
+) -> Result<Vec<String>, StatusCode> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+
+    let code_hashes = codes
+        .iter()
+        .map(|code| hash_recovery_code(state.config, code))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    state
+        .two_factors
+        .replace_recovery_codes(user_id, &code_hashes)
+        .await?;
+
+    Ok(codes)
+}
+
+pub async fn count_recovery_codes(state: &AppState, user_id: &i32) -> Result<i64, StatusCode> {
+    Ok(state.two_factors.count_recovery_codes(user_id).await?)
+}
+
+// Deleting the matching row is what makes a code single use, so two concurrent logins can't both use it
+pub async fn use_recovery_code(
+    state: &AppState,
+    user_id: &i32,
+    code: &str,
+) -> Result<bool, StatusCode> {
+    let code_hash = hash_recovery_code(state.config, code)?;
+
+    let deleted_codes = state
+        .two_factors
+        .delete_recovery_code(user_id, &code_hash)
+        .await?;
+
+    Ok(deleted_codes == 1)
+}
+
+pub async fn delete_recovery_codes(state: &AppState, user_id: &i32) -> Result<u64, StatusCode> {
+    Ok(state.two_factors.delete_recovery_codes(user_id).await?)
+}
+
+// The emailed code only approves new codes, they are handed out on an authenticated request afterwards
+pub async fn approve_recovery_codes_regeneration(
+    state: &AppState,
+    user_id: &i32,
+) -> Result<(), StatusCode> {
+    set_token(
+        state,
+        &format_recovery_codes_regeneration_key(user_id),
+        "approved",
+        RECOVERY_CODES_REGENERATION_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// Returns whether the user approved new codes by email, an approval can be used once
+pub async fn take_recovery_codes_regeneration_approval(
+    state: &AppState,
+    user_id: &i32,
+) -> Result<bool, StatusCode> {
+    let approval = take_token(state, &format_recovery_codes_regeneration_key(user_id))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(approval.is_some())
+}
@@ -1,54 +1,99 @@
-use crate::models::general::AppState;
-use axum::http::StatusCode;
-use redis::{AsyncCommands, RedisError};
+use crate::{models::general::AppState, utils::errors::ServiceError};
+
+// These go through the token store of the state, which is Redis outside of tests
 
 pub async fn set_token(
     state: &AppState,
     key: &str,
     value: &str,
     expiration_seconds: i32,
-) -> Result<(), RedisError> {
-    let mut redis_con = state.redis.lock().await;
-
-    let result: Result<(), redis::RedisError> = redis::cmd("SETEX")
-        .arg(key)
-        .arg(expiration_seconds)
-        .arg(value)
-        .query_async(&mut *redis_con)
-        .await;
+) -> Result<(), ServiceError> {
+    state.tokens.set_token(key, value, expiration_seconds).await
+}
 
-    result
+// Only sets a key that doesn't exist yet, returns whether it did
+pub async fn set_token_if_absent(
+    state: &AppState,
+    key: &str,
+    value: &str,
+    expiration_seconds: i32,
+) -> Result<bool, ServiceError> {
+    state
+        .tokens
+        .set_token_if_absent(key, value, expiration_seconds)
+        .await
 }
 
-pub async fn get_token(state: &AppState, key: &str) -> Result<Option<String>, redis::RedisError> {
-    let mut redis_con = state.redis.lock().await;
+pub async fn get_token(state: &AppState, key: &str) -> Result<Option<String>, ServiceError> {
+    state.tokens.get_token(key).await
+}
 
-    let token_json: Option<String> = redis_con.get(key).await?;
+pub async fn remove_token(state: &AppState, key: &str) -> Result<(), ServiceError> {
+    state.tokens.remove_token(key).await
+}
 
-    Ok(token_json)
+// Reads and removes the key in one step, so a single use token can't be redeemed twice
+pub async fn take_token(state: &AppState, key: &str) -> Result<Option<String>, ServiceError> {
+    state.tokens.take_token(key).await
 }
 
-pub async fn remove_token(state: &AppState, key: &str) -> Result<(), redis::RedisError> {
-    let mut redis_con = state.redis.lock().await;
+pub async fn add_to_set(
+    state: &AppState,
+    key: &str,
+    member: &str,
+    expiration_seconds: i32,
+) -> Result<(), ServiceError> {
+    state
+        .tokens
+        .add_to_set(key, member, expiration_seconds)
+        .await
+}
 
-    let _: () = redis_con.del(key).await?;
+pub async fn get_set_members(state: &AppState, key: &str) -> Result<Vec<String>, ServiceError> {
+    state.tokens.get_set_members(key).await
+}
 
-    Ok(())
+pub async fn remove_from_set(
+    state: &AppState,
+    key: &str,
+    member: &str,
+) -> Result<(), ServiceError> {
+    state.tokens.remove_from_set(key, member).await
 }
 
-pub async fn verify_token(state: &AppState, key: &str, id: &i32) -> Result<(), StatusCode> {
-    let token = get_token(state, key)
+// Increments a counter and (re)sets its expiration, returns the new count
+pub async fn increment_counter(
+    state: &AppState,
+    key: &str,
+    expiration_seconds: i32,
+) -> Result<i64, ServiceError> {
+    state
+        .tokens
+        .increment_counter(key, expiration_seconds)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+}
+
+// Takes one off a counter that still exists
+pub async fn decrement_counter(state: &AppState, key: &str) -> Result<(), ServiceError> {
+    state.tokens.decrement_counter(key).await
+}
+
+// Returns the seconds until the key expires, or None when it doesn't exist or never expires
+pub async fn get_time_to_live(state: &AppState, key: &str) -> Result<Option<i64>, ServiceError> {
+    state.tokens.get_time_to_live(key).await
+}
+
+pub async fn verify_token(state: &AppState, key: &str, id: &i32) -> Result<(), ServiceError> {
+    let token = get_token(state, key).await?;
 
     match token {
         Some(token_value) => {
             if token_value == id.to_string() {
                 Ok(())
             } else {
-                Err(StatusCode::UNAUTHORIZED)
+                Err(ServiceError::Unauthorized)
             }
         }
-        None => Err(StatusCode::UNAUTHORIZED),
+        None => Err(ServiceError::Unauthorized),
     }
 }
@@ -0,0 +1,303 @@
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use redis::{
+    aio::{ConnectionManager, ConnectionManagerConfig},
+    sentinel::{Sentinel, SentinelNodeConnectionInfo},
+    Client, IntoConnectionInfo, RedisConnectionInfo, RedisResult, TlsMode,
+};
+
+use crate::{
+    constants::redis::{
+        REDIS_CONNECTION_TIMEOUT_SECONDS, REDIS_HEALTH_CHECK_INTERVAL_SECONDS,
+        REDIS_RECONNECT_EXPONENT_BASE, REDIS_RECONNECT_FACTOR_MILLISECONDS,
+        REDIS_RECONNECT_MAX_DELAY_MILLISECONDS, REDIS_RECONNECT_RETRIES,
+        REDIS_RESPONSE_TIMEOUT_SECONDS, REDIS_SENTINEL_DEFAULT_PORT, REDIS_SENTINEL_SCHEME,
+        REDIS_SENTINEL_TLS_SCHEME,
+    },
+    models::{
+        health::models::TokenStoreHealth,
+        redis::models::{RedisTarget, SentinelTarget},
+    },
+};
+
+fn parse_sentinel_address(address: &str) -> Result<(String, u16), String> {
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("Invalid port in sentinel address {}", address))?,
+        ),
+        None => (address, REDIS_SENTINEL_DEFAULT_PORT),
+    };
+
+    if host.is_empty() {
+        return Err(format!("Missing host in sentinel address {}", address));
+    }
+
+    Ok((host.to_string(), port))
+}
+
+// redis+sentinel://[username:password@]host:port[,host:port]/service_name[/db], rediss+sentinel for TLS
+fn parse_sentinel_url(rest: &str, use_tls: bool) -> Result<SentinelTarget, String> {
+    // Split on the last @ so passwords can contain one
+    let (user_info, rest) = match rest.rsplit_once('@') {
+        Some((user_info, rest)) => (Some(user_info), rest),
+        None => (None, rest),
+    };
+
+    let (addresses, path) = rest
+        .split_once('/')
+        .ok_or_else(|| "Sentinel URL is missing the service name".to_string())?;
+
+    let sentinel_addresses = addresses
+        .split(',')
+        .map(parse_sentinel_address)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (service_name, db) = match path.split_once('/') {
+        Some((service_name, db)) => (
+            service_name,
+            db.parse()
+                .map_err(|_| format!("Invalid database {} in sentinel URL", db))?,
+        ),
+        None => (path, 0),
+    };
+
+    if service_name.is_empty() {
+        return Err("Sentinel URL is missing the service name".to_string());
+    }
+
+    let (username, password) = match user_info.map(|user_info| user_info.split_once(':')) {
+        Some(Some((username, password))) => (
+            Some(username.to_string()).filter(|username| !username.is_empty()),
+            Some(password.to_string()).filter(|password| !password.is_empty()),
+        ),
+        Some(None) => (user_info.map(str::to_string), None),
+        None => (None, None),
+    };
+
+    Ok(SentinelTarget {
+        sentinel_addresses,
+        service_name: service_name.to_string(),
+        db,
+        username,
+        password,
+        use_tls,
+    })
+}
+
+// Accepts redis:// and rediss:// for a single server, and redis+sentinel:// or rediss+sentinel:// for Sentinel
+pub fn parse_redis_target(redis_url: &str) -> Result<RedisTarget, String> {
+    let (scheme, rest) = redis_url
+        .split_once("://")
+        .ok_or_else(|| format!("Invalid Redis URL {}", redis_url))?;
+
+    match scheme {
+        REDIS_SENTINEL_SCHEME => Ok(RedisTarget::Sentinel(parse_sentinel_url(rest, false)?)),
+        REDIS_SENTINEL_TLS_SCHEME => Ok(RedisTarget::Sentinel(parse_sentinel_url(rest, true)?)),
+        _ => {
+            redis_url
+                .into_connection_info()
+                .map_err(|err| format!("Invalid Redis URL: {}", err))?;
+
+            Ok(RedisTarget::Server(redis_url.to_string()))
+        }
+    }
+}
+
+// Asks the sentinels which node is the master of the service right now
+async fn get_sentinel_master(sentinel_target: &SentinelTarget) -> RedisResult<Client> {
+    let scheme = if sentinel_target.use_tls {
+        "rediss"
+    } else {
+        "redis"
+    };
+
+    let sentinel_urls = sentinel_target
+        .sentinel_addresses
+        .iter()
+        .map(|(host, port)| format!("{}://{}:{}", scheme, host, port))
+        .collect::<Vec<_>>();
+
+    let node_connection_info = SentinelNodeConnectionInfo {
+        tls_mode: sentinel_target.use_tls.then_some(TlsMode::Secure),
+        redis_connection_info: Some(RedisConnectionInfo {
+            db: sentinel_target.db,
+            username: sentinel_target.username.clone(),
+            password: sentinel_target.password.clone(),
+            ..Default::default()
+        }),
+    };
+
+    Sentinel::build(sentinel_urls)?
+        .async_master_for(&sentinel_target.service_name, Some(&node_connection_info))
+        .await
+}
+
+async fn create_connection_manager(target: &RedisTarget) -> RedisResult<ConnectionManager> {
+    let client = match target {
+        RedisTarget::Server(redis_url) => Client::open(redis_url.as_str())?,
+        RedisTarget::Sentinel(sentinel_target) => get_sentinel_master(sentinel_target).await?,
+    };
+
+    let manager_config = ConnectionManagerConfig::new()
+        .set_exponent_base(REDIS_RECONNECT_EXPONENT_BASE)
+        .set_factor(REDIS_RECONNECT_FACTOR_MILLISECONDS)
+        .set_max_delay(REDIS_RECONNECT_MAX_DELAY_MILLISECONDS)
+        .set_number_of_retries(REDIS_RECONNECT_RETRIES)
+        .set_connection_timeout(Duration::from_secs(REDIS_CONNECTION_TIMEOUT_SECONDS))
+        .set_response_timeout(Duration::from_secs(REDIS_RESPONSE_TIMEOUT_SECONDS));
+
+    ConnectionManager::new_with_config(client, manager_config).await
+}
+
+// The delay before the next reconnect after the given number of failures, the same backoff the connection
+// manager uses: 200ms, 400ms, 800ms and so on, at most 5 seconds apart
+pub fn get_reconnect_delay(failures: u32) -> Duration {
+    let delay_milliseconds = REDIS_RECONNECT_EXPONENT_BASE
+        .saturating_pow(failures)
+        .saturating_mul(REDIS_RECONNECT_FACTOR_MILLISECONDS)
+        .min(REDIS_RECONNECT_MAX_DELAY_MILLISECONDS);
+
+    Duration::from_millis(delay_milliseconds)
+}
+
+#[derive(Default)]
+struct SentinelRediscovery {
+    failures: u32,
+    next_attempt_at: Option<Instant>,
+}
+
+// A multiplexed connection shared by all requests, which reconnects with backoff when it drops
+pub struct RedisPool {
+    target: RedisTarget,
+    connection: tokio::sync::RwLock<ConnectionManager>,
+    health: RwLock<TokenStoreHealth>,
+    rediscovery: Mutex<SentinelRediscovery>,
+}
+
+impl RedisPool {
+    pub async fn connect(target: RedisTarget) -> RedisResult<Self> {
+        let connection = create_connection_manager(&target).await?;
+
+        Ok(Self {
+            target,
+            connection: tokio::sync::RwLock::new(connection),
+            health: RwLock::new(TokenStoreHealth {
+                is_healthy: true,
+                ..Default::default()
+            }),
+            rediscovery: Mutex::new(SentinelRediscovery::default()),
+        })
+    }
+
+    // Health probes can come in faster than a failover settles, so the master is looked up with backoff
+    fn should_rediscover_master(&self, is_healthy: bool) -> bool {
+        let mut rediscovery = match self.rediscovery.lock() {
+            Ok(rediscovery) => rediscovery,
+            Err(_) => return false,
+        };
+
+        if is_healthy {
+            *rediscovery = SentinelRediscovery::default();
+
+            return false;
+        }
+
+        let now = Instant::now();
+
+        if rediscovery
+            .next_attempt_at
+            .is_some_and(|next_attempt_at| now < next_attempt_at)
+        {
+            return false;
+        }
+
+        rediscovery.failures = rediscovery.failures.saturating_add(1);
+        rediscovery.next_attempt_at = Some(now + get_reconnect_delay(rediscovery.failures));
+
+        true
+    }
+
+    // Clones are cheap and share the underlying connection, so no lock is held during a command
+    pub async fn get_connection(&self) -> ConnectionManager {
+        self.connection.read().await.clone()
+    }
+
+    // The result of the last health check
+    pub fn get_health(&self) -> TokenStoreHealth {
+        self.health
+            .read()
+            .map(|health| health.clone())
+            .unwrap_or_default()
+    }
+
+    pub async fn check_health(&self) -> TokenStoreHealth {
+        let mut connection = self.get_connection().await;
+
+        let started_at = Instant::now();
+        let ping: RedisResult<String> = redis::cmd("PING").query_async(&mut connection).await;
+
+        let mut health = self.get_health();
+        let should_rediscover_master = matches!(self.target, RedisTarget::Sentinel(_))
+            && self.should_rediscover_master(ping.is_ok());
+
+        match ping {
+            Ok(_) => {
+                health.is_healthy = true;
+                health.latency_ms = Some(started_at.elapsed().as_millis() as u64);
+                health.last_error = None;
+            }
+            Err(err) => {
+                health.is_healthy = false;
+                health.latency_ms = None;
+                health.last_error = Some(err.to_string());
+
+                // The connection manager only reconnects to the same node, after a failover the master has moved
+                if should_rediscover_master {
+                    match create_connection_manager(&self.target).await {
+                        Ok(connection) => {
+                            *self.connection.write().await = connection;
+                            health.failovers += 1;
+                        }
+                        Err(err) => {
+                            eprintln!("Error finding Redis master through Sentinel: {}", err)
+                        }
+                    }
+                }
+            }
+        }
+
+        health.checked_at = Some(Utc::now().timestamp());
+
+        if let Ok(mut current_health) = self.health.write() {
+            *current_health = health.clone();
+        }
+
+        health
+    }
+
+    // Keeps the health up to date and notices failovers while there is no traffic
+    pub fn spawn_health_checks(self: &Arc<Self>) {
+        let redis_pool = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(REDIS_HEALTH_CHECK_INTERVAL_SECONDS));
+
+            loop {
+                interval.tick().await;
+
+                let health = redis_pool.check_health().await;
+
+                if let Some(last_error) = health.last_error {
+                    eprintln!("Redis health check failed: {}", last_error);
+                }
+            }
+        });
+    }
+}
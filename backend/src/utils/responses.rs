@@ -1,10 +1,10 @@
 use axum::response::{IntoResponse, Response};
-use http::StatusCode;
+use http::{header, HeaderValue, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::models::translations::Translations;
-use crate::utils::translations::get_translation_by_key;
+use crate::models::{oauth::models::OAuthErrorResponse, translations::Translations};
+use crate::utils::{errors::ServiceError, translations::get_translation_by_key};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T: Serialize> {
@@ -28,6 +28,7 @@ impl<T: Serialize> IntoResponse for ApiResponse<T> {
 pub struct AppError {
     status_code: StatusCode,
     message: String,
+    retry_after: Option<u64>,
 }
 
 impl IntoResponse for AppError {
@@ -37,7 +38,15 @@ impl IntoResponse for AppError {
             "message": self.message,
         });
 
-        (self.status_code, axum::Json(body)).into_response()
+        let mut response = (self.status_code, axum::Json(body)).into_response();
+
+        if let Some(retry_after) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+
+        response
     }
 }
 
@@ -46,9 +55,17 @@ impl AppError {
         Self {
             status_code,
             message,
+            retry_after: None,
         }
     }
 
+    // Tells the client how many seconds to wait before retrying, used with 429 Too Many Requests
+    pub fn with_retry_after(mut self, retry_after_seconds: u64) -> Self {
+        self.retry_after = Some(retry_after_seconds);
+
+        self
+    }
+
     pub fn format_error(
         translations: &Translations,
         status_code: StatusCode,
@@ -65,6 +82,30 @@ impl AppError {
         AppError::new(StatusCode::INTERNAL_SERVER_ERROR, error_message)
     }
 
+    // Logs the cause of server errors, clients only get the translated message for the kind of error
+    pub fn format_service_error(
+        translations: &Translations,
+        error: impl Into<ServiceError>,
+    ) -> AppError {
+        let error = error.into();
+        error.log();
+
+        AppError::format_error(translations, error.status_code(), error.translation_key())
+    }
+
+    // For helpers that only return a status code, anything that isn't a client error stays an internal error
+    pub fn format_status_error(translations: &Translations, status_code: StatusCode) -> AppError {
+        let translation_key = match status_code {
+            StatusCode::BAD_REQUEST => "general.errors.bad_request",
+            StatusCode::UNAUTHORIZED => "general.errors.unauthorized",
+            StatusCode::NOT_FOUND => "general.errors.not_found",
+            StatusCode::TOO_MANY_REQUESTS => "general.errors.too_many_requests",
+            _ => return AppError::format_internal_error(translations),
+        };
+
+        AppError::format_error(translations, status_code, translation_key)
+    }
+
     // Used for formatting an AppError when translations are not defined
     pub fn format_raw_error(status_code: StatusCode, error_message: &str) -> AppError {
         AppError::new(status_code, error_message.to_string())
@@ -85,3 +126,117 @@ impl<T: Serialize> ApiResponse<T> {
         (status_code, axum::Json(response))
     }
 }
+
+// OAuth clients expect the RFC 6749 error format instead of a translated ApiResponse
+#[derive(Debug)]
+pub struct OAuthError {
+    status_code: StatusCode,
+    error: &'static str,
+    error_description: String,
+    www_authenticate: Option<&'static str>,
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let body = OAuthErrorResponse {
+            error: self.error.to_string(),
+            error_description: self.error_description,
+        };
+
+        let mut response = (self.status_code, axum::Json(body)).into_response();
+
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+        if let Some(www_authenticate) = self.www_authenticate {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(www_authenticate),
+            );
+        }
+
+        response
+    }
+}
+
+impl OAuthError {
+    pub fn new(status_code: StatusCode, error: &'static str, error_description: &str) -> Self {
+        let www_authenticate =
+            (status_code == StatusCode::UNAUTHORIZED).then_some("Basic realm=\"oauth\"");
+
+        Self {
+            status_code,
+            error,
+            error_description: error_description.to_string(),
+            www_authenticate,
+        }
+    }
+
+    pub fn invalid_request(error_description: &str) -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            error_description,
+        )
+    }
+
+    pub fn invalid_client() -> Self {
+        Self::new(
+            StatusCode::UNAUTHORIZED,
+            "invalid_client",
+            "Client authentication failed",
+        )
+    }
+
+    pub fn invalid_grant(error_description: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_grant", error_description)
+    }
+
+    pub fn invalid_scope() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_scope",
+            "The requested scope is invalid or exceeds the granted scope",
+        )
+    }
+
+    pub fn unsupported_grant_type() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "unsupported_grant_type",
+            "Supported grant types are authorization_code and refresh_token",
+        )
+    }
+
+    // Errors of resources protected by a bearer access token, see RFC 6750 section 3.1
+    pub fn invalid_token() -> Self {
+        Self {
+            www_authenticate: Some("Bearer error=\"invalid_token\""),
+            ..Self::new(
+                StatusCode::UNAUTHORIZED,
+                "invalid_token",
+                "The access token is missing, expired or revoked",
+            )
+        }
+    }
+
+    pub fn insufficient_scope() -> Self {
+        Self {
+            www_authenticate: Some("Bearer error=\"insufficient_scope\", scope=\"openid\""),
+            ..Self::new(
+                StatusCode::FORBIDDEN,
+                "insufficient_scope",
+                "The access token was not granted the openid scope",
+            )
+        }
+    }
+
+    pub fn server_error() -> Self {
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "server_error",
+            "The server encountered an unexpected error",
+        )
+    }
+}
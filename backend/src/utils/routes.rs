@@ -0,0 +1,34 @@
+use http::Method;
+
+use crate::constants::routes::{OPTIONAL_AUTH_ROUTES, PROTECTED_ROUTES};
+
+// Matches a request path against a route pattern, where a {param} segment matches any single segment
+pub fn matches_route_pattern(pattern: &str, path: &str) -> bool {
+    let pattern_segments: Vec<&str> = pattern.trim_end_matches('/').split('/').collect();
+    let path_segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    if pattern_segments.len() != path_segments.len() {
+        return false;
+    }
+
+    pattern_segments
+        .iter()
+        .zip(path_segments.iter())
+        .all(|(pattern_segment, path_segment)| {
+            let is_param = pattern_segment.starts_with('{') && pattern_segment.ends_with('}');
+
+            (is_param && !path_segment.is_empty()) || pattern_segment == path_segment
+        })
+}
+
+pub fn is_protected_route(path: &str, method: &Method) -> bool {
+    PROTECTED_ROUTES.iter().any(|(pattern, route_method)| {
+        *route_method == method && matches_route_pattern(pattern, path)
+    })
+}
+
+pub fn is_optional_auth_route(path: &str, method: &Method) -> bool {
+    OPTIONAL_AUTH_ROUTES.iter().any(|(pattern, route_method)| {
+        *route_method == method && matches_route_pattern(pattern, path)
+    })
+}
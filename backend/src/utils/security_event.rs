@@ -0,0 +1,31 @@
+use crate::{
+    constants::security_event::SECURITY_EVENT_MAX_USER_AGENT_LENGTH,
+    models::{general::AppState, session::models::SessionClient},
+    traits::security_event_repository::SecurityEvent,
+};
+use axum::http::StatusCode;
+
+pub async fn record_security_event(
+    state: &AppState,
+    user_id: &i32,
+    event_type: &str,
+    session_id: Option<&str>,
+    client: &SessionClient,
+) -> Result<u64, StatusCode> {
+    let user_agent = client.user_agent.as_deref().map(|user_agent| {
+        user_agent
+            .chars()
+            .take(SECURITY_EVENT_MAX_USER_AGENT_LENGTH)
+            .collect::<String>()
+    });
+
+    let event = SecurityEvent {
+        user_id: *user_id,
+        event_type: event_type.to_string(),
+        session_id: session_id.map(str::to_string),
+        ip: client.ip.clone(),
+        user_agent,
+    };
+
+    Ok(state.security_events.create_security_event(&event).await?)
+}
@@ -0,0 +1,458 @@
+use std::convert::Infallible;
+
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{header, request::Parts, Extensions, HeaderMap, Response, StatusCode},
+};
+use chrono::Utc;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+use crate::{
+    constants::{
+        auth::REFRESH_TOKEN_REUSE_GRACE_SECONDS, security_event::SECURITY_EVENT_REFRESH_TOKEN_REUSE,
+    },
+    models::{
+        config::models::Config,
+        general::AppState,
+        session::models::{
+            RefreshTokenPayload, RotatedRefreshToken, RotatedRefreshTokenPayload, SessionClient,
+            SessionPayload,
+        },
+        translations::Translations,
+    },
+    utils::{
+        client_ip::get_client_ip,
+        cookie::set_cookie,
+        jwt::{
+            encode_jwt, format_refresh_token_key, format_rotated_refresh_token_key,
+            generate_refresh_token, revoke_user_jwts,
+        },
+        oauth::revoke_oauth_grants,
+        redis::{
+            add_to_set, get_set_members, get_token, remove_from_set, remove_token, set_token,
+            take_token,
+        },
+        responses::AppError,
+        security_event::record_security_event,
+        user::get_user_by_id,
+    },
+};
+
+pub fn format_session_key(session_id: &str) -> String {
+    let session_key = format!("session:{}", session_id);
+
+    session_key
+}
+
+pub fn format_user_sessions_key(user_id: &i32) -> String {
+    let user_sessions_key = format!("sessions:{}", user_id);
+
+    user_sessions_key
+}
+
+pub fn generate_session_id() -> String {
+    let session_id: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect();
+
+    session_id
+}
+
+pub fn get_session_client(
+    config: &Config,
+    headers: &HeaderMap,
+    extensions: &Extensions,
+) -> SessionClient {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok())
+        .map(str::to_string);
+
+    let ip = get_client_ip(config, headers, extensions).map(|ip| ip.to_string());
+
+    SessionClient { user_agent, ip }
+}
+
+impl FromRequestParts<AppState> for SessionClient {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(get_session_client(
+            state.config,
+            &parts.headers,
+            &parts.extensions,
+        ))
+    }
+}
+
+pub async fn get_refresh_token_payload(
+    state: &AppState,
+    refresh_token_key: &str,
+) -> Result<Option<RefreshTokenPayload>, StatusCode> {
+    let refresh_token_payload = get_token(state, refresh_token_key)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|json| serde_json::from_str(&json).map_err(|_| StatusCode::UNAUTHORIZED))
+        .transpose()?;
+
+    Ok(refresh_token_payload)
+}
+
+pub async fn get_session(
+    state: &AppState,
+    session_id: &str,
+) -> Result<Option<SessionPayload>, StatusCode> {
+    let session = get_token(state, &format_session_key(session_id))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|json| serde_json::from_str(&json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR))
+        .transpose()?;
+
+    Ok(session)
+}
+
+pub async fn save_session(state: &AppState, session: &SessionPayload) -> Result<(), StatusCode> {
+    let session_json =
+        serde_json::to_string(session).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_token(
+        state,
+        &format_session_key(&session.id),
+        &session_json,
+        state.config.tokens.refresh_expiration_seconds,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    add_to_set(
+        state,
+        &format_user_sessions_key(&session.user_id),
+        &session.id,
+        state.config.tokens.refresh_expiration_seconds,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+// Points the session at its rotated refresh token, returns false when the session was revoked in the meantime
+async fn touch_session(
+    state: &AppState,
+    session_id: &str,
+    refresh_token_key: &str,
+    client: &SessionClient,
+) -> Result<bool, StatusCode> {
+    let mut session = match get_session(state, session_id).await? {
+        Some(session) => session,
+        None => return Ok(false),
+    };
+
+    session.refresh_token_key = refresh_token_key.to_string();
+    session.last_refreshed_at = Utc::now();
+
+    if client.user_agent.is_some() {
+        session.user_agent = client.user_agent.clone();
+    }
+
+    if client.ip.is_some() {
+        session.ip = client.ip.clone();
+    }
+
+    save_session(state, &session).await?;
+
+    Ok(true)
+}
+
+// Returns the live sessions of a user, dropping index entries whose session already expired
+pub async fn get_user_sessions(
+    state: &AppState,
+    user_id: &i32,
+) -> Result<Vec<SessionPayload>, StatusCode> {
+    let user_sessions_key = format_user_sessions_key(user_id);
+
+    let session_ids = get_set_members(state, &user_sessions_key)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut sessions = Vec::new();
+
+    for session_id in session_ids {
+        match get_session(state, &session_id).await? {
+            Some(session) => sessions.push(session),
+            None => remove_from_set(state, &user_sessions_key, &session_id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        }
+    }
+
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.last_refreshed_at));
+
+    Ok(sessions)
+}
+
+pub async fn revoke_session(state: &AppState, session: &SessionPayload) -> Result<(), StatusCode> {
+    remove_token(state, &session.refresh_token_key)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    remove_token(state, &format_session_key(&session.id))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    remove_from_set(
+        state,
+        &format_user_sessions_key(&session.user_id),
+        &session.id,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+pub async fn revoke_user_sessions(state: &AppState, user_id: &i32) -> Result<(), StatusCode> {
+    for session in get_user_sessions(state, user_id).await? {
+        revoke_session(state, &session).await?;
+    }
+
+    Ok(())
+}
+
+// Ends every refresh session, access token and OAuth grant of a user, for when the password is reset or the
+// account deleted
+pub async fn revoke_user_access(state: &AppState, user_id: &i32) -> Result<(), StatusCode> {
+    revoke_user_sessions(state, user_id).await?;
+    revoke_oauth_grants(state, user_id).await?;
+
+    revoke_user_jwts(state, user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+// A refresh token that was already rotated can only be replayed when it leaked, so its token family is
+// revoked. Within the grace window it's a concurrent refresh instead, which gets a JWT for the session.
+async fn handle_refresh_token_reuse(
+    state: &AppState,
+    refresh_token: &str,
+    client: &SessionClient,
+) -> Result<RotatedRefreshToken, StatusCode> {
+    let rotated_token_payload: Option<RotatedRefreshTokenPayload> = get_token(
+        state,
+        &format_rotated_refresh_token_key(state.config, refresh_token)?,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map(|json| serde_json::from_str(&json).map_err(|_| StatusCode::UNAUTHORIZED))
+    .transpose()?;
+
+    let rotated_token_payload = match rotated_token_payload {
+        Some(payload) => payload,
+        None => return Err(StatusCode::UNAUTHORIZED),
+    };
+
+    let session = get_session(state, &rotated_token_payload.session_id).await?;
+
+    let rotated_seconds_ago = (Utc::now() - rotated_token_payload.rotated_at).num_seconds();
+
+    if rotated_seconds_ago <= REFRESH_TOKEN_REUSE_GRACE_SECONDS {
+        // The session may have been revoked in the meantime, like by a logout in the other tab
+        if session.is_none() {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+
+        let (user_id, name, email, _, _) =
+            get_user_by_id(state, &rotated_token_payload.user_id).await?;
+
+        let jwt = encode_jwt(
+            state.config,
+            &user_id,
+            &name,
+            &email,
+            Some(&rotated_token_payload.session_id),
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        return Ok(RotatedRefreshToken {
+            user_id,
+            name,
+            email,
+            session_id: rotated_token_payload.session_id,
+            jwt,
+            refresh_token: None,
+        });
+    }
+
+    if let Some(session) = session {
+        revoke_session(state, &session).await?;
+    }
+
+    record_security_event(
+        state,
+        &rotated_token_payload.user_id,
+        SECURITY_EVENT_REFRESH_TOKEN_REUSE,
+        Some(&rotated_token_payload.session_id),
+        client,
+    )
+    .await?;
+
+    Err(StatusCode::UNAUTHORIZED)
+}
+
+// Exchanges a refresh token for a new JWT and refresh token within the same session
+pub async fn rotate_refresh_token(
+    state: &AppState,
+    refresh_token: &str,
+    client: &SessionClient,
+) -> Result<RotatedRefreshToken, StatusCode> {
+    let refresh_token_key = format_refresh_token_key(state.config, refresh_token)?;
+
+    // Taken in one step, so of two requests with the same token only one can rotate it
+    let token_payload: Option<RefreshTokenPayload> = take_token(state, &refresh_token_key)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|json| serde_json::from_str(&json).map_err(|_| StatusCode::UNAUTHORIZED))
+        .transpose()?;
+
+    let token_payload = match token_payload {
+        Some(payload) => payload,
+        None => return handle_refresh_token_reuse(state, refresh_token, client).await,
+    };
+
+    let rotated_token_payload = serde_json::to_string(&RotatedRefreshTokenPayload {
+        user_id: token_payload.user_id,
+        session_id: token_payload.session_id.clone(),
+        rotated_at: Utc::now(),
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_token(
+        state,
+        &format_rotated_refresh_token_key(state.config, refresh_token)?,
+        &rotated_token_payload,
+        state.config.tokens.refresh_expiration_seconds,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (user_id, name, email, _, _) = get_user_by_id(state, &token_payload.user_id).await?;
+
+    let token_payload_json =
+        serde_json::to_string(&token_payload).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let new_refresh_token = generate_refresh_token();
+    let new_refresh_token_key = format_refresh_token_key(state.config, &new_refresh_token)?;
+
+    // A session that was revoked in the meantime can't be refreshed anymore
+    if !touch_session(
+        state,
+        &token_payload.session_id,
+        &new_refresh_token_key,
+        client,
+    )
+    .await?
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    set_token(
+        state,
+        &new_refresh_token_key,
+        &token_payload_json,
+        state.config.tokens.refresh_expiration_seconds,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let jwt = encode_jwt(
+        state.config,
+        &user_id,
+        &name,
+        &email,
+        Some(&token_payload.session_id),
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(RotatedRefreshToken {
+        user_id,
+        name,
+        email,
+        session_id: token_payload.session_id,
+        jwt,
+        refresh_token: Some(new_refresh_token),
+    })
+}
+
+// Registers a new session, issues a JWT and refresh token for it and sets them as cookies on the response
+pub async fn start_session(
+    state: &AppState,
+    translations: &Translations,
+    mut response: Response<Body>,
+    client: &SessionClient,
+    id: &i32,
+    name: &str,
+    email: &str,
+) -> Result<Response<Body>, AppError> {
+    let session_id = generate_session_id();
+    let new_refresh_token = generate_refresh_token();
+    let new_redis_refresh_token_key = format_refresh_token_key(state.config, &new_refresh_token)
+        .map_err(|error| AppError::format_service_error(translations, error))?;
+    let now = Utc::now();
+
+    let session = SessionPayload {
+        id: session_id.clone(),
+        user_id: *id,
+        refresh_token_key: new_redis_refresh_token_key.clone(),
+        created_at: now,
+        last_refreshed_at: now,
+        user_agent: client.user_agent.clone(),
+        ip: client.ip.clone(),
+    };
+
+    save_session(state, &session)
+        .await
+        .map_err(|error| AppError::format_status_error(translations, error))?;
+
+    let refresh_token_payload = serde_json::to_string(&RefreshTokenPayload {
+        user_id: *id,
+        session_id: session_id.clone(),
+    })
+    .map_err(|error| AppError::format_service_error(translations, error))?;
+
+    set_token(
+        state,
+        &new_redis_refresh_token_key,
+        &refresh_token_payload,
+        state.config.tokens.refresh_expiration_seconds,
+    )
+    .await
+    .map_err(|error| AppError::format_service_error(translations, error))?;
+
+    let new_jwt = encode_jwt(state.config, id, name, email, Some(&session_id))
+        .map_err(|error| AppError::format_service_error(translations, error))?;
+
+    response = set_cookie(
+        translations,
+        response,
+        "Bearer",
+        &new_jwt,
+        Some(state.config.tokens.bearer_expiration_seconds),
+    )?;
+    response = set_cookie(
+        translations,
+        response,
+        "RefreshToken",
+        &new_refresh_token,
+        Some(state.config.tokens.refresh_expiration_seconds),
+    )?;
+
+    Ok(response)
+}
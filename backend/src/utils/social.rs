@@ -0,0 +1,399 @@
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use axum::http::StatusCode;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+use crate::{
+    constants::social::{
+        GITHUB_AUTHORIZATION_URL, GITHUB_EMAILS_URL, GITHUB_TOKEN_URL, GITHUB_USER_URL,
+        GOOGLE_AUTHORIZATION_URL, GOOGLE_TOKEN_URL, GOOGLE_USERINFO_URL,
+        SOCIAL_LINK_EXPIRATION_SECONDS, SOCIAL_LINK_TOKEN_BYTES, SOCIAL_LOGIN_PASSWORD_LENGTH,
+    },
+    models::{
+        config::models::{Config, SocialProviderConfig},
+        general::AppState,
+        social::models::{
+            GitHubEmail, GitHubUser, OidcDiscoveryDocument, OidcUserInfo, ProviderTokenResponse,
+            SocialLinkPayload, SocialProfile, SocialProvider, SocialProviderKind, SocialUser,
+        },
+    },
+    utils::{
+        auth::hash_password,
+        hashing::hash_with_secret,
+        http::get_http_client,
+        oauth::{format_redirect_uri, generate_oauth_token},
+        oidc::get_issuer_url,
+        password_history::change_user_password,
+        redis::set_token,
+        session::revoke_user_access,
+        user::{confirm_user, create_user, get_user_by_email},
+    },
+};
+
+static SOCIAL_PROVIDERS: OnceLock<Vec<SocialProvider>> = OnceLock::new();
+
+pub fn format_social_login_state_key(state: &str) -> String {
+    let state_key = format!("social-state:{}", state);
+
+    state_key
+}
+
+pub fn format_social_link_key(config: &Config, link_token: &str) -> Result<String, StatusCode> {
+    let token_hash = hash_with_secret(config, &format!("social-link:{}", link_token))?;
+
+    Ok(format!("social-link:{}", token_hash))
+}
+
+pub fn generate_social_login_password() -> String {
+    let password: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SOCIAL_LOGIN_PASSWORD_LENGTH)
+        .map(char::from)
+        .collect();
+
+    password
+}
+
+// Google and GitHub have known endpoints, any other provider needs an issuer URL
+pub fn is_known_social_provider(name: &str) -> bool {
+    matches!(name, "google" | "github")
+}
+
+pub async fn discover_oidc_provider(
+    http_client: &reqwest::Client,
+    issuer_url: &str,
+) -> Result<OidcDiscoveryDocument, String> {
+    let discovery_url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer_url.trim_end_matches('/')
+    );
+
+    let discovery_document = http_client
+        .get(&discovery_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| format!("Error fetching {}: {}", discovery_url, err))?
+        .json::<OidcDiscoveryDocument>()
+        .await
+        .map_err(|err| format!("Error parsing {}: {}", discovery_url, err))?;
+
+    Ok(discovery_document)
+}
+
+// Google and GitHub have known endpoints, any other provider is configured through OIDC discovery
+pub async fn load_social_provider(
+    http_client: &reqwest::Client,
+    name: &str,
+    provider_config: &SocialProviderConfig,
+) -> Result<SocialProvider, String> {
+    let client_id = provider_config.client_id.clone();
+    let client_secret = provider_config.client_secret.clone();
+
+    let provider = match name {
+        "google" => SocialProvider {
+            name: name.to_string(),
+            kind: SocialProviderKind::Oidc,
+            client_id,
+            client_secret,
+            authorization_url: GOOGLE_AUTHORIZATION_URL.to_string(),
+            token_url: GOOGLE_TOKEN_URL.to_string(),
+            userinfo_url: GOOGLE_USERINFO_URL.to_string(),
+            emails_url: None,
+            scopes: "openid email profile".to_string(),
+        },
+        "github" => SocialProvider {
+            name: name.to_string(),
+            kind: SocialProviderKind::GitHub,
+            client_id,
+            client_secret,
+            authorization_url: GITHUB_AUTHORIZATION_URL.to_string(),
+            token_url: GITHUB_TOKEN_URL.to_string(),
+            userinfo_url: GITHUB_USER_URL.to_string(),
+            emails_url: Some(GITHUB_EMAILS_URL.to_string()),
+            scopes: "read:user user:email".to_string(),
+        },
+        _ => {
+            let issuer_url = provider_config
+                .issuer_url
+                .as_deref()
+                .ok_or_else(|| format!("Missing issuer URL for social provider {}", name))?;
+            let discovery_document = discover_oidc_provider(http_client, issuer_url).await?;
+
+            SocialProvider {
+                name: name.to_string(),
+                kind: SocialProviderKind::Oidc,
+                client_id,
+                client_secret,
+                authorization_url: discovery_document.authorization_endpoint,
+                token_url: discovery_document.token_endpoint,
+                userinfo_url: discovery_document.userinfo_endpoint,
+                emails_url: None,
+                scopes: provider_config
+                    .scopes
+                    .clone()
+                    .unwrap_or_else(|| "openid email profile".to_string()),
+            }
+        }
+    };
+
+    Ok(provider)
+}
+
+// Loads the configured providers once at startup, social login is disabled without any
+pub async fn init_social_providers(
+    provider_configs: &BTreeMap<String, SocialProviderConfig>,
+) -> Result<(), String> {
+    if SOCIAL_PROVIDERS.get().is_some() {
+        return Ok(());
+    }
+
+    let mut providers = Vec::new();
+
+    for (name, provider_config) in provider_configs {
+        providers.push(load_social_provider(get_http_client(), name, provider_config).await?);
+    }
+
+    let _ = SOCIAL_PROVIDERS.set(providers);
+
+    Ok(())
+}
+
+pub fn get_social_providers() -> &'static [SocialProvider] {
+    SOCIAL_PROVIDERS
+        .get()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+pub fn get_social_provider(name: &str) -> Option<&'static SocialProvider> {
+    get_social_providers()
+        .iter()
+        .find(|provider| provider.name == name)
+}
+
+// The URL the provider sends the user back to, it has to be registered with the provider
+pub fn format_social_callback_url(config: &Config, provider: &SocialProvider) -> String {
+    let callback_url = format!(
+        "{}/api/auth/social/{}/callback",
+        get_issuer_url(config),
+        provider.name
+    );
+
+    callback_url
+}
+
+pub fn format_social_authorization_url(
+    provider: &SocialProvider,
+    callback_url: &str,
+    state: &str,
+    code_challenge: &str,
+) -> Result<String, StatusCode> {
+    format_redirect_uri(
+        &provider.authorization_url,
+        &[
+            ("response_type", "code"),
+            ("client_id", &provider.client_id),
+            ("redirect_uri", callback_url),
+            ("scope", &provider.scopes),
+            ("state", state),
+            ("code_challenge", code_challenge),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// Returns the provider's access token, failures mean the code was invalid or the provider is unreachable
+pub async fn exchange_social_authorization_code(
+    http_client: &reqwest::Client,
+    provider: &SocialProvider,
+    code: &str,
+    code_verifier: &str,
+    callback_url: &str,
+) -> Result<String, StatusCode> {
+    let token_response = http_client
+        .post(&provider.token_url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", callback_url),
+            ("client_id", &provider.client_id),
+            ("client_secret", &provider.client_secret),
+            ("code_verifier", code_verifier),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| StatusCode::BAD_GATEWAY)?
+        .json::<ProviderTokenResponse>()
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+
+    // GitHub reports errors with a 200 response without an access token
+    token_response.access_token.ok_or(StatusCode::UNAUTHORIZED)
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    http_client: &reqwest::Client,
+    url: &str,
+    access_token: &str,
+) -> Result<T, StatusCode> {
+    let body = http_client
+        .get(url)
+        .bearer_auth(access_token)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| StatusCode::BAD_GATEWAY)?
+        .json::<T>()
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+
+    Ok(body)
+}
+
+// Fetched over the back channel with the access token, so the profile can't be forged by the browser
+pub async fn fetch_social_profile(
+    http_client: &reqwest::Client,
+    provider: &SocialProvider,
+    access_token: &str,
+) -> Result<SocialProfile, StatusCode> {
+    let profile = match provider.kind {
+        SocialProviderKind::Oidc => {
+            let user_info: OidcUserInfo =
+                fetch_json(http_client, &provider.userinfo_url, access_token).await?;
+
+            SocialProfile {
+                subject: user_info.sub,
+                email: user_info.email,
+                email_verified: user_info.email_verified,
+                name: user_info.name,
+            }
+        }
+        SocialProviderKind::GitHub => {
+            let user: GitHubUser =
+                fetch_json(http_client, &provider.userinfo_url, access_token).await?;
+
+            let emails: Vec<GitHubEmail> = match &provider.emails_url {
+                Some(emails_url) => fetch_json(http_client, emails_url, access_token).await?,
+                None => Vec::new(),
+            };
+
+            let primary_email = emails.into_iter().find(|email| email.primary);
+
+            SocialProfile {
+                subject: user.id.to_string(),
+                email_verified: primary_email.as_ref().is_some_and(|email| email.verified),
+                email: primary_email.map(|email| email.email),
+                name: user.name.or(Some(user.login)),
+            }
+        }
+    };
+
+    Ok(profile)
+}
+
+pub async fn get_linked_identity_user_id(
+    state: &AppState,
+    provider: &str,
+    subject: &str,
+) -> Result<Option<i32>, StatusCode> {
+    Ok(state
+        .linked_identities
+        .get_linked_identity_user_id(provider, subject)
+        .await?)
+}
+
+pub async fn create_linked_identity(
+    state: &AppState,
+    user_id: &i32,
+    provider: &str,
+    subject: &str,
+) -> Result<(), StatusCode> {
+    Ok(state
+        .linked_identities
+        .create_linked_identity(user_id, provider, subject)
+        .await?)
+}
+
+async fn create_social_link_token(
+    state: &AppState,
+    user_id: &i32,
+    provider: &str,
+    subject: &str,
+) -> Result<String, StatusCode> {
+    let link_token = generate_oauth_token(SOCIAL_LINK_TOKEN_BYTES);
+
+    let link_payload = serde_json::to_string(&SocialLinkPayload {
+        user_id: *user_id,
+        provider: provider.to_string(),
+        subject: subject.to_string(),
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_token(
+        state,
+        &format_social_link_key(state.config, &link_token)?,
+        &link_payload,
+        SOCIAL_LINK_EXPIRATION_SECONDS,
+    )
+    .await?;
+
+    Ok(link_token)
+}
+
+// Finds the user for a provider identity. An unknown email gets a new account, a confirmed account with the
+// same email has to confirm its password before it's linked. An unconfirmed one may have been registered by
+// someone else ahead of the real owner, so its password and sessions are dropped before the provider takes it.
+pub async fn resolve_social_user(
+    state: &AppState,
+    provider: &str,
+    profile: SocialProfile,
+) -> Result<SocialUser, StatusCode> {
+    if let Some(user_id) = get_linked_identity_user_id(state, provider, &profile.subject).await? {
+        return Ok(SocialUser::Linked(user_id));
+    }
+
+    let email = match (profile.email, profile.email_verified) {
+        (Some(email), true) => email.to_lowercase(),
+        _ => return Ok(SocialUser::EmailNotVerified),
+    };
+
+    let user_id = match get_user_by_email(state, &email).await {
+        Ok((id, _, _, _, _, true)) => {
+            let link_token =
+                create_social_link_token(state, &id, provider, &profile.subject).await?;
+
+            return Ok(SocialUser::PendingLink(link_token));
+        }
+        Ok((id, _, _, _, _, false)) => {
+            let password_hash = hash_password(&generate_social_login_password())
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            change_user_password(state, &id, &password_hash).await?;
+            revoke_user_access(state, &id).await?;
+            confirm_user(state, &id).await?;
+
+            id
+        }
+        Err(_) => {
+            let name = profile
+                .name
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string());
+
+            let id = create_user(state, &name, &email, &generate_social_login_password()).await?;
+            confirm_user(state, &id).await?;
+
+            id
+        }
+    };
+
+    create_linked_identity(state, &user_id, provider, &profile.subject).await?;
+
+    Ok(SocialUser::Linked(user_id))
+}
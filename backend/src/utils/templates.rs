@@ -1,8 +1,9 @@
-use crate::utils::dates::get_current_year;
+use crate::{models::config::models::Config, utils::dates::get_current_year};
 use std::collections::HashMap;
 use tera::{Context, Tera};
 
 pub fn generate_template(
+    config: &Config,
     template: &str,
     template_name: &str,
     variables: HashMap<&str, &str>,
@@ -13,7 +14,7 @@ pub fn generate_template(
 
     let mut context = Context::new();
     context.insert("year", &get_current_year());
-    context.insert("company_name", "Authentication Inc.");
+    context.insert("company_name", &config.company_name);
 
     for (key, value) in variables {
         context.insert(key, &value);
@@ -0,0 +1,171 @@
+use axum::http::StatusCode;
+
+use crate::{
+    constants::throttle::{
+        LOGIN_BACKOFF_BASE_SECONDS, LOGIN_EMAIL_FREE_ATTEMPTS, LOGIN_EMAIL_LOCKOUT_FAILURES,
+        LOGIN_EMAIL_LOCKOUT_SECONDS, LOGIN_FAILURE_WINDOW_SECONDS, LOGIN_IP_FREE_ATTEMPTS,
+        LOGIN_IP_LOCKOUT_FAILURES, LOGIN_IP_LOCKOUT_SECONDS,
+    },
+    models::{general::AppState, session::models::SessionClient},
+    utils::redis::{
+        decrement_counter, get_time_to_live, increment_counter, remove_token, set_token,
+    },
+};
+
+pub struct LoginThrottlePolicy {
+    pub scope: &'static str,
+    pub free_attempts: i64,
+    pub lockout_failures: i64,
+    pub lockout_seconds: u64,
+}
+
+pub const EMAIL_LOGIN_THROTTLE_POLICY: LoginThrottlePolicy = LoginThrottlePolicy {
+    scope: "email",
+    free_attempts: LOGIN_EMAIL_FREE_ATTEMPTS,
+    lockout_failures: LOGIN_EMAIL_LOCKOUT_FAILURES,
+    lockout_seconds: LOGIN_EMAIL_LOCKOUT_SECONDS,
+};
+
+pub const IP_LOGIN_THROTTLE_POLICY: LoginThrottlePolicy = LoginThrottlePolicy {
+    scope: "ip",
+    free_attempts: LOGIN_IP_FREE_ATTEMPTS,
+    lockout_failures: LOGIN_IP_LOCKOUT_FAILURES,
+    lockout_seconds: LOGIN_IP_LOCKOUT_SECONDS,
+};
+
+pub fn format_login_failures_key(scope: &str, identifier: &str) -> String {
+    let login_failures_key = format!("login-failures:{}:{}", scope, identifier);
+
+    login_failures_key
+}
+
+pub fn format_login_throttle_key(scope: &str, identifier: &str) -> String {
+    let login_throttle_key = format!("login-throttle:{}:{}", scope, identifier);
+
+    login_throttle_key
+}
+
+// The first failures are free, after that the wait doubles with every failure until the account or IP is locked out
+pub fn get_login_backoff_seconds(failures: i64, policy: &LoginThrottlePolicy) -> u64 {
+    if failures >= policy.lockout_failures {
+        return policy.lockout_seconds;
+    }
+
+    if failures <= policy.free_attempts {
+        return 0;
+    }
+
+    let exponent = u32::try_from(failures - policy.free_attempts - 1).unwrap_or(u32::MAX);
+
+    LOGIN_BACKOFF_BASE_SECONDS
+        .saturating_mul(2u64.saturating_pow(exponent))
+        .min(policy.lockout_seconds)
+}
+
+fn get_login_throttle_targets(
+    email: &str,
+    client: &SessionClient,
+) -> Vec<(&'static LoginThrottlePolicy, String)> {
+    let mut targets = vec![(&EMAIL_LOGIN_THROTTLE_POLICY, email.trim().to_lowercase())];
+
+    if let Some(ip) = &client.ip {
+        targets.push((&IP_LOGIN_THROTTLE_POLICY, ip.clone()));
+    }
+
+    targets
+}
+
+// Counts the attempt before anything is checked, so parallel requests can't all slip through on the same
+// count. Returns how many seconds the client has to wait when it may not try this email now
+pub async fn start_login_attempt(
+    state: &AppState,
+    email: &str,
+    client: &SessionClient,
+) -> Result<Option<u64>, StatusCode> {
+    let targets = get_login_throttle_targets(email, client);
+    let mut attempts = Vec::new();
+
+    for (policy, identifier) in &targets {
+        let failures = increment_counter(
+            state,
+            &format_login_failures_key(policy.scope, identifier),
+            LOGIN_FAILURE_WINDOW_SECONDS,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        attempts.push(failures);
+    }
+
+    let mut retry_after: Option<u64> = None;
+
+    for (policy, identifier) in &targets {
+        let time_to_live =
+            get_time_to_live(state, &format_login_throttle_key(policy.scope, identifier))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if let Some(time_to_live) = time_to_live {
+            retry_after = retry_after.max(Some(time_to_live as u64));
+        }
+    }
+
+    if retry_after.is_some() {
+        // A rejected attempt never reached the password check, so it doesn't count as a failure
+        for (policy, identifier) in &targets {
+            decrement_counter(state, &format_login_failures_key(policy.scope, identifier))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+
+        return Ok(retry_after);
+    }
+
+    // The attempt counts as a failure until clear_login_failures says otherwise
+    for ((policy, identifier), failures) in targets.iter().zip(attempts) {
+        let backoff_seconds = get_login_backoff_seconds(failures, policy);
+
+        if backoff_seconds > 0 {
+            set_token(
+                state,
+                &format_login_throttle_key(policy.scope, identifier),
+                &failures.to_string(),
+                backoff_seconds as i32,
+            )
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+    }
+
+    Ok(None)
+}
+
+// Resets the email after a successful login. The IP only gets this attempt back, other accounts
+// guessed from the same IP keep counting
+pub async fn clear_login_failures(
+    state: &AppState,
+    email: &str,
+    client: &SessionClient,
+) -> Result<(), StatusCode> {
+    let email = email.trim().to_lowercase();
+    let policy = &EMAIL_LOGIN_THROTTLE_POLICY;
+
+    remove_token(state, &format_login_failures_key(policy.scope, &email))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    remove_token(state, &format_login_throttle_key(policy.scope, &email))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if let Some(ip) = &client.ip {
+        decrement_counter(
+            state,
+            &format_login_failures_key(IP_LOGIN_THROTTLE_POLICY.scope, ip),
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use base32::Alphabet;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use sha1::Sha1;
+
+use crate::{
+    constants::two_factor::{
+        TOTP_ALLOWED_DRIFT_STEPS, TOTP_DIGITS, TOTP_PERIOD_SECONDS, TOTP_SECRET_BYTES,
+    },
+    models::config::models::Config,
+};
+
+const SECRET_ALPHABET: Alphabet = Alphabet::Rfc4648 { padding: false };
+
+pub fn generate_totp_secret() -> String {
+    let mut secret = [0u8; TOTP_SECRET_BYTES];
+    thread_rng().fill_bytes(&mut secret);
+
+    base32::encode(SECRET_ALPHABET, &secret)
+}
+
+// Authenticator apps list the code under the company name
+pub fn format_otpauth_uri(config: &Config, secret: &str, email: &str) -> String {
+    let issuer = urlencode(&config.company_name);
+
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer,
+        urlencode(email),
+        secret,
+        issuer,
+        TOTP_DIGITS,
+        TOTP_PERIOD_SECONDS
+    )
+}
+
+pub fn get_totp_time_step(unix_time: u64) -> u64 {
+    unix_time / TOTP_PERIOD_SECONDS
+}
+
+// HOTP (RFC 4226) value for the given counter, which for TOTP (RFC 6238) is the time step
+pub fn generate_totp_code(secret: &[u8], time_step: u64, digits: u32) -> Option<String> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).ok()?;
+    mac.update(&time_step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    let code = binary % 10u32.pow(digits);
+
+    Some(format!("{:0width$}", code, width = digits as usize))
+}
+
+// Returns the time step the code matched, so callers can reject a code that was already used
+pub fn verify_totp_code(secret: &str, code: &str, unix_time: u64) -> Option<u64> {
+    let secret = base32::decode(SECRET_ALPHABET, secret)?;
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if code.len() != TOTP_DIGITS as usize {
+        return None;
+    }
+
+    let current_step = get_totp_time_step(unix_time);
+    let first_step = current_step.saturating_sub(TOTP_ALLOWED_DRIFT_STEPS);
+    let last_step = current_step + TOTP_ALLOWED_DRIFT_STEPS;
+
+    (first_step..=last_step).find(|step| {
+        generate_totp_code(&secret, *step, TOTP_DIGITS).is_some_and(|expected| expected == code)
+    })
+}
+
+fn urlencode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
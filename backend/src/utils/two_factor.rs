@@ -0,0 +1,98 @@
+use crate::{
+    constants::two_factor::TWO_FACTOR_CHALLENGE_EXPIRATION_SECONDS,
+    models::{general::AppState, two_factor::models::TwoFactorChallengePayload},
+    utils::redis::set_token,
+};
+use axum::http::StatusCode;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+pub fn format_two_factor_challenge_key(challenge_token: &str) -> String {
+    let challenge_key = format!("2fa-challenge:{}", challenge_token);
+
+    challenge_key
+}
+
+// Counted apart from the challenge, so parallel guesses each get their own attempt
+pub fn format_two_factor_challenge_attempts_key(challenge_token: &str) -> String {
+    let attempts_key = format!("2fa-challenge-attempts:{}", challenge_token);
+
+    attempts_key
+}
+
+pub fn format_totp_last_step_key(user_id: &i32) -> String {
+    let last_step_key = format!("totp-last-step:{}", user_id);
+
+    last_step_key
+}
+
+// Claimed by the first login that uses a step, so the same code can't be accepted twice at once
+pub fn format_totp_used_step_key(user_id: &i32, step: u64) -> String {
+    let used_step_key = format!("totp-used-step:{}:{}", user_id, step);
+
+    used_step_key
+}
+
+pub fn generate_two_factor_challenge_token() -> String {
+    let token: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    token
+}
+
+// Stores a pending second factor check for the user and returns the token to complete it with
+pub async fn create_two_factor_challenge(
+    state: &AppState,
+    user_id: &i32,
+) -> Result<String, StatusCode> {
+    let challenge_token = generate_two_factor_challenge_token();
+
+    let challenge_payload = serde_json::to_string(&TwoFactorChallengePayload { user_id: *user_id })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_token(
+        state,
+        &format_two_factor_challenge_key(&challenge_token),
+        &challenge_payload,
+        TWO_FACTOR_CHALLENGE_EXPIRATION_SECONDS,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(challenge_token)
+}
+
+// Returns the stored secret and whether it has been confirmed, or None when 2FA was never set up
+pub async fn get_two_factor_by_user_id(
+    state: &AppState,
+    user_id: &i32,
+) -> Result<Option<(String, bool)>, StatusCode> {
+    Ok(state.two_factors.get_two_factor_by_user_id(user_id).await?)
+}
+
+pub async fn is_two_factor_enabled(state: &AppState, user_id: &i32) -> Result<bool, StatusCode> {
+    let two_factor = get_two_factor_by_user_id(state, user_id).await?;
+
+    Ok(matches!(two_factor, Some((_, true))))
+}
+
+pub async fn set_two_factor_secret(
+    state: &AppState,
+    user_id: &i32,
+    secret: &str,
+) -> Result<u64, StatusCode> {
+    Ok(state
+        .two_factors
+        .set_two_factor_secret(user_id, secret)
+        .await?)
+}
+
+pub async fn enable_two_factor(state: &AppState, user_id: &i32) -> Result<u64, StatusCode> {
+    Ok(state.two_factors.enable_two_factor(user_id).await?)
+}
+
+pub async fn delete_two_factor(state: &AppState, user_id: &i32) -> Result<u64, StatusCode> {
+    Ok(state.two_factors.delete_two_factor(user_id).await?)
+}
@@ -1,67 +1,51 @@
 use crate::{
-    models::general::AppState,
-    queries::user::{
-        CONFIRM_USER, CREATE_USER, DELETE_USER, GET_USER_BY_EMAIL, GET_USER_BY_ID,
-        UPDATE_NON_SENSITIVE_USER_FIELDS, UPDATE_USER_EMAIL, UPDATE_USER_PASSWORD,
+    constants::user::PASSWORD_RESET_TOKEN_BYTES,
+    models::{config::models::Config, general::AppState},
+    traits::user_repository::{UserRow, UserWithPasswordHash},
+    utils::{
+        auth::hash_password, encoding::encode_base64url, errors::ServiceError,
+        hashing::hash_with_secret,
     },
-    utils::auth::hash_password,
 };
-use axum::http::StatusCode;
-use sqlx::mysql::MySqlQueryResult;
+use rand::{rngs::OsRng, RngCore};
 
-pub fn format_reset_token_key(token: &str) -> String {
-    let reset_token_key = format!("reset-token:{}", token);
+pub fn generate_reset_token() -> String {
+    let mut token = [0u8; PASSWORD_RESET_TOKEN_BYTES];
+    OsRng.fill_bytes(&mut token);
 
-    reset_token_key
+    encode_base64url(&token)
 }
 
-pub async fn get_user_by_email(
-    state: &AppState,
-    email: &str,
-) -> Result<(i32, String, String, String, Option<String>, bool), StatusCode> {
-    let user =
-        sqlx::query_as::<_, (i32, String, String, String, Option<String>, bool)>(GET_USER_BY_EMAIL)
-            .bind(email)
-            .fetch_one(&state.db_pool)
-            .await
-            .map_err(|_| StatusCode::UNAUTHORIZED);
+// Only a keyed hash of the reset token is stored, like refresh tokens
+pub fn format_reset_token_key(config: &Config, token: &str) -> Result<String, ServiceError> {
+    let token_hash = hash_with_secret(config, &format!("reset-token:{}", token))?;
 
-    user
+    Ok(format!("reset-token:{}", token_hash))
 }
 
-pub async fn get_user_by_id(
+pub async fn get_user_by_email(
     state: &AppState,
-    id: &i32,
-) -> Result<(i32, String, String, Option<String>, bool), StatusCode> {
-    let user = sqlx::query_as::<_, (i32, String, String, Option<String>, bool)>(GET_USER_BY_ID)
-        .bind(id)
-        .fetch_one(&state.db_pool)
-        .await
-        .map_err(|_| StatusCode::UNAUTHORIZED);
+    email: &str,
+) -> Result<UserWithPasswordHash, ServiceError> {
+    state.users.get_user_by_email(email).await
+}
 
-    user
+pub async fn get_user_by_id(state: &AppState, id: &i32) -> Result<UserRow, ServiceError> {
+    state.users.get_user_by_id(id).await
 }
 
+// Hashes the password and returns the id of the new user
 pub async fn create_user(
     state: &AppState,
     name: &str,
     email: &str,
     password: &str,
-) -> Result<MySqlQueryResult, StatusCode> {
-    let password_hash = match hash_password(&password) {
-        Ok(hash) => hash,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
-
-    let created_user_result = sqlx::query(CREATE_USER)
-        .bind(name)
-        .bind(email)
-        .bind(password_hash)
-        .execute(&state.db_pool)
+) -> Result<i32, ServiceError> {
+    let password_hash = hash_password(&password)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+        .map_err(|_| ServiceError::Hashing("failed to hash the password".to_string()))?;
 
-    created_user_result
+    state.users.create_user(name, email, &password_hash).await
 }
 
 pub async fn update_non_sensitive_user_fields(
@@ -69,64 +53,33 @@ pub async fn update_non_sensitive_user_fields(
     id: &i32,
     name: &str,
     phone: Option<&str>,
-) -> Result<MySqlQueryResult, StatusCode> {
-    let update_user_result = sqlx::query(UPDATE_NON_SENSITIVE_USER_FIELDS)
-        .bind(name)
-        .bind(phone)
-        .bind(id)
-        .execute(&state.db_pool)
+) -> Result<(), ServiceError> {
+    state
+        .users
+        .update_non_sensitive_user_fields(id, name, phone)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
-
-    update_user_result
 }
 
 pub async fn update_user_email(
     state: &AppState,
     id: &i32,
     email: &str,
-) -> Result<MySqlQueryResult, StatusCode> {
-    let update_user_result = sqlx::query(UPDATE_USER_EMAIL)
-        .bind(email)
-        .bind(id)
-        .execute(&state.db_pool)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
-
-    update_user_result
+) -> Result<(), ServiceError> {
+    state.users.update_user_email(id, email).await
 }
 
 pub async fn update_user_password(
     state: &AppState,
     id: &i32,
     password_hash: &str,
-) -> Result<MySqlQueryResult, StatusCode> {
-    let update_user_result = sqlx::query(UPDATE_USER_PASSWORD)
-        .bind(password_hash)
-        .bind(id)
-        .execute(&state.db_pool)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
-
-    update_user_result
+) -> Result<(), ServiceError> {
+    state.users.update_user_password(id, password_hash).await
 }
 
-pub async fn delete_user_by_id(state: &AppState, id: &i32) -> Result<MySqlQueryResult, StatusCode> {
-    let delete_user_result = sqlx::query(DELETE_USER)
-        .bind(id)
-        .execute(&state.db_pool)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
-
-    delete_user_result
+pub async fn delete_user_by_id(state: &AppState, id: &i32) -> Result<(), ServiceError> {
+    state.users.delete_user_by_id(id).await
 }
 
-pub async fn confirm_user(state: &AppState, id: &i32) -> Result<MySqlQueryResult, StatusCode> {
-    let confirm_user_result = sqlx::query(CONFIRM_USER)
-        .bind(id)
-        .execute(&state.db_pool)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
-
-    confirm_user_result
+pub async fn confirm_user(state: &AppState, id: &i32) -> Result<(), ServiceError> {
+    state.users.confirm_user(id).await
 }
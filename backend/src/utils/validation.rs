@@ -1,6 +1,7 @@
 use regex::Regex;
 
 use crate::models::user::models::{PasswordResetUser, RegisterUser, UpdateUser};
+use crate::utils::password_policy::{get_password_policy, get_password_policy_feedback};
 
 pub fn get_email_feedback_message(email: &str) -> Option<&str> {
     if Regex::new(r"[^A-Za-z0-9.@-]").ok()?.is_match(email) {
@@ -21,20 +22,9 @@ pub fn get_email_feedback_message(email: &str) -> Option<&str> {
     }
 }
 
-pub fn get_password_feedback_message(password: &str) -> Option<&str> {
-    if password.len() < 8 {
-        Some("authentication.errors.password_too_short")
-    } else if !Regex::new(r"[A-Z]").ok()?.is_match(password) {
-        Some("authentication.errors.missing_uppercase")
-    } else if !Regex::new(r"\d").ok()?.is_match(password) {
-        Some("authentication.errors.missing_number")
-    } else if !Regex::new(r"[-!]").ok()?.is_match(password) {
-        Some("authentication.errors.missing_special_character")
-    } else if Regex::new(r"[^A-Za-z\d\-!]").ok()?.is_match(password) {
-        Some("authentication.errors.invalid_characters")
-    } else {
-        None
-    }
+// Checks the password against the configured policy, user_inputs are the name and email of the user
+pub fn get_password_feedback_message(password: &str, user_inputs: &[&str]) -> Option<&'static str> {
+    get_password_policy_feedback(password, get_password_policy(), user_inputs)
 }
 
 pub fn get_phone_number_feedback_message(phone: &str) -> Option<&str> {
@@ -68,7 +58,7 @@ pub fn validate_register_user_data(user: &RegisterUser) -> Option<&str> {
         return Some(error);
     }
 
-    if let Some(error) = get_password_feedback_message(&user.password) {
+    if let Some(error) = get_password_feedback_message(&user.password, &[&user.name, &user.email]) {
         return Some(error);
     }
 
@@ -94,14 +84,20 @@ pub fn validate_update_user_data(user: &UpdateUser) -> Option<&str> {
         }
     }
 
-    if let (Some(password), Some(password_confirm)) = (&user.password, &user.password_confirm) {
-        if let Some(error) = get_password_feedback_message(password) {
-            return Some(error);
-        }
+    match (&user.password, &user.password_confirm) {
+        (Some(password), Some(password_confirm)) => {
+            if let Some(error) = get_password_feedback_message(password, &[&user.name, &user.email])
+            {
+                return Some(error);
+            }
 
-        if password != password_confirm {
-            return Some("auth.errors.password_mismatch");
+            if password != password_confirm {
+                return Some("auth.errors.password_mismatch");
+            }
         }
+        // Only one of the two would otherwise be dropped without changing the password
+        (Some(_), None) | (None, Some(_)) => return Some("auth.errors.password_mismatch"),
+        (None, None) => (),
     }
 
     if let Some(phone) = &user.phone {
@@ -113,8 +109,11 @@ pub fn validate_update_user_data(user: &UpdateUser) -> Option<&str> {
     None
 }
 
-pub fn validate_password_reset_user_data(user: &PasswordResetUser) -> Option<&str> {
-    if let Some(error) = get_password_feedback_message(&user.password) {
+pub fn validate_password_reset_user_data(
+    user: &PasswordResetUser,
+    user_inputs: &[&str],
+) -> Option<&'static str> {
+    if let Some(error) = get_password_feedback_message(&user.password, user_inputs) {
         return Some(error);
     }
 
@@ -0,0 +1,255 @@
+use axum::http::StatusCode;
+use ciborium::value::Value;
+use p256::ecdsa::{signature::Verifier, DerSignature, VerifyingKey};
+use rand::{thread_rng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    constants::passkey::{WEBAUTHN_CHALLENGE_BYTES, WEBAUTHN_ES256_ALGORITHM},
+    models::{
+        config::models::Config,
+        passkey::models::{CollectedClientData, RegisteredPasskey},
+    },
+    utils::encoding::encode_base64url,
+};
+
+// Authenticator data flags, see https://www.w3.org/TR/webauthn-2/#authenticator-data
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+const RP_ID_HASH_LENGTH: usize = 32;
+const AUTHENTICATOR_DATA_MIN_LENGTH: usize = RP_ID_HASH_LENGTH + 1 + 4;
+const AAGUID_LENGTH: usize = 16;
+
+// COSE key parameters, see RFC 9053
+const COSE_KEY_TYPE: i128 = 1;
+const COSE_KEY_ALGORITHM: i128 = 3;
+const COSE_EC2_CURVE: i128 = -1;
+const COSE_EC2_X: i128 = -2;
+const COSE_EC2_Y: i128 = -3;
+const COSE_KEY_TYPE_EC2: i128 = 2;
+const COSE_CURVE_P256: i128 = 1;
+
+pub fn generate_webauthn_challenge() -> String {
+    let mut challenge = [0u8; WEBAUTHN_CHALLENGE_BYTES];
+    thread_rng().fill_bytes(&mut challenge);
+
+    encode_base64url(&challenge)
+}
+
+pub fn format_registration_challenge_key(user_id: &i32) -> String {
+    let challenge_key = format!("webauthn-registration:{}", user_id);
+
+    challenge_key
+}
+
+pub fn format_authentication_challenge_key(challenge: &str) -> String {
+    let challenge_key = format!("webauthn-authentication:{}", challenge);
+
+    challenge_key
+}
+
+// Passkeys are scoped to the frontend, so its URL is both the expected origin and the source of the RP ID
+pub fn get_relying_party(config: &Config) -> Result<(String, String), StatusCode> {
+    let origin = config.client_base_url.trim_end_matches('/').to_string();
+
+    let rp_id = get_relying_party_id(&origin).ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((rp_id, origin))
+}
+
+pub fn get_relying_party_id(origin: &str) -> Option<String> {
+    let (_, authority) = origin.split_once("://")?;
+    let host = authority.split(['/', ':']).next()?;
+
+    if host.is_empty() {
+        return None;
+    }
+
+    Some(host.to_lowercase())
+}
+
+pub fn parse_client_data(client_data_json: &[u8]) -> Result<CollectedClientData, StatusCode> {
+    serde_json::from_slice(client_data_json).map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+pub fn verify_client_data(
+    client_data: &CollectedClientData,
+    expected_type: &str,
+    expected_challenge: &str,
+    expected_origin: &str,
+) -> Result<(), StatusCode> {
+    if client_data.ceremony_type != expected_type
+        || client_data.challenge != expected_challenge
+        || client_data.origin != expected_origin
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(())
+}
+
+// Returns the flags and signature counter after checking the RP ID hash and user presence
+fn parse_authenticator_data(auth_data: &[u8], rp_id: &str) -> Result<(u8, u32), StatusCode> {
+    if auth_data.len() < AUTHENTICATOR_DATA_MIN_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let rp_id_hash = Sha256::digest(rp_id.as_bytes());
+
+    if auth_data[..RP_ID_HASH_LENGTH] != rp_id_hash[..] {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let flags = auth_data[RP_ID_HASH_LENGTH];
+
+    if flags & FLAG_USER_PRESENT == 0 {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let sign_count = u32::from_be_bytes([
+        auth_data[RP_ID_HASH_LENGTH + 1],
+        auth_data[RP_ID_HASH_LENGTH + 2],
+        auth_data[RP_ID_HASH_LENGTH + 3],
+        auth_data[RP_ID_HASH_LENGTH + 4],
+    ]);
+
+    Ok((flags, sign_count))
+}
+
+fn get_map_value<'a>(map: &'a [(Value, Value)], key: &Value) -> Option<&'a Value> {
+    map.iter()
+        .find(|(map_key, _)| map_key == key)
+        .map(|(_, value)| value)
+}
+
+fn get_cose_integer(map: &[(Value, Value)], key: i128) -> Option<i128> {
+    match get_map_value(map, &Value::Integer(key.try_into().ok()?))? {
+        Value::Integer(value) => Some(i128::from(*value)),
+        _ => None,
+    }
+}
+
+fn get_cose_bytes(map: &[(Value, Value)], key: i128) -> Option<Vec<u8>> {
+    match get_map_value(map, &Value::Integer(key.try_into().ok()?))? {
+        Value::Bytes(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+// Converts an ES256 COSE key into an uncompressed SEC1 point, the only key type we request
+fn parse_cose_public_key(cose_key: &[u8]) -> Result<Vec<u8>, StatusCode> {
+    let cose_key: Value =
+        ciborium::de::from_reader(cose_key).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let cose_map = match cose_key {
+        Value::Map(map) => map,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    if get_cose_integer(&cose_map, COSE_KEY_TYPE) != Some(COSE_KEY_TYPE_EC2)
+        || get_cose_integer(&cose_map, COSE_KEY_ALGORITHM) != Some(WEBAUTHN_ES256_ALGORITHM as i128)
+        || get_cose_integer(&cose_map, COSE_EC2_CURVE) != Some(COSE_CURVE_P256)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let x = get_cose_bytes(&cose_map, COSE_EC2_X).ok_or(StatusCode::BAD_REQUEST)?;
+    let y = get_cose_bytes(&cose_map, COSE_EC2_Y).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let mut public_key = Vec::with_capacity(1 + x.len() + y.len());
+    public_key.push(0x04);
+    public_key.extend_from_slice(&x);
+    public_key.extend_from_slice(&y);
+
+    VerifyingKey::from_sec1_bytes(&public_key).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(public_key)
+}
+
+// Only "none" attestation is accepted, which is what the registration options ask for
+pub fn verify_registration(
+    attestation_object: &[u8],
+    rp_id: &str,
+) -> Result<RegisteredPasskey, StatusCode> {
+    let attestation_object: Value =
+        ciborium::de::from_reader(attestation_object).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let attestation_map = match attestation_object {
+        Value::Map(map) => map,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    match get_map_value(&attestation_map, &Value::Text("fmt".to_string())) {
+        Some(Value::Text(format)) if format == "none" => (),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    }
+
+    let auth_data = match get_map_value(&attestation_map, &Value::Text("authData".to_string())) {
+        Some(Value::Bytes(auth_data)) => auth_data,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let (flags, sign_count) = parse_authenticator_data(auth_data, rp_id)?;
+
+    if flags & FLAG_ATTESTED_CREDENTIAL_DATA == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let credential_data = &auth_data[AUTHENTICATOR_DATA_MIN_LENGTH..];
+
+    if credential_data.len() < AAGUID_LENGTH + 2 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let credential_id_length = u16::from_be_bytes([
+        credential_data[AAGUID_LENGTH],
+        credential_data[AAGUID_LENGTH + 1],
+    ]) as usize;
+
+    let credential_id_start = AAGUID_LENGTH + 2;
+    let credential_id_end = credential_id_start + credential_id_length;
+
+    if credential_data.len() <= credential_id_end {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let credential_id = credential_data[credential_id_start..credential_id_end].to_vec();
+    let public_key = parse_cose_public_key(&credential_data[credential_id_end..])?;
+
+    Ok(RegisteredPasskey {
+        credential_id,
+        public_key,
+        sign_count,
+    })
+}
+
+// Returns the new signature counter once the assertion signature has been verified
+pub fn verify_assertion(
+    authenticator_data: &[u8],
+    client_data_json: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+    rp_id: &str,
+    stored_sign_count: u32,
+) -> Result<u32, StatusCode> {
+    let (_, sign_count) = parse_authenticator_data(authenticator_data, rp_id)?;
+
+    let verifying_key =
+        VerifyingKey::from_sec1_bytes(public_key).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let signature = DerSignature::try_from(signature).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut signed_data = authenticator_data.to_vec();
+    signed_data.extend_from_slice(&Sha256::digest(client_data_json));
+
+    verifying_key
+        .verify(&signed_data, &signature)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    // Authenticators that don't keep a counter always report 0, anything else has to increase
+    if (sign_count != 0 || stored_sign_count != 0) && sign_count <= stored_sign_count {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(sign_count)
+}
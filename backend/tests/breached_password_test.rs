@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    mod breached_password_tests {
+        use std::path::PathBuf;
+
+        use backend::utils::breached_password::{
+            check_breached_passwords_dir, format_breached_password_range,
+            is_breached_password_in_dir, is_suffix_in_range,
+        };
+
+        fn fixture_dir() -> PathBuf {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/breached_passwords")
+        }
+
+        #[test]
+        fn test_format_breached_password_range() {
+            let (prefix, suffix) = format_breached_password_range("password");
+
+            assert_eq!(prefix, "5BAA6");
+            assert_eq!(suffix, "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+        }
+
+        #[test]
+        fn test_is_suffix_in_range() {
+            let range = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n00D4F6E8FA6EECAD2A3AA415EEC418D38EC:0\r\n";
+
+            assert!(is_suffix_in_range(
+                range,
+                "0018A45C4D1DEF81644B54AB7F969B88D65"
+            ));
+            assert!(is_suffix_in_range(
+                range,
+                "0018a45c4d1def81644b54ab7f969b88d65"
+            ));
+            // Padding entries have a count of 0
+            assert!(!is_suffix_in_range(
+                range,
+                "00D4F6E8FA6EECAD2A3AA415EEC418D38EC"
+            ));
+            assert!(!is_suffix_in_range(
+                range,
+                "1E4C9B93F3F0682250B6CF8331B7EE68FD8"
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_breached_password_in_dataset() {
+            assert!(is_breached_password_in_dir(&fixture_dir(), "password").await);
+        }
+
+        #[tokio::test]
+        async fn test_password_not_in_dataset() {
+            assert!(!is_breached_password_in_dir(&fixture_dir(), "Correct-Horse-Battery-1").await);
+        }
+
+        #[tokio::test]
+        async fn test_missing_dataset_is_skipped() {
+            let missing_dir = fixture_dir().join("missing");
+
+            assert!(!is_breached_password_in_dir(&missing_dir, "password").await);
+        }
+
+        #[test]
+        fn test_configured_dataset_is_checked() {
+            assert!(check_breached_passwords_dir(&fixture_dir()).is_ok());
+
+            let error = check_breached_passwords_dir(&fixture_dir().join("missing")).unwrap_err();
+            assert!(error.contains("missing"));
+
+            // A file instead of a directory
+            assert!(check_breached_passwords_dir(&fixture_dir().join("5BAA6.txt")).is_err());
+        }
+    }
+}
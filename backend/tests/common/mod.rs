@@ -0,0 +1,85 @@
+// Shared by the test files through `mod common;`, every file only uses part of it
+#![allow(dead_code)]
+
+use std::{path::PathBuf, sync::Arc};
+
+use backend::{
+    models::{
+        config::models::{Config, JwtKeysConfig},
+        general::AppState,
+    },
+    traits::{mailer::InMemoryMailer, token_store::InMemoryTokenStore},
+    utils::{
+        database::DatabasePool,
+        jwt_keys::{init_jwt_keys, load_jwt_keys, JwtKeys},
+        migrations::run_migrations,
+    },
+};
+use sqlx::sqlite::SqlitePoolOptions;
+
+pub fn jwt_fixture_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/jwt")
+        .join(path)
+}
+
+pub fn load_fixture_keys(signing_kid: &str) -> JwtKeys {
+    load_jwt_keys(
+        &jwt_fixture_path(&format!("private/{}.pem", signing_kid)),
+        signing_kid,
+        &jwt_fixture_path("public"),
+    )
+    .expect("Fixture keys should load")
+}
+
+pub fn init_fixture_jwt_keys() {
+    init_jwt_keys(&JwtKeysConfig {
+        private_key_path: jwt_fixture_path("private/rsa-1.pem").display().to_string(),
+        signing_key_id: "rsa-1".to_string(),
+        public_keys_dir: jwt_fixture_path("public").display().to_string(),
+    })
+    .expect("Fixture keys should load");
+}
+
+pub fn create_test_config() -> Config {
+    Config {
+        hash_secret_key: "test-secret".to_string(),
+        ..Default::default()
+    }
+}
+
+// No database, Redis or SMTP server, everything the tests touch stays in the process
+pub fn create_in_memory_state() -> AppState {
+    AppState::in_memory(Box::leak(Box::new(create_test_config())))
+}
+
+// Every connection to sqlite::memory: is a database of its own, so the pool keeps just one
+pub async fn create_sqlite_pool() -> DatabasePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("SQLite should open in memory");
+
+    DatabasePool::Sqlite(pool)
+}
+
+// For tests that need the SQL of the repositories, tokens and emails still stay in memory
+pub async fn create_sqlite_state_with_config(config: Config) -> AppState {
+    let db_pool = create_sqlite_pool().await;
+
+    run_migrations(&db_pool)
+        .await
+        .expect("SQLite migrations should apply");
+
+    AppState::with_database(
+        db_pool,
+        Arc::new(InMemoryTokenStore::new()),
+        Arc::new(InMemoryMailer::new()),
+        Box::leak(Box::new(config)),
+    )
+}
+
+pub async fn create_sqlite_state() -> AppState {
+    create_sqlite_state_with_config(create_test_config()).await
+}
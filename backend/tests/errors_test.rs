@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    mod service_error_tests {
+        use std::error::Error;
+
+        use backend::utils::errors::ServiceError;
+        use http::StatusCode;
+
+        #[test]
+        fn test_missing_row_becomes_not_found() {
+            let error = ServiceError::from(sqlx::Error::RowNotFound);
+
+            assert!(matches!(error, ServiceError::NotFound));
+            assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        }
+
+        #[test]
+        fn test_database_error_keeps_source() {
+            let error = ServiceError::from(sqlx::Error::PoolTimedOut);
+
+            assert!(matches!(error, ServiceError::Database(_)));
+            assert!(error.is_server_error());
+            assert_eq!(error.translation_key(), "general.errors.internal_error");
+            assert!(error.source().is_some());
+        }
+
+        #[test]
+        fn test_email_errors_are_told_apart() {
+            let address_error = "not an address"
+                .parse::<lettre::Address>()
+                .expect_err("Address should be invalid");
+            let error = ServiceError::from(address_error);
+
+            assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(error.translation_key(), "general.errors.email_not_sent");
+        }
+
+        #[test]
+        fn test_validation_error_uses_its_translation_key() {
+            let error = ServiceError::Validation("auth.errors.invalid_email");
+
+            assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+            assert_eq!(error.translation_key(), "auth.errors.invalid_email");
+            assert!(!error.is_server_error());
+        }
+
+        #[test]
+        fn test_jwt_errors_are_told_apart() {
+            let decode_error =
+                ServiceError::JwtDecode(jsonwebtoken::errors::ErrorKind::ExpiredSignature.into());
+            let encode_error = ServiceError::JwtEncode(
+                jsonwebtoken::errors::ErrorKind::InvalidRsaKey("broken".to_string()).into(),
+            );
+
+            assert_eq!(decode_error.status_code(), StatusCode::UNAUTHORIZED);
+            assert!(!decode_error.is_server_error());
+            assert_eq!(
+                encode_error.status_code(),
+                StatusCode::INTERNAL_SERVER_ERROR
+            );
+            assert_eq!(
+                encode_error.translation_key(),
+                "general.errors.internal_error"
+            );
+            assert!(encode_error.source().is_some());
+        }
+
+        #[test]
+        fn test_converts_to_status_code() {
+            assert_eq!(
+                StatusCode::from(ServiceError::Unauthorized),
+                StatusCode::UNAUTHORIZED
+            );
+            assert_eq!(
+                StatusCode::from(ServiceError::Configuration(
+                    "hash_secret_key is not set".to_string()
+                )),
+                StatusCode::INTERNAL_SERVER_ERROR
+            );
+        }
+    }
+
+    mod response_tests {
+        use axum::{http::StatusCode, response::IntoResponse};
+        use backend::utils::{
+            errors::ServiceError, responses::AppError, translations::load_translations,
+        };
+
+        #[test]
+        fn test_format_service_error_uses_status_of_error() {
+            let translations = load_translations("en").expect("English translations should load");
+
+            let response = AppError::format_service_error(&translations, ServiceError::NotFound)
+                .into_response();
+
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+
+        #[test]
+        fn test_format_service_error_accepts_underlying_errors() {
+            let translations = load_translations("en").expect("English translations should load");
+
+            let json_error = serde_json::from_str::<i32>("not json").unwrap_err();
+            let response =
+                AppError::format_service_error(&translations, json_error).into_response();
+
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+
+        #[test]
+        fn test_format_status_error_keeps_client_errors() {
+            let translations = load_translations("en").expect("English translations should load");
+
+            let response = AppError::format_status_error(&translations, StatusCode::BAD_REQUEST)
+                .into_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let response = AppError::format_status_error(&translations, StatusCode::BAD_GATEWAY)
+                .into_response();
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+}
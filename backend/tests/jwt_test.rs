@@ -0,0 +1,355 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod jwt_tests {
+        use crate::common::init_fixture_jwt_keys;
+        use backend::{
+            models::config::models::Config,
+            utils::jwt::{decode_jwt, encode_jwt},
+        };
+
+        #[test]
+        fn test_encode_jwt_round_trips_session_id() {
+            init_fixture_jwt_keys();
+
+            let jwt = encode_jwt(
+                &Config::default(),
+                &1,
+                "Test",
+                "test@example.com",
+                Some("session"),
+            )
+            .unwrap();
+            let claims = decode_jwt(&jwt).unwrap().claims;
+
+            assert_eq!(claims.id, 1);
+            assert_eq!(claims.sid, Some("session".to_string()));
+        }
+
+        #[test]
+        fn test_encode_jwt_generates_unique_ids() {
+            init_fixture_jwt_keys();
+
+            let config = Config::default();
+            let first =
+                decode_jwt(&encode_jwt(&config, &1, "Test", "test@example.com", None).unwrap())
+                    .unwrap()
+                    .claims;
+            let second =
+                decode_jwt(&encode_jwt(&config, &1, "Test", "test@example.com", None).unwrap())
+                    .unwrap()
+                    .claims;
+
+            assert_eq!(first.jti.len(), 32);
+            assert_ne!(first.jti, second.jti);
+        }
+    }
+
+    mod signing_key_tests {
+        use crate::common::load_fixture_keys;
+        use backend::{
+            models::auth::models::JwtClaims,
+            utils::jwt::{decode_jwt_with_keys, sign_jwt_claims},
+        };
+        use chrono::Utc;
+        use http::StatusCode;
+        use jsonwebtoken::{decode_header, Algorithm};
+
+        fn test_claims() -> JwtClaims {
+            let now = Utc::now().timestamp() as usize;
+
+            JwtClaims {
+                exp: now + 60,
+                iat: now,
+                iat_ms: None,
+                id: 1,
+                name: "Test".to_string(),
+                email: "test@example.com".to_string(),
+                sid: None,
+                jti: "jti".to_string(),
+                aud: None,
+                scope: None,
+            }
+        }
+
+        #[test]
+        fn test_sign_rs256_sets_kid_header() {
+            let jwt_keys = load_fixture_keys("rsa-1");
+            let jwt = sign_jwt_claims(&jwt_keys, &test_claims()).unwrap();
+
+            let header = decode_header(&jwt).unwrap();
+            assert_eq!(header.alg, Algorithm::RS256);
+            assert_eq!(header.kid, Some("rsa-1".to_string()));
+
+            assert_eq!(decode_jwt_with_keys(&jwt_keys, &jwt).unwrap().claims.id, 1);
+        }
+
+        #[test]
+        fn test_sign_eddsa_sets_kid_header() {
+            let jwt_keys = load_fixture_keys("ed-1");
+            let jwt = sign_jwt_claims(&jwt_keys, &test_claims()).unwrap();
+
+            let header = decode_header(&jwt).unwrap();
+            assert_eq!(header.alg, Algorithm::EdDSA);
+            assert_eq!(header.kid, Some("ed-1".to_string()));
+
+            assert_eq!(decode_jwt_with_keys(&jwt_keys, &jwt).unwrap().claims.id, 1);
+        }
+
+        #[test]
+        fn test_tokens_of_previous_signing_key_stay_valid() {
+            let previous_keys = load_fixture_keys("ed-1");
+            let current_keys = load_fixture_keys("rsa-1");
+
+            let jwt = sign_jwt_claims(&previous_keys, &test_claims()).unwrap();
+
+            assert!(decode_jwt_with_keys(&current_keys, &jwt).is_ok());
+        }
+
+        #[test]
+        fn test_decode_rejects_unknown_kid() {
+            let jwt_keys = load_fixture_keys("rsa-1");
+            let mut other_keys = load_fixture_keys("rsa-1");
+            other_keys.signing_key.kid = "unknown".to_string();
+
+            let jwt = sign_jwt_claims(&other_keys, &test_claims()).unwrap();
+
+            assert_eq!(
+                decode_jwt_with_keys(&jwt_keys, &jwt)
+                    .map_err(|error| error.status_code())
+                    .err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_decode_rejects_expired_token() {
+            let jwt_keys = load_fixture_keys("rsa-1");
+            let mut claims = test_claims();
+            claims.exp = claims.iat - 3600;
+
+            let jwt = sign_jwt_claims(&jwt_keys, &claims).unwrap();
+
+            assert_eq!(
+                decode_jwt_with_keys(&jwt_keys, &jwt)
+                    .map_err(|error| error.status_code())
+                    .err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+        }
+    }
+
+    mod jwks_tests {
+        use crate::common::{jwt_fixture_path, load_fixture_keys};
+        use backend::utils::jwt_keys::{load_jwt_keys, parse_public_key_pem};
+
+        #[test]
+        fn test_jwk_set_contains_all_verification_keys() {
+            let jwk_set = serde_json::to_value(load_fixture_keys("rsa-1").get_jwk_set()).unwrap();
+            let keys = jwk_set["keys"].as_array().unwrap();
+
+            assert_eq!(keys.len(), 2);
+
+            let ed_key = keys.iter().find(|key| key["kid"] == "ed-1").unwrap();
+            assert_eq!(ed_key["kty"], "OKP");
+            assert_eq!(ed_key["crv"], "Ed25519");
+            assert_eq!(ed_key["alg"], "EdDSA");
+            assert_eq!(ed_key["use"], "sig");
+            assert!(ed_key.get("n").is_none());
+
+            let rsa_key = keys.iter().find(|key| key["kid"] == "rsa-1").unwrap();
+            assert_eq!(rsa_key["kty"], "RSA");
+            assert_eq!(rsa_key["alg"], "RS256");
+            assert_eq!(rsa_key["e"], "AQAB");
+            assert!(rsa_key.get("x").is_none());
+        }
+
+        #[test]
+        fn test_parse_public_key_pem_rejects_private_key() {
+            let private_key =
+                std::fs::read_to_string(jwt_fixture_path("private/rsa-1.pem")).unwrap();
+
+            assert!(parse_public_key_pem("rsa-1", &private_key).is_err());
+        }
+
+        #[test]
+        fn test_load_jwt_keys_requires_public_key_for_signing_key() {
+            assert!(load_jwt_keys(
+                &jwt_fixture_path("private/rsa-1.pem"),
+                "missing",
+                &jwt_fixture_path("public"),
+            )
+            .is_err());
+        }
+    }
+
+    mod token_key_tests {
+        use backend::utils::{
+            jwt::{
+                format_refresh_token_key, format_rotated_refresh_token_key, generate_refresh_token,
+            },
+            user::{format_reset_token_key, generate_reset_token},
+        };
+
+        use crate::common::create_test_config;
+
+        #[test]
+        fn test_tokens_have_256_bits() {
+            // 32 bytes as unpadded base64url
+            assert_eq!(generate_refresh_token().len(), 43);
+            assert_eq!(generate_reset_token().len(), 43);
+            assert_ne!(generate_refresh_token(), generate_refresh_token());
+        }
+
+        #[test]
+        fn test_token_keys_only_contain_hash() {
+            let config = create_test_config();
+
+            let refresh_token = generate_refresh_token();
+            let refresh_token_key = format_refresh_token_key(&config, &refresh_token).unwrap();
+            let rotated_token_key =
+                format_rotated_refresh_token_key(&config, &refresh_token).unwrap();
+
+            assert!(refresh_token_key.starts_with("refresh:"));
+            assert!(!refresh_token_key.contains(&refresh_token));
+            assert!(rotated_token_key.starts_with("refresh-rotated:"));
+            assert!(!rotated_token_key.contains(&refresh_token));
+
+            let reset_token = generate_reset_token();
+            let reset_token_key = format_reset_token_key(&config, &reset_token).unwrap();
+
+            assert!(reset_token_key.starts_with("reset-token:"));
+            assert!(!reset_token_key.contains(&reset_token));
+        }
+
+        #[test]
+        fn test_token_keys_are_stable() {
+            let config = create_test_config();
+
+            assert_eq!(
+                format_refresh_token_key(&config, "token").unwrap(),
+                format_refresh_token_key(&config, "token").unwrap()
+            );
+            assert_ne!(
+                format_refresh_token_key(&config, "token").unwrap(),
+                format_refresh_token_key(&config, "other-token").unwrap()
+            );
+        }
+    }
+
+    mod revocation_tests {
+        use backend::{
+            models::auth::models::JwtClaims,
+            utils::{
+                jwt::{format_jwt_watermark_key, is_jwt_revoked, revoke_jwt, revoke_user_jwts},
+                redis::set_token,
+            },
+        };
+        use chrono::Utc;
+
+        use crate::common::create_in_memory_state;
+
+        fn build_claims(jti: &str, issued_seconds_ago: i64) -> JwtClaims {
+            let now = Utc::now().timestamp();
+
+            JwtClaims {
+                exp: (now + 600) as usize,
+                iat: (now - issued_seconds_ago) as usize,
+                iat_ms: Some(Utc::now().timestamp_millis() - issued_seconds_ago * 1000),
+                id: 1,
+                name: "Test".to_string(),
+                email: "test@example.com".to_string(),
+                sid: None,
+                jti: jti.to_string(),
+                aud: None,
+                scope: None,
+            }
+        }
+
+        #[tokio::test]
+        async fn test_revoked_jwt_is_denylisted() {
+            let state = create_in_memory_state();
+            let claims = build_claims("revoked", 0);
+
+            assert!(!is_jwt_revoked(&state, &claims).await.unwrap());
+
+            revoke_jwt(&state, &claims).await.unwrap();
+
+            assert!(is_jwt_revoked(&state, &claims).await.unwrap());
+            assert!(!is_jwt_revoked(&state, &build_claims("other", 0))
+                .await
+                .unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_watermark_revokes_tokens_issued_before_now() {
+            let state = create_in_memory_state();
+
+            revoke_user_jwts(&state, &1).await.unwrap();
+
+            assert!(is_jwt_revoked(&state, &build_claims("older", 60))
+                .await
+                .unwrap());
+            // Like the session a password change starts right after revoking the old ones
+            assert!(!is_jwt_revoked(&state, &build_claims("right-after", 0))
+                .await
+                .unwrap());
+            assert!(!is_jwt_revoked(&state, &build_claims("newer", -2))
+                .await
+                .unwrap());
+
+            let mut other_user_claims = build_claims("other-user", 60);
+            other_user_claims.id = 2;
+            assert!(!is_jwt_revoked(&state, &other_user_claims).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_watermark_compares_milliseconds() {
+            let state = create_in_memory_state();
+            let now = Utc::now().timestamp();
+            let not_before = now * 1000 + 500;
+
+            set_token(
+                &state,
+                &format_jwt_watermark_key(&1),
+                &not_before.to_string(),
+                60,
+            )
+            .await
+            .unwrap();
+
+            let mut claims = build_claims("same-second", 0);
+            claims.iat = now as usize;
+
+            claims.iat_ms = Some(not_before - 1);
+            assert!(is_jwt_revoked(&state, &claims).await.unwrap());
+
+            claims.iat_ms = Some(not_before);
+            assert!(!is_jwt_revoked(&state, &claims).await.unwrap());
+
+            // Without iat_ms the whole second of the revocation is revoked
+            claims.iat_ms = None;
+            assert!(is_jwt_revoked(&state, &claims).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_tokens_without_jti_still_decode() {
+            let state = create_in_memory_state();
+            let now = Utc::now().timestamp();
+
+            let claims: JwtClaims = serde_json::from_str(&format!(
+                r#"{{"exp":{},"iat":{},"id":1,"name":"Test","email":"test@example.com"}}"#,
+                now + 600,
+                now
+            ))
+            .expect("Tokens from before the jti claim should decode");
+
+            assert!(claims.jti.is_empty());
+
+            // Denylisting one of them doesn't revoke every other token without a jti
+            revoke_jwt(&state, &claims).await.unwrap();
+            assert!(!is_jwt_revoked(&state, &claims).await.unwrap());
+        }
+    }
+}
@@ -0,0 +1,147 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod command_tests {
+        use backend::{
+            models::migration::models::ApiCommand, utils::migrations::parse_api_command,
+        };
+
+        fn parse(args: &[&str]) -> Result<ApiCommand, String> {
+            let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+            parse_api_command(&args)
+        }
+
+        #[test]
+        fn test_commands_are_parsed() {
+            assert_eq!(parse(&[]), Ok(ApiCommand::Serve));
+            assert_eq!(parse(&["migrate", "up"]), Ok(ApiCommand::MigrateUp));
+            assert_eq!(parse(&["migrate", "status"]), Ok(ApiCommand::MigrateStatus));
+        }
+
+        #[test]
+        fn test_unknown_commands_are_rejected() {
+            assert!(parse(&["migrate"]).is_err());
+            assert!(parse(&["migrate", "down"]).is_err());
+            assert!(parse(&["serve", "--port"]).is_err());
+        }
+    }
+
+    mod status_tests {
+        use backend::{
+            models::database::models::DatabaseKind,
+            utils::migrations::{
+                check_schema_version, format_migration_status, get_migration_status_from,
+                get_migrator,
+            },
+        };
+
+        #[test]
+        fn test_every_dialect_has_the_same_versions() {
+            let get_versions = |database_kind| {
+                get_migrator(database_kind)
+                    .iter()
+                    .map(|migration| migration.version)
+                    .collect::<Vec<_>>()
+            };
+
+            let mysql_versions = get_versions(DatabaseKind::MySql);
+
+            assert!(!mysql_versions.is_empty());
+            assert_eq!(get_versions(DatabaseKind::Postgres), mysql_versions);
+            assert_eq!(get_versions(DatabaseKind::Sqlite), mysql_versions);
+        }
+
+        #[test]
+        fn test_status_lists_applied_pending_and_unknown_migrations() {
+            let status = get_migration_status_from(
+                get_migrator(DatabaseKind::MySql),
+                &[20241006115933, 20200101000000],
+            );
+
+            assert!(status.migrations[0].is_applied);
+            assert_eq!(status.migrations[0].description, "users");
+            assert!(status.migrations[1..]
+                .iter()
+                .all(|migration| !migration.is_applied));
+            assert_eq!(status.unknown_versions, vec![20200101000000]);
+
+            let formatted_status = format_migration_status(&status);
+            assert!(formatted_status.starts_with("applied 20241006115933 users\npending "));
+            assert!(formatted_status.ends_with("unknown 20200101000000"));
+        }
+
+        #[test]
+        fn test_newer_schema_is_refused() {
+            let migrator = get_migrator(DatabaseKind::MySql);
+            let mut applied_versions = migrator
+                .iter()
+                .map(|migration| migration.version)
+                .collect::<Vec<_>>();
+
+            assert!(
+                check_schema_version(&get_migration_status_from(migrator, &applied_versions))
+                    .is_ok()
+            );
+
+            applied_versions.push(99990101000000);
+
+            let err = check_schema_version(&get_migration_status_from(migrator, &applied_versions))
+                .expect_err("Newer schema should be refused");
+            assert!(err.contains("99990101000000"));
+        }
+    }
+
+    mod sqlite_tests {
+        use backend::utils::{
+            database::DatabasePool,
+            migrations::{get_migration_status, run_migrations},
+        };
+
+        use crate::common::create_sqlite_pool;
+
+        #[tokio::test]
+        async fn test_migrations_are_applied_once() {
+            let db_pool = create_sqlite_pool().await;
+
+            let status = get_migration_status(&db_pool).await.unwrap();
+            assert!(status
+                .migrations
+                .iter()
+                .all(|migration| !migration.is_applied));
+
+            let applied_migrations = run_migrations(&db_pool).await.unwrap();
+            assert_eq!(applied_migrations.len(), status.migrations.len());
+
+            let status = get_migration_status(&db_pool).await.unwrap();
+            assert!(status
+                .migrations
+                .iter()
+                .all(|migration| migration.is_applied));
+
+            assert!(run_migrations(&db_pool).await.unwrap().is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_migrations_refuse_a_newer_schema() {
+            let db_pool = create_sqlite_pool().await;
+            run_migrations(&db_pool).await.unwrap();
+
+            if let DatabasePool::Sqlite(pool) = &db_pool {
+                sqlx::query(
+                    "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+                     VALUES (99990101000000, 'from a newer release', true, x'00', 0)",
+                )
+                .execute(pool)
+                .await
+                .unwrap();
+            }
+
+            let err = run_migrations(&db_pool)
+                .await
+                .expect_err("Newer schema should be refused");
+            assert!(err.contains("99990101000000"));
+        }
+    }
+}
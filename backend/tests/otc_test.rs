@@ -0,0 +1,205 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod otc_tests {
+        use backend::{
+            constants::otc::{OTC_CHARACTERS, OTC_LENGTH},
+            utils::otc::{
+                create_otc, format_otc_attempts_key, format_otc_key, is_valid_login_otc,
+                sign_login_otc,
+            },
+        };
+
+        use crate::common::create_test_config;
+
+        #[test]
+        fn test_create_otc_format() {
+            for _ in 0..100 {
+                let otc = create_otc();
+
+                assert_eq!(otc.len(), OTC_LENGTH);
+                assert!(otc.bytes().all(|byte| OTC_CHARACTERS.contains(&byte)));
+            }
+        }
+
+        #[test]
+        fn test_otc_key_does_not_contain_code() {
+            let config = create_test_config();
+
+            let otc_key = format_otc_key(&config, "test@example.com", "ABC123").unwrap();
+
+            assert!(otc_key.starts_with("otc:"));
+            assert!(!otc_key.contains("ABC123"));
+            assert!(!otc_key.contains("test@example.com"));
+        }
+
+        #[test]
+        fn test_otc_key_is_scoped_to_email() {
+            let config = create_test_config();
+
+            let otc_key = format_otc_key(&config, "test@example.com", "ABC123").unwrap();
+
+            assert_eq!(
+                otc_key,
+                format_otc_key(&config, "Test@Example.com", "abc123").unwrap()
+            );
+            assert_ne!(
+                otc_key,
+                format_otc_key(&config, "other@example.com", "ABC123").unwrap()
+            );
+            assert_ne!(
+                otc_key,
+                format_otc_key(&config, "test@example.com", "ABC124").unwrap()
+            );
+        }
+
+        #[test]
+        fn test_otc_attempts_key_is_scoped_to_email_only() {
+            let config = create_test_config();
+
+            let attempts_key = format_otc_attempts_key(&config, "test@example.com").unwrap();
+
+            assert_eq!(
+                attempts_key,
+                format_otc_attempts_key(&config, "TEST@example.com").unwrap()
+            );
+            assert_ne!(
+                attempts_key,
+                format_otc_attempts_key(&config, "other@example.com").unwrap()
+            );
+            assert!(!attempts_key.contains("test@example.com"));
+        }
+
+        #[test]
+        fn test_login_otc_needs_signature_of_its_email() {
+            let config = create_test_config();
+
+            let signature = sign_login_otc(&config, "ABC123", "test@example.com").unwrap();
+
+            assert!(
+                is_valid_login_otc(&config, "abc123", "Test@Example.com", Some(&signature))
+                    .unwrap()
+            );
+            assert!(!is_valid_login_otc(&config, "ABC123", "test@example.com", None).unwrap());
+            assert!(
+                !is_valid_login_otc(&config, "ABC123", "other@example.com", Some(&signature))
+                    .unwrap()
+            );
+            assert!(
+                !is_valid_login_otc(&config, "ABC124", "test@example.com", Some(&signature))
+                    .unwrap()
+            );
+            assert!(!is_valid_login_otc(&config, "ABC123", "test@example.com", Some("")).unwrap());
+        }
+    }
+
+    mod attempt_tests {
+        use axum::http::StatusCode;
+        use backend::{
+            constants::otc::OTC_MAX_ATTEMPTS,
+            models::{
+                general::AppState,
+                otc::models::{OtcPayload, OtcPayloadAction},
+            },
+            utils::{
+                otc::{format_otc_key, store_otc, take_otc_payload},
+                redis::get_token,
+            },
+        };
+
+        use crate::common::create_in_memory_state;
+
+        async fn store_test_otc(state: &AppState) -> String {
+            let payload = OtcPayload {
+                user_id: 1,
+                action: OtcPayloadAction::ConfirmAccount,
+                name: "Test".to_string(),
+                phone: None,
+                email: "test@example.com".to_string(),
+                password_hash: None,
+            };
+
+            store_otc(state, "test@example.com", &payload)
+                .await
+                .unwrap()
+        }
+
+        async fn is_otc_stored(state: &AppState, otc: &str) -> bool {
+            let otc_key = format_otc_key(state.config, "test@example.com", otc).unwrap();
+
+            get_token(state, &otc_key).await.unwrap().is_some()
+        }
+
+        #[tokio::test]
+        async fn test_wrong_codes_burn_the_code() {
+            let state = create_in_memory_state();
+            let otc = store_test_otc(&state).await;
+
+            for _ in 0..OTC_MAX_ATTEMPTS {
+                assert!(take_otc_payload(&state, "test@example.com", "WRONG1")
+                    .await
+                    .unwrap()
+                    .is_none());
+            }
+
+            assert!(!is_otc_stored(&state, &otc).await);
+            assert_eq!(
+                take_otc_payload(&state, "test@example.com", &otc)
+                    .await
+                    .err(),
+                Some(StatusCode::TOO_MANY_REQUESTS)
+            );
+        }
+
+        #[tokio::test]
+        async fn test_right_code_works_within_the_limit() {
+            let state = create_in_memory_state();
+            let otc = store_test_otc(&state).await;
+
+            for _ in 0..OTC_MAX_ATTEMPTS - 1 {
+                assert!(take_otc_payload(&state, "test@example.com", "WRONG1")
+                    .await
+                    .unwrap()
+                    .is_none());
+            }
+
+            assert!(take_otc_payload(&state, "test@example.com", &otc)
+                .await
+                .unwrap()
+                .is_some());
+        }
+
+        #[tokio::test]
+        async fn test_codes_are_single_use() {
+            let state = create_in_memory_state();
+            let otc = store_test_otc(&state).await;
+
+            assert!(take_otc_payload(&state, "test@example.com", &otc)
+                .await
+                .unwrap()
+                .is_some());
+            assert!(take_otc_payload(&state, "test@example.com", &otc)
+                .await
+                .unwrap()
+                .is_none());
+        }
+
+        #[tokio::test]
+        async fn test_new_code_starts_a_new_count() {
+            let state = create_in_memory_state();
+            store_test_otc(&state).await;
+
+            for _ in 0..OTC_MAX_ATTEMPTS {
+                let _ = take_otc_payload(&state, "test@example.com", "WRONG1").await;
+            }
+
+            let otc = store_test_otc(&state).await;
+
+            assert!(take_otc_payload(&state, "test@example.com", &otc)
+                .await
+                .unwrap()
+                .is_some());
+        }
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod tests {
+    mod password_hasher_tests {
+        use backend::utils::auth::{Argon2idHasher, BcryptHasher, PasswordHasher};
+
+        // Small costs keep the tests fast, production uses ARGON2_MEMORY_COST and ARGON2_TIME_COST
+        fn argon2_hasher(time_cost: u32) -> Argon2idHasher {
+            Argon2idHasher::new(1024, time_cost, 1).unwrap()
+        }
+
+        #[test]
+        fn test_argon2id_hash_and_verify() {
+            let hasher = argon2_hasher(1);
+            let hash = hasher.hash("correct horse").unwrap();
+
+            assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+            assert!(hasher.verify("correct horse", &hash).unwrap());
+            assert!(!hasher.verify("wrong horse", &hash).unwrap());
+        }
+
+        #[test]
+        fn test_argon2id_verifies_hashes_with_other_params() {
+            let hash = argon2_hasher(2).hash("correct horse").unwrap();
+
+            assert!(argon2_hasher(1).verify("correct horse", &hash).unwrap());
+        }
+
+        #[test]
+        fn test_bcrypt_hash_and_verify() {
+            let hasher = BcryptHasher::new(4);
+            let hash = hasher.hash("correct horse").unwrap();
+
+            assert!(hasher.is_own_hash(&hash));
+            assert!(hasher.verify("correct horse", &hash).unwrap());
+            assert!(!hasher.verify("wrong horse", &hash).unwrap());
+        }
+
+        #[test]
+        fn test_algorithm_detection() {
+            let argon2_hash = argon2_hasher(1).hash("password").unwrap();
+            let bcrypt_hash = BcryptHasher::new(4).hash("password").unwrap();
+
+            assert!(argon2_hasher(1).is_own_hash(&argon2_hash));
+            assert!(!argon2_hasher(1).is_own_hash(&bcrypt_hash));
+            assert!(BcryptHasher::new(4).is_own_hash(&bcrypt_hash));
+            assert!(!BcryptHasher::new(4).is_own_hash(&argon2_hash));
+        }
+
+        #[test]
+        fn test_needs_rehash() {
+            let argon2_hash = argon2_hasher(1).hash("password").unwrap();
+            let bcrypt_hash = BcryptHasher::new(4).hash("password").unwrap();
+
+            assert!(!argon2_hasher(1).needs_rehash(&argon2_hash));
+            assert!(argon2_hasher(2).needs_rehash(&argon2_hash));
+            assert!(argon2_hasher(1).needs_rehash(&bcrypt_hash));
+
+            assert!(!BcryptHasher::new(4).needs_rehash(&bcrypt_hash));
+            assert!(BcryptHasher::new(5).needs_rehash(&bcrypt_hash));
+            assert!(BcryptHasher::new(4).needs_rehash(&argon2_hash));
+        }
+    }
+
+    mod verify_password_tests {
+        use backend::utils::auth::{verify_password, BcryptHasher, PasswordHasher};
+
+        #[tokio::test]
+        async fn test_verify_password_detects_bcrypt_hashes() {
+            let bcrypt_hash = BcryptHasher::new(4).hash("password").unwrap();
+
+            assert!(verify_password("password", &bcrypt_hash).await.unwrap());
+            assert!(!verify_password("other", &bcrypt_hash).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_verify_password_rejects_unknown_hash_format() {
+            assert!(verify_password("password", "plain-text").await.is_err());
+        }
+    }
+
+    mod load_password_hasher_tests {
+        use backend::{
+            models::config::models::PasswordHashingConfig, utils::auth::load_password_hasher,
+        };
+
+        #[test]
+        fn test_defaults_to_argon2id() {
+            let hasher = load_password_hasher(&PasswordHashingConfig::default()).unwrap();
+
+            assert!(hasher.is_own_hash("$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA"));
+        }
+
+        #[test]
+        fn test_invalid_costs_are_rejected_with_their_variable() {
+            let error = load_password_hasher(&PasswordHashingConfig {
+                argon2_memory_cost_kib: 1,
+                ..Default::default()
+            })
+            .err()
+            .unwrap();
+
+            assert!(error.contains("ARGON2_MEMORY_COST"));
+
+            for bcrypt_cost in [2, 40] {
+                let error = load_password_hasher(&PasswordHashingConfig {
+                    algorithm: "bcrypt".to_string(),
+                    bcrypt_cost,
+                    ..Default::default()
+                })
+                .err()
+                .unwrap();
+
+                assert!(error.contains("BCRYPT_COST"));
+            }
+        }
+
+        #[test]
+        fn test_unknown_algorithm_is_rejected() {
+            let error = load_password_hasher(&PasswordHashingConfig {
+                algorithm: "md5".to_string(),
+                ..Default::default()
+            })
+            .err()
+            .unwrap();
+
+            assert!(error.contains("PASSWORD_HASH_ALGORITHM"));
+        }
+    }
+}
@@ -0,0 +1,79 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod fixtures {
+        // Bcrypt with the lowest cost keeps the tests fast, hashes are verified by their format
+        pub fn hash(password: &str) -> String {
+            bcrypt::hash(password, 4).unwrap()
+        }
+    }
+
+    mod password_history_tests {
+        use backend::{
+            constants::password_policy::PASSWORD_HISTORY_SIZE,
+            models::general::AppState,
+            utils::password_history::{change_user_password, is_password_reused},
+        };
+
+        use super::fixtures::hash;
+        use crate::common::create_sqlite_state;
+
+        // Creates a user with password-0 and changes it to password-1 up to password-<changes>
+        async fn create_user_with_changes(state: &AppState, changes: i64) -> i32 {
+            let id = state
+                .users
+                .create_user("Test", "test@example.com", &hash("password-0"))
+                .await
+                .unwrap();
+
+            for change in 1..=changes {
+                change_user_password(state, &id, &hash(&format!("password-{}", change)))
+                    .await
+                    .unwrap();
+            }
+
+            id
+        }
+
+        #[tokio::test]
+        async fn test_current_and_recent_passwords_are_reused() {
+            let state = create_sqlite_state().await;
+            let id = create_user_with_changes(&state, 2).await;
+
+            for password in ["password-0", "password-1", "password-2"] {
+                assert!(
+                    is_password_reused(&state, &id, password).await.unwrap(),
+                    "{}",
+                    password
+                );
+            }
+
+            assert!(!is_password_reused(&state, &id, "password-3").await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_passwords_older_than_the_history_are_allowed() {
+            let state = create_sqlite_state().await;
+            let id = create_user_with_changes(&state, PASSWORD_HISTORY_SIZE + 1).await;
+
+            assert!(!is_password_reused(&state, &id, "password-0").await.unwrap());
+            assert!(is_password_reused(&state, &id, "password-1").await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_history_is_pruned_to_its_size() {
+            let state = create_sqlite_state().await;
+            let id = create_user_with_changes(&state, PASSWORD_HISTORY_SIZE + 3).await;
+
+            // The current hash and exactly the history, even when asking for more
+            let password_hashes = state
+                .users
+                .get_password_hashes(&id, PASSWORD_HISTORY_SIZE + 10)
+                .await
+                .unwrap();
+
+            assert_eq!(password_hashes.len() as i64, PASSWORD_HISTORY_SIZE + 1);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    mod policy_tests {
+        use backend::{
+            models::config::models::{RateLimitConfig, RateLimitsConfig},
+            utils::rate_limit::{format_rate_limit_key, get_rate_limit_policy},
+        };
+
+        fn get_policy_name(path: &str) -> &'static str {
+            get_rate_limit_policy(&RateLimitsConfig::default(), path).name
+        }
+
+        #[test]
+        fn test_auth_routes_get_stricter_policy() {
+            let rate_limits = RateLimitsConfig::default();
+            let policy = get_rate_limit_policy(&rate_limits, "/api/auth/login");
+
+            assert_eq!(policy.name, "auth");
+            assert!(policy.limit < rate_limits.default.limit);
+            assert!(!policy.key_by_user);
+        }
+
+        #[test]
+        fn test_policy_uses_configured_limits() {
+            let rate_limits = RateLimitsConfig {
+                auth: RateLimitConfig {
+                    limit: 3,
+                    window_seconds: 10,
+                },
+                ..RateLimitsConfig::default()
+            };
+            let policy = get_rate_limit_policy(&rate_limits, "/api/auth/login");
+
+            assert_eq!(policy.limit, 3);
+            assert_eq!(policy.window_seconds, 10);
+        }
+
+        #[test]
+        fn test_most_specific_route_wins() {
+            assert_eq!(
+                get_policy_name("/api/user/reset-password/request"),
+                "password-reset-request"
+            );
+            assert_eq!(get_policy_name("/api/otc/verify"), "otc-verify");
+        }
+
+        #[test]
+        fn test_prefix_only_matches_whole_segments() {
+            assert_eq!(get_policy_name("/api/authors"), "default");
+            assert_eq!(get_policy_name("/api/user"), "default");
+            assert_eq!(get_policy_name("/oauth/token"), "default");
+        }
+
+        #[test]
+        fn test_format_rate_limit_key() {
+            assert_eq!(
+                format_rate_limit_key("auth", "ip:127.0.0.1", 42),
+                "rate-limit:auth:ip:127.0.0.1:42"
+            );
+        }
+    }
+
+    mod sliding_window_tests {
+        use backend::utils::rate_limit::get_sliding_window_count;
+
+        #[test]
+        fn test_previous_window_counts_fully_at_window_start() {
+            assert_eq!(get_sliding_window_count(10, 1, 0, 60), 11);
+        }
+
+        #[test]
+        fn test_previous_window_weight_decreases_over_time() {
+            assert_eq!(get_sliding_window_count(10, 1, 30, 60), 6);
+            assert_eq!(get_sliding_window_count(10, 1, 59, 60), 1);
+        }
+    }
+
+    mod client_ip_tests {
+        use std::net::IpAddr;
+
+        use backend::utils::client_ip::{resolve_client_ip, TrustedProxy};
+
+        fn ip(value: &str) -> IpAddr {
+            value.parse().unwrap()
+        }
+
+        fn proxies(values: &[&str]) -> Vec<TrustedProxy> {
+            values
+                .iter()
+                .map(|value| TrustedProxy::parse(value).unwrap())
+                .collect()
+        }
+
+        #[test]
+        fn test_trusted_proxy_cidr() {
+            let proxy = TrustedProxy::parse("10.0.0.0/8").unwrap();
+
+            assert!(proxy.contains(&ip("10.1.2.3")));
+            assert!(!proxy.contains(&ip("11.0.0.1")));
+            assert!(!proxy.contains(&ip("::1")));
+        }
+
+        #[test]
+        fn test_trusted_proxy_rejects_invalid_values() {
+            assert!(TrustedProxy::parse("not-an-ip").is_none());
+            assert!(TrustedProxy::parse("10.0.0.0/33").is_none());
+            assert!(TrustedProxy::parse("::/0").is_some());
+        }
+
+        #[test]
+        fn test_forwarded_for_ignored_from_untrusted_peer() {
+            let client_ip = resolve_client_ip(
+                Some(ip("203.0.113.7")),
+                Some("198.51.100.1"),
+                &proxies(&["10.0.0.0/8"]),
+            );
+
+            assert_eq!(client_ip, Some(ip("203.0.113.7")));
+        }
+
+        #[test]
+        fn test_forwarded_for_used_behind_trusted_proxy() {
+            let client_ip = resolve_client_ip(
+                Some(ip("10.0.0.2")),
+                Some("198.51.100.99, 203.0.113.7, 10.0.0.3"),
+                &proxies(&["10.0.0.0/8"]),
+            );
+
+            // The left-most address is set by the client and can't be trusted
+            assert_eq!(client_ip, Some(ip("203.0.113.7")));
+        }
+
+        #[test]
+        fn test_trusted_proxy_without_forwarded_for() {
+            let client_ip = resolve_client_ip(Some(ip("10.0.0.2")), None, &proxies(&["10.0.0.2"]));
+
+            assert_eq!(client_ip, Some(ip("10.0.0.2")));
+        }
+    }
+}
@@ -0,0 +1,183 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod recovery_code_tests {
+        use backend::utils::recovery_codes::{generate_recovery_code, normalize_recovery_code};
+
+        #[test]
+        fn test_generate_recovery_code_format() {
+            let code = generate_recovery_code();
+            let (first_half, second_half) =
+                code.split_once('-').expect("Code should contain a dash");
+
+            assert_eq!(first_half.len(), 5);
+            assert_eq!(second_half.len(), 5);
+            assert!(code
+                .chars()
+                .all(|c| c == '-' || c.is_ascii_uppercase() || c.is_ascii_digit()));
+        }
+
+        #[test]
+        fn test_generate_recovery_code_excludes_ambiguous_characters() {
+            for _ in 0..100 {
+                let code = generate_recovery_code();
+                assert!(!code.contains(['0', 'O', '1', 'I']));
+            }
+        }
+
+        #[test]
+        fn test_normalize_recovery_code() {
+            assert_eq!(normalize_recovery_code("abcde-23456"), "ABCDE23456");
+            assert_eq!(normalize_recovery_code(" ABCDE 23456 "), "ABCDE23456");
+        }
+    }
+
+    mod recovery_code_login_tests {
+        use axum::{
+            body::Body,
+            http::{header, Request, StatusCode},
+        };
+        use backend::{
+            models::general::AppState,
+            routes::app::app_routes,
+            utils::{
+                jwt::encode_jwt,
+                recovery_codes::{
+                    approve_recovery_codes_regeneration, count_recovery_codes,
+                    replace_recovery_codes,
+                },
+                two_factor::{
+                    create_two_factor_challenge, enable_two_factor, set_two_factor_secret,
+                },
+                user::create_user,
+            },
+        };
+        use serde_json::json;
+        use tower::ServiceExt;
+
+        use crate::common::{create_sqlite_state, init_fixture_jwt_keys};
+
+        async fn create_two_factor_user(state: &AppState) -> (i32, Vec<String>) {
+            let id = create_user(state, "Test", "test@example.com", "correct horse battery")
+                .await
+                .unwrap();
+
+            set_two_factor_secret(state, &id, "JBSWY3DPEHPK3PXP")
+                .await
+                .unwrap();
+            enable_two_factor(state, &id).await.unwrap();
+
+            let codes = replace_recovery_codes(state, &id).await.unwrap();
+
+            (id, codes)
+        }
+
+        async fn log_in_with_recovery_code(
+            state: &AppState,
+            user_id: &i32,
+            recovery_code: &str,
+        ) -> StatusCode {
+            let challenge_token = create_two_factor_challenge(state, user_id).await.unwrap();
+
+            let body = json!({
+                "challengeToken": challenge_token,
+                "recoveryCode": recovery_code,
+            });
+
+            app_routes(state.clone())
+                .oneshot(
+                    Request::post("/api/auth/2fa")
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from(body.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+        }
+
+        #[tokio::test]
+        async fn test_login_with_recovery_code() {
+            init_fixture_jwt_keys();
+
+            let state = create_sqlite_state().await;
+            let (id, codes) = create_two_factor_user(&state).await;
+
+            // Codes are accepted in lower case and without the dash too
+            let typed_code = codes[0].to_lowercase().replace('-', "");
+
+            assert_eq!(
+                log_in_with_recovery_code(&state, &id, &typed_code).await,
+                StatusCode::OK
+            );
+            assert_eq!(
+                count_recovery_codes(&state, &id).await.unwrap() as usize,
+                codes.len() - 1
+            );
+        }
+
+        #[tokio::test]
+        async fn test_recovery_code_is_single_use() {
+            init_fixture_jwt_keys();
+
+            let state = create_sqlite_state().await;
+            let (id, codes) = create_two_factor_user(&state).await;
+
+            assert_eq!(
+                log_in_with_recovery_code(&state, &id, &codes[1]).await,
+                StatusCode::OK
+            );
+            assert_eq!(
+                log_in_with_recovery_code(&state, &id, &codes[1]).await,
+                StatusCode::UNAUTHORIZED
+            );
+            assert_eq!(
+                log_in_with_recovery_code(&state, &id, "AAAAA-BBBBB").await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+
+        #[tokio::test]
+        async fn test_new_codes_need_an_approval_and_a_session() {
+            init_fixture_jwt_keys();
+
+            let state = create_sqlite_state().await;
+            let (id, _) = create_two_factor_user(&state).await;
+            let jwt = encode_jwt(state.config, &id, "Test", "test@example.com", None).unwrap();
+
+            let regenerate = |jwt: Option<String>| {
+                let mut request = Request::post("/api/auth/2fa/recovery-codes/regenerate");
+
+                if let Some(jwt) = jwt {
+                    request = request.header(header::COOKIE, format!("Bearer={}", jwt));
+                }
+
+                app_routes(state.clone()).oneshot(request.body(Body::empty()).unwrap())
+            };
+
+            assert_eq!(
+                regenerate(Some(jwt.clone())).await.unwrap().status(),
+                StatusCode::FORBIDDEN
+            );
+
+            approve_recovery_codes_regeneration(&state, &id)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                regenerate(None).await.unwrap().status(),
+                StatusCode::UNAUTHORIZED
+            );
+            assert_eq!(
+                regenerate(Some(jwt.clone())).await.unwrap().status(),
+                StatusCode::OK
+            );
+            // The approval is used up by the first request
+            assert_eq!(
+                regenerate(Some(jwt)).await.unwrap().status(),
+                StatusCode::FORBIDDEN
+            );
+        }
+    }
+}
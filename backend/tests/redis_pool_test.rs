@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    mod redis_target_tests {
+        use backend::{
+            models::redis::models::{RedisTarget, SentinelTarget},
+            utils::redis_pool::parse_redis_target,
+        };
+
+        #[test]
+        fn test_plain_and_tls_urls_are_single_servers() {
+            assert_eq!(
+                parse_redis_target("redis://redis:6379"),
+                Ok(RedisTarget::Server("redis://redis:6379".to_string()))
+            );
+            assert_eq!(
+                parse_redis_target("rediss://:secret@redis.example.com:6380/2"),
+                Ok(RedisTarget::Server(
+                    "rediss://:secret@redis.example.com:6380/2".to_string()
+                ))
+            );
+        }
+
+        #[test]
+        fn test_sentinel_url_lists_every_sentinel() {
+            assert_eq!(
+                parse_redis_target(
+                    "redis+sentinel://user:p@ss@sentinel-1:26379,sentinel-2:26380,sentinel-3/mymaster/1"
+                ),
+                Ok(RedisTarget::Sentinel(SentinelTarget {
+                    sentinel_addresses: vec![
+                        ("sentinel-1".to_string(), 26379),
+                        ("sentinel-2".to_string(), 26380),
+                        ("sentinel-3".to_string(), 26379),
+                    ],
+                    service_name: "mymaster".to_string(),
+                    db: 1,
+                    username: Some("user".to_string()),
+                    password: Some("p@ss".to_string()),
+                    use_tls: false,
+                }))
+            );
+        }
+
+        #[test]
+        fn test_tls_sentinel_url_without_credentials() {
+            assert_eq!(
+                parse_redis_target("rediss+sentinel://sentinel:26379/mymaster"),
+                Ok(RedisTarget::Sentinel(SentinelTarget {
+                    sentinel_addresses: vec![("sentinel".to_string(), 26379)],
+                    service_name: "mymaster".to_string(),
+                    db: 0,
+                    username: None,
+                    password: None,
+                    use_tls: true,
+                }))
+            );
+        }
+
+        #[test]
+        fn test_sentinel_url_needs_a_service_name() {
+            assert!(parse_redis_target("redis+sentinel://sentinel:26379").is_err());
+            assert!(parse_redis_target("redis+sentinel://sentinel:26379/").is_err());
+        }
+
+        #[test]
+        fn test_invalid_urls_are_rejected() {
+            assert!(parse_redis_target("redis-server:6379").is_err());
+            assert!(parse_redis_target("http://redis:6379").is_err());
+            assert!(parse_redis_target("redis+sentinel://sentinel:port/mymaster").is_err());
+        }
+    }
+
+    mod reconnect_tests {
+        use std::time::Duration;
+
+        use backend::utils::redis_pool::get_reconnect_delay;
+
+        #[test]
+        fn test_reconnect_delay_doubles_up_to_the_max() {
+            assert_eq!(get_reconnect_delay(1), Duration::from_millis(200));
+            assert_eq!(get_reconnect_delay(2), Duration::from_millis(400));
+            assert_eq!(get_reconnect_delay(3), Duration::from_millis(800));
+            assert_eq!(get_reconnect_delay(6), Duration::from_secs(5));
+            assert_eq!(get_reconnect_delay(u32::MAX), Duration::from_secs(5));
+        }
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    mod route_pattern_tests {
+        use backend::utils::routes::{is_protected_route, matches_route_pattern};
+        use http::Method;
+
+        #[test]
+        fn test_matches_route_pattern_static_path() {
+            assert!(matches_route_pattern(
+                "/api/auth/sessions",
+                "/api/auth/sessions"
+            ));
+            assert!(matches_route_pattern(
+                "/api/auth/sessions",
+                "/api/auth/sessions/"
+            ));
+            assert!(!matches_route_pattern(
+                "/api/auth/sessions",
+                "/api/auth/session"
+            ));
+        }
+
+        #[test]
+        fn test_matches_route_pattern_with_param() {
+            assert!(matches_route_pattern(
+                "/api/auth/sessions/{id}",
+                "/api/auth/sessions/abc123"
+            ));
+            assert!(!matches_route_pattern(
+                "/api/auth/sessions/{id}",
+                "/api/auth/sessions"
+            ));
+            assert!(!matches_route_pattern(
+                "/api/auth/sessions/{id}",
+                "/api/auth/sessions/abc123/extra"
+            ));
+        }
+
+        #[test]
+        fn test_is_protected_route_checks_method() {
+            assert!(is_protected_route(
+                "/api/auth/sessions/abc123",
+                &Method::DELETE
+            ));
+            assert!(!is_protected_route(
+                "/api/auth/sessions/abc123",
+                &Method::GET
+            ));
+            assert!(!is_protected_route("/api/auth", &Method::POST));
+        }
+    }
+}
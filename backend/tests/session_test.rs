@@ -0,0 +1,247 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod fixtures {
+        use axum::{body::Body, http::header, response::Response};
+        use backend::{
+            models::{general::AppState, session::models::SessionClient},
+            utils::{session::start_session, translations::load_translations, user::create_user},
+        };
+
+        use crate::common::init_fixture_jwt_keys;
+
+        // Logs a new user in and returns its id and refresh token
+        pub async fn start_test_session(state: &AppState) -> (i32, String) {
+            init_fixture_jwt_keys();
+
+            let id = create_user(state, "Test", "test@example.com", "correct horse battery")
+                .await
+                .unwrap();
+
+            let translations = load_translations("en").unwrap();
+
+            let response = start_session(
+                state,
+                &translations,
+                Response::new(Body::empty()),
+                &SessionClient::default(),
+                &id,
+                "Test",
+                "test@example.com",
+            )
+            .await
+            .unwrap();
+
+            let refresh_token = response
+                .headers()
+                .get_all(header::SET_COOKIE)
+                .iter()
+                .filter_map(|cookie| cookie.to_str().ok())
+                .find_map(|cookie| cookie.strip_prefix("RefreshToken="))
+                .and_then(|cookie| cookie.split(';').next())
+                .expect("Refresh token cookie should be set")
+                .to_string();
+
+            (id, refresh_token)
+        }
+    }
+
+    mod refresh_token_rotation_tests {
+        use std::sync::Arc;
+
+        use axum::http::StatusCode;
+        use backend::{
+            models::{
+                general::AppState,
+                session::models::{RotatedRefreshTokenPayload, SessionClient},
+            },
+            traits::security_event_repository::InMemorySecurityEventRepository,
+            utils::{
+                jwt::format_rotated_refresh_token_key,
+                redis::{get_token, set_token},
+                session::{get_user_sessions, rotate_refresh_token},
+            },
+        };
+        use chrono::{Duration, Utc};
+
+        use super::fixtures::start_test_session;
+        use crate::common::create_sqlite_state;
+
+        #[tokio::test]
+        async fn test_refresh_token_is_rotated() {
+            let state = create_sqlite_state().await;
+            let (id, refresh_token) = start_test_session(&state).await;
+
+            let rotated = rotate_refresh_token(&state, &refresh_token, &SessionClient::default())
+                .await
+                .unwrap();
+
+            assert_eq!(rotated.user_id, id);
+            let new_refresh_token = rotated.refresh_token.expect("A new token should be issued");
+            assert_ne!(new_refresh_token, refresh_token);
+
+            assert!(
+                rotate_refresh_token(&state, &new_refresh_token, &SessionClient::default())
+                    .await
+                    .is_ok()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_parallel_refreshes_issue_one_refresh_token() {
+            let state = create_sqlite_state().await;
+            let (id, refresh_token) = start_test_session(&state).await;
+            let client = SessionClient::default();
+
+            let (first, second) = tokio::join!(
+                rotate_refresh_token(&state, &refresh_token, &client),
+                rotate_refresh_token(&state, &refresh_token, &client),
+            );
+
+            let refresh_tokens = [first.unwrap(), second.unwrap()]
+                .into_iter()
+                .filter_map(|rotated| rotated.refresh_token)
+                .collect::<Vec<_>>();
+
+            // The family doesn't fork, the other request only gets a JWT for the same session
+            assert_eq!(refresh_tokens.len(), 1);
+            assert_eq!(get_user_sessions(&state, &id).await.unwrap().len(), 1);
+            assert!(rotate_refresh_token(&state, &refresh_tokens[0], &client)
+                .await
+                .is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_reuse_within_grace_window_keeps_the_session() {
+            let state = create_sqlite_state().await;
+            let (id, refresh_token) = start_test_session(&state).await;
+            let client = SessionClient::default();
+
+            let rotated = rotate_refresh_token(&state, &refresh_token, &client)
+                .await
+                .unwrap();
+            let reused = rotate_refresh_token(&state, &refresh_token, &client)
+                .await
+                .unwrap();
+
+            assert_eq!(reused.session_id, rotated.session_id);
+            assert!(reused.refresh_token.is_none());
+            assert_eq!(get_user_sessions(&state, &id).await.unwrap().len(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_reuse_after_grace_window_revokes_the_family() {
+            let security_events = Arc::new(InMemorySecurityEventRepository::new());
+            let state = AppState {
+                security_events: security_events.clone(),
+                ..create_sqlite_state().await
+            };
+            let (id, refresh_token) = start_test_session(&state).await;
+            let client = SessionClient::default();
+
+            let rotated = rotate_refresh_token(&state, &refresh_token, &client)
+                .await
+                .unwrap();
+            let new_refresh_token = rotated.refresh_token.unwrap();
+
+            // Moves the rotation of the spent token back in time, past the grace window
+            let rotated_key =
+                format_rotated_refresh_token_key(state.config, &refresh_token).unwrap();
+            let mut rotated_payload: RotatedRefreshTokenPayload =
+                serde_json::from_str(&get_token(&state, &rotated_key).await.unwrap().unwrap())
+                    .unwrap();
+            rotated_payload.rotated_at = Utc::now() - Duration::minutes(5);
+            set_token(
+                &state,
+                &rotated_key,
+                &serde_json::to_string(&rotated_payload).unwrap(),
+                60,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(
+                rotate_refresh_token(&state, &refresh_token, &client)
+                    .await
+                    .err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+
+            // The whole family is gone, including the token the legitimate client holds
+            assert!(get_user_sessions(&state, &id).await.unwrap().is_empty());
+            assert_eq!(
+                rotate_refresh_token(&state, &new_refresh_token, &client)
+                    .await
+                    .err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+
+            let reuse_events = security_events
+                .get_security_events()
+                .into_iter()
+                .filter(|event| event.event_type == "refresh_token_reuse")
+                .count();
+
+            assert_eq!(reuse_events, 1);
+        }
+
+        #[tokio::test]
+        async fn test_unknown_refresh_token_is_rejected() {
+            let state = create_sqlite_state().await;
+            let (id, _) = start_test_session(&state).await;
+
+            assert_eq!(
+                rotate_refresh_token(&state, "unknown", &SessionClient::default())
+                    .await
+                    .err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+            assert_eq!(get_user_sessions(&state, &id).await.unwrap().len(), 1);
+        }
+    }
+
+    mod revoke_user_access_tests {
+        use backend::{
+            models::{auth::models::JwtClaims, session::models::SessionClient},
+            utils::{
+                jwt::is_jwt_revoked,
+                session::{get_user_sessions, revoke_user_access, rotate_refresh_token},
+            },
+        };
+        use chrono::Utc;
+
+        use super::fixtures::start_test_session;
+        use crate::common::create_sqlite_state;
+
+        #[tokio::test]
+        async fn test_sessions_and_jwts_are_revoked() {
+            let state = create_sqlite_state().await;
+            let (id, refresh_token) = start_test_session(&state).await;
+
+            let now = Utc::now().timestamp();
+            let claims = JwtClaims {
+                exp: (now + 600) as usize,
+                iat: now as usize,
+                iat_ms: Some(Utc::now().timestamp_millis() - 1000),
+                id,
+                name: "Test".to_string(),
+                email: "test@example.com".to_string(),
+                sid: None,
+                jti: "jwt".to_string(),
+                aud: None,
+                scope: None,
+            };
+
+            revoke_user_access(&state, &id).await.unwrap();
+
+            assert!(get_user_sessions(&state, &id).await.unwrap().is_empty());
+            assert!(
+                rotate_refresh_token(&state, &refresh_token, &SessionClient::default())
+                    .await
+                    .is_err()
+            );
+            assert!(is_jwt_revoked(&state, &claims).await.unwrap());
+        }
+    }
+}
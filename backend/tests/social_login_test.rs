@@ -0,0 +1,552 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod fixtures {
+        use std::collections::BTreeMap;
+
+        use backend::{
+            models::{
+                config::models::{Config, SocialProviderConfig},
+                general::AppState,
+                social::models::{SocialProfile, SocialProvider, SocialProviderKind},
+            },
+            utils::social::init_social_providers,
+        };
+
+        use crate::common::{
+            create_sqlite_state_with_config, create_test_config, init_fixture_jwt_keys,
+        };
+
+        pub fn mock_provider(base_url: &str, kind: SocialProviderKind) -> SocialProvider {
+            SocialProvider {
+                name: "mock".to_string(),
+                kind,
+                client_id: "client-id".to_string(),
+                client_secret: "client-secret".to_string(),
+                authorization_url: format!("{}/authorize", base_url),
+                token_url: format!("{}/token", base_url),
+                userinfo_url: format!("{}/userinfo", base_url),
+                emails_url: Some(format!("{}/emails", base_url)),
+                scopes: "openid email profile".to_string(),
+            }
+        }
+
+        // Linked identities live in the database, so these tests run on SQLite
+        pub async fn create_sqlite_state() -> AppState {
+            init_fixture_jwt_keys();
+
+            create_sqlite_state_with_config(Config {
+                client_base_url: "http://localhost:3000".to_string(),
+                oidc_issuer_url: "http://localhost:8080".to_string(),
+                ..create_test_config()
+            })
+            .await
+        }
+
+        // Google needs no discovery, so it can be configured without a provider to talk to
+        pub async fn init_google_provider() {
+            let provider_configs = BTreeMap::from([(
+                "google".to_string(),
+                SocialProviderConfig {
+                    client_id: "client-id".to_string(),
+                    client_secret: "client-secret".to_string(),
+                    ..Default::default()
+                },
+            )]);
+
+            init_social_providers(&provider_configs).await.unwrap();
+        }
+
+        pub fn verified_profile(email: &str) -> SocialProfile {
+            SocialProfile {
+                subject: "subject-1".to_string(),
+                email: Some(email.to_string()),
+                email_verified: true,
+                name: Some("Provider Name".to_string()),
+            }
+        }
+    }
+
+    mod discovery_tests {
+        use backend::utils::social::discover_oidc_provider;
+        use serde_json::json;
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        #[tokio::test]
+        async fn test_discover_oidc_provider() {
+            let mock_server = MockServer::start().await;
+            let base_url = mock_server.uri();
+
+            Mock::given(method("GET"))
+                .and(path("/.well-known/openid-configuration"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "issuer": base_url,
+                    "authorization_endpoint": format!("{}/authorize", base_url),
+                    "token_endpoint": format!("{}/token", base_url),
+                    "userinfo_endpoint": format!("{}/userinfo", base_url),
+                    "jwks_uri": format!("{}/jwks", base_url),
+                })))
+                .mount(&mock_server)
+                .await;
+
+            let discovery_document =
+                discover_oidc_provider(&reqwest::Client::new(), &format!("{}/", base_url))
+                    .await
+                    .unwrap();
+
+            assert_eq!(
+                discovery_document.token_endpoint,
+                format!("{}/token", base_url)
+            );
+            assert_eq!(
+                discovery_document.userinfo_endpoint,
+                format!("{}/userinfo", base_url)
+            );
+        }
+
+        #[tokio::test]
+        async fn test_discover_oidc_provider_fails_without_document() {
+            let mock_server = MockServer::start().await;
+
+            assert!(
+                discover_oidc_provider(&reqwest::Client::new(), &mock_server.uri())
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    mod authorization_tests {
+        use super::fixtures::mock_provider;
+        use backend::{
+            models::social::models::SocialProviderKind,
+            utils::{oauth::create_pkce_challenge, social::format_social_authorization_url},
+        };
+        use url::Url;
+
+        #[test]
+        fn test_format_social_authorization_url() {
+            let provider = mock_provider("https://provider.example.com", SocialProviderKind::Oidc);
+
+            let authorization_url = format_social_authorization_url(
+                &provider,
+                "https://api.example.com/api/auth/social/mock/callback",
+                "state",
+                &create_pkce_challenge("verifier"),
+            )
+            .unwrap();
+
+            let url = Url::parse(&authorization_url).unwrap();
+            let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+
+            assert_eq!(url.path(), "/authorize");
+            assert!(params.contains(&("client_id".to_string(), "client-id".to_string())));
+            assert!(params.contains(&("state".to_string(), "state".to_string())));
+            assert!(params.contains(&("code_challenge_method".to_string(), "S256".to_string())));
+            assert!(params.contains(&(
+                "redirect_uri".to_string(),
+                "https://api.example.com/api/auth/social/mock/callback".to_string()
+            )));
+        }
+    }
+
+    mod token_exchange_tests {
+        use super::fixtures::mock_provider;
+        use axum::http::StatusCode;
+        use backend::{
+            models::social::models::SocialProviderKind,
+            utils::social::exchange_social_authorization_code,
+        };
+        use serde_json::json;
+        use wiremock::{
+            matchers::{body_string_contains, method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        #[tokio::test]
+        async fn test_exchange_social_authorization_code() {
+            let mock_server = MockServer::start().await;
+            let provider = mock_provider(&mock_server.uri(), SocialProviderKind::Oidc);
+
+            Mock::given(method("POST"))
+                .and(path("/token"))
+                .and(body_string_contains("code=code"))
+                .and(body_string_contains("code_verifier=verifier"))
+                .and(body_string_contains("client_secret=client-secret"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "access_token": "access-token",
+                    "token_type": "Bearer",
+                })))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let access_token = exchange_social_authorization_code(
+                &reqwest::Client::new(),
+                &provider,
+                "code",
+                "verifier",
+                "https://api.example.com/callback",
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(access_token, "access-token");
+        }
+
+        #[tokio::test]
+        async fn test_exchange_social_authorization_code_rejects_error_response() {
+            let mock_server = MockServer::start().await;
+            let provider = mock_provider(&mock_server.uri(), SocialProviderKind::GitHub);
+
+            Mock::given(method("POST"))
+                .and(path("/token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "error": "bad_verification_code",
+                })))
+                .mount(&mock_server)
+                .await;
+
+            let result = exchange_social_authorization_code(
+                &reqwest::Client::new(),
+                &provider,
+                "code",
+                "verifier",
+                "https://api.example.com/callback",
+            )
+            .await;
+
+            assert_eq!(result, Err(StatusCode::UNAUTHORIZED));
+        }
+
+        #[tokio::test]
+        async fn test_exchange_social_authorization_code_fails_on_provider_error() {
+            let mock_server = MockServer::start().await;
+            let provider = mock_provider(&mock_server.uri(), SocialProviderKind::Oidc);
+
+            Mock::given(method("POST"))
+                .and(path("/token"))
+                .respond_with(ResponseTemplate::new(400))
+                .mount(&mock_server)
+                .await;
+
+            let result = exchange_social_authorization_code(
+                &reqwest::Client::new(),
+                &provider,
+                "code",
+                "verifier",
+                "https://api.example.com/callback",
+            )
+            .await;
+
+            assert_eq!(result, Err(StatusCode::BAD_GATEWAY));
+        }
+    }
+
+    mod profile_tests {
+        use super::fixtures::mock_provider;
+        use backend::{
+            models::social::models::{SocialProfile, SocialProviderKind},
+            utils::social::fetch_social_profile,
+        };
+        use serde_json::json;
+        use wiremock::{
+            matchers::{header, method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        #[tokio::test]
+        async fn test_fetch_oidc_profile() {
+            let mock_server = MockServer::start().await;
+            let provider = mock_provider(&mock_server.uri(), SocialProviderKind::Oidc);
+
+            Mock::given(method("GET"))
+                .and(path("/userinfo"))
+                .and(header("authorization", "Bearer access-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "sub": "subject",
+                    "email": "test@example.com",
+                    "email_verified": true,
+                    "name": "Test",
+                })))
+                .mount(&mock_server)
+                .await;
+
+            let profile = fetch_social_profile(&reqwest::Client::new(), &provider, "access-token")
+                .await
+                .unwrap();
+
+            assert_eq!(
+                profile,
+                SocialProfile {
+                    subject: "subject".to_string(),
+                    email: Some("test@example.com".to_string()),
+                    email_verified: true,
+                    name: Some("Test".to_string()),
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn test_fetch_oidc_profile_defaults_to_unverified_email() {
+            let mock_server = MockServer::start().await;
+            let provider = mock_provider(&mock_server.uri(), SocialProviderKind::Oidc);
+
+            Mock::given(method("GET"))
+                .and(path("/userinfo"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "sub": "subject",
+                    "email": "test@example.com",
+                })))
+                .mount(&mock_server)
+                .await;
+
+            let profile = fetch_social_profile(&reqwest::Client::new(), &provider, "access-token")
+                .await
+                .unwrap();
+
+            assert!(!profile.email_verified);
+        }
+
+        #[tokio::test]
+        async fn test_fetch_github_profile_uses_primary_email() {
+            let mock_server = MockServer::start().await;
+            let provider = mock_provider(&mock_server.uri(), SocialProviderKind::GitHub);
+
+            Mock::given(method("GET"))
+                .and(path("/userinfo"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "id": 42,
+                    "login": "octocat",
+                    "name": null,
+                })))
+                .mount(&mock_server)
+                .await;
+
+            Mock::given(method("GET"))
+                .and(path("/emails"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                    { "email": "other@example.com", "primary": false, "verified": true },
+                    { "email": "octocat@example.com", "primary": true, "verified": true },
+                ])))
+                .mount(&mock_server)
+                .await;
+
+            let profile = fetch_social_profile(&reqwest::Client::new(), &provider, "access-token")
+                .await
+                .unwrap();
+
+            assert_eq!(
+                profile,
+                SocialProfile {
+                    subject: "42".to_string(),
+                    email: Some("octocat@example.com".to_string()),
+                    email_verified: true,
+                    name: Some("octocat".to_string()),
+                }
+            );
+        }
+    }
+
+    mod resolve_user_tests {
+        use axum::{
+            body::Body,
+            http::{header, Request, StatusCode},
+        };
+        use backend::{
+            models::social::models::SocialUser,
+            routes::app::app_routes,
+            utils::{
+                auth::verify_password,
+                social::{get_linked_identity_user_id, resolve_social_user},
+                user::{confirm_user, create_user, get_user_by_email},
+            },
+        };
+        use tower::ServiceExt;
+
+        use super::fixtures::{create_sqlite_state, verified_profile};
+
+        async fn link(
+            state: &backend::models::general::AppState,
+            link_token: &str,
+            password: &str,
+        ) -> StatusCode {
+            let body = serde_json::json!({ "linkToken": link_token, "password": password });
+
+            app_routes(state.clone())
+                .oneshot(
+                    Request::post("/api/auth/social/link")
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from(body.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+        }
+
+        #[tokio::test]
+        async fn test_unknown_email_gets_a_confirmed_account() {
+            let state = create_sqlite_state().await;
+
+            let social_user =
+                resolve_social_user(&state, "mock", verified_profile("new@example.com"))
+                    .await
+                    .unwrap();
+
+            let (id, _, _, _, _, is_confirmed) =
+                get_user_by_email(&state, "new@example.com").await.unwrap();
+
+            assert_eq!(social_user, SocialUser::Linked(id));
+            assert!(is_confirmed);
+        }
+
+        #[tokio::test]
+        async fn test_unverified_email_is_refused() {
+            let state = create_sqlite_state().await;
+
+            let mut profile = verified_profile("new@example.com");
+            profile.email_verified = false;
+
+            assert_eq!(
+                resolve_social_user(&state, "mock", profile).await.unwrap(),
+                SocialUser::EmailNotVerified
+            );
+        }
+
+        #[tokio::test]
+        async fn test_confirmed_account_is_linked_after_password_confirmation() {
+            let state = create_sqlite_state().await;
+            let id = create_user(&state, "Test", "test@example.com", "correct horse battery")
+                .await
+                .unwrap();
+            confirm_user(&state, &id).await.unwrap();
+
+            let resolve =
+                || resolve_social_user(&state, "mock", verified_profile("test@example.com"));
+
+            let link_token = match resolve().await.unwrap() {
+                SocialUser::PendingLink(link_token) => link_token,
+                other => panic!("Expected a pending link, got {:?}", other),
+            };
+            assert_eq!(
+                get_linked_identity_user_id(&state, "mock", "subject-1")
+                    .await
+                    .unwrap(),
+                None
+            );
+
+            // A wrong password spends the token
+            assert_eq!(
+                link(&state, &link_token, "wrong password").await,
+                StatusCode::UNAUTHORIZED
+            );
+            assert_eq!(
+                link(&state, &link_token, "correct horse battery").await,
+                StatusCode::UNAUTHORIZED
+            );
+
+            let link_token = match resolve().await.unwrap() {
+                SocialUser::PendingLink(link_token) => link_token,
+                other => panic!("Expected a pending link, got {:?}", other),
+            };
+            assert_eq!(
+                link(&state, &link_token, "correct horse battery").await,
+                StatusCode::OK
+            );
+
+            assert_eq!(resolve().await.unwrap(), SocialUser::Linked(id));
+        }
+
+        #[tokio::test]
+        async fn test_unconfirmed_account_loses_its_password_before_linking() {
+            let state = create_sqlite_state().await;
+            let id = create_user(&state, "Test", "test@example.com", "attacker password")
+                .await
+                .unwrap();
+
+            assert_eq!(
+                resolve_social_user(&state, "mock", verified_profile("test@example.com"))
+                    .await
+                    .unwrap(),
+                SocialUser::Linked(id)
+            );
+
+            let (_, _, _, password_hash, _, is_confirmed) =
+                get_user_by_email(&state, "test@example.com").await.unwrap();
+
+            assert!(is_confirmed);
+            assert!(!verify_password("attacker password", &password_hash)
+                .await
+                .unwrap());
+        }
+    }
+
+    mod login_state_tests {
+        use axum::{
+            body::Body,
+            http::{header, Request, StatusCode},
+            response::Response,
+        };
+        use backend::routes::app::app_routes;
+        use tower::ServiceExt;
+        use url::Url;
+
+        use super::fixtures::{create_sqlite_state, init_google_provider};
+
+        fn get_state(response: &Response) -> String {
+            let location = response.headers()[header::LOCATION].to_str().unwrap();
+
+            Url::parse(location)
+                .unwrap()
+                .query_pairs()
+                .find(|(name, _)| name == "state")
+                .map(|(_, state)| state.to_string())
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_callback_needs_the_state_cookie_of_the_same_browser() {
+            init_google_provider().await;
+            let state = create_sqlite_state().await;
+            let start = || {
+                app_routes(state.clone()).oneshot(
+                    Request::get("/api/auth/social/google")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            };
+
+            let response = start().await.unwrap();
+            let login_state = get_state(&response);
+            let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+            assert!(set_cookie.starts_with(&format!("SocialLoginState={};", login_state)));
+
+            // The cookie of another login attempt, like the attacker's own browser
+            let other_state = get_state(&start().await.unwrap());
+
+            for cookie in [None, Some(other_state)] {
+                let mut request = Request::get(format!(
+                    "/api/auth/social/google/callback?state={}&code=code",
+                    login_state
+                ));
+
+                if let Some(cookie) = cookie {
+                    request =
+                        request.header(header::COOKIE, format!("SocialLoginState={}", cookie));
+                }
+
+                let response = app_routes(state.clone())
+                    .oneshot(request.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+
+                assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod token_store_tests {
+        use backend::traits::token_store::{InMemoryTokenStore, TokenStore};
+
+        #[tokio::test]
+        async fn test_tokens_are_set_read_and_removed() {
+            let tokens = InMemoryTokenStore::new();
+
+            tokens.set_token("key", "value", 60).await.unwrap();
+            assert_eq!(
+                tokens.get_token("key").await.unwrap(),
+                Some("value".to_string())
+            );
+            assert_eq!(tokens.get_time_to_live("key").await.unwrap(), Some(60));
+
+            tokens.remove_token("key").await.unwrap();
+            assert_eq!(tokens.get_token("key").await.unwrap(), None);
+            assert_eq!(tokens.get_time_to_live("key").await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn test_expired_tokens_are_gone() {
+            let tokens = InMemoryTokenStore::new();
+
+            tokens.set_token("key", "value", 0).await.unwrap();
+
+            assert_eq!(tokens.get_token("key").await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn test_only_the_first_set_if_absent_wins() {
+            let tokens = InMemoryTokenStore::new();
+
+            assert!(tokens
+                .set_token_if_absent("key", "first", 60)
+                .await
+                .unwrap());
+            assert!(!tokens
+                .set_token_if_absent("key", "second", 60)
+                .await
+                .unwrap());
+            assert_eq!(
+                tokens.get_token("key").await.unwrap(),
+                Some("first".to_string())
+            );
+        }
+
+        #[tokio::test]
+        async fn test_sets_and_counters() {
+            let tokens = InMemoryTokenStore::new();
+
+            tokens.add_to_set("set", "first", 60).await.unwrap();
+            tokens.add_to_set("set", "second", 60).await.unwrap();
+            tokens.remove_from_set("set", "first").await.unwrap();
+            assert_eq!(
+                tokens.get_set_members("set").await.unwrap(),
+                vec!["second".to_string()]
+            );
+
+            assert_eq!(tokens.increment_counter("counter", 60).await.unwrap(), 1);
+            assert_eq!(tokens.increment_counter("counter", 60).await.unwrap(), 2);
+        }
+    }
+
+    mod user_repository_tests {
+        use backend::{
+            traits::user_repository::{InMemoryUserRepository, UserRepository},
+            utils::errors::ServiceError,
+        };
+
+        #[tokio::test]
+        async fn test_users_are_created_and_found() {
+            let users = InMemoryUserRepository::new();
+
+            let id = users
+                .create_user("Test", "test@example.com", "hash")
+                .await
+                .unwrap();
+            users.confirm_user(&id).await.unwrap();
+
+            let (_, name, email, password_hash, _, is_confirmed) =
+                users.get_user_by_email("test@example.com").await.unwrap();
+
+            assert_eq!(
+                (name.as_str(), email.as_str(), password_hash.as_str()),
+                ("Test", "test@example.com", "hash")
+            );
+            assert!(is_confirmed);
+            assert!(users
+                .create_user("Other", "test@example.com", "hash")
+                .await
+                .is_err());
+
+            users.delete_user_by_id(&id).await.unwrap();
+            assert!(matches!(
+                users.get_user_by_id(&id).await,
+                Err(ServiceError::NotFound)
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_changed_passwords_are_kept_in_the_history() {
+            let users = InMemoryUserRepository::new();
+
+            let id = users
+                .create_user("Test", "test@example.com", "first")
+                .await
+                .unwrap();
+            users.change_user_password(&id, "second", 1).await.unwrap();
+            users.change_user_password(&id, "third", 1).await.unwrap();
+
+            assert_eq!(
+                users.get_password_hashes(&id, 5).await.unwrap(),
+                vec!["third".to_string(), "second".to_string()]
+            );
+        }
+    }
+
+    mod in_process_api_tests {
+        use std::sync::Arc;
+
+        use axum::{
+            body::{to_bytes, Body},
+            http::{header, Request, Response, StatusCode},
+            Router,
+        };
+        use backend::{
+            models::general::AppState,
+            routes::app::app_routes,
+            traits::mailer::InMemoryMailer,
+            utils::{
+                jwt::encode_jwt,
+                redis::set_token,
+                user::{create_user, format_reset_token_key, generate_reset_token},
+            },
+        };
+        use serde_json::json;
+        use tower::ServiceExt;
+
+        use crate::common::{create_in_memory_state, init_fixture_jwt_keys};
+
+        fn get_cookie(response: &Response<Body>, name: &str) -> Option<String> {
+            response
+                .headers()
+                .get_all(header::SET_COOKIE)
+                .iter()
+                .filter_map(|cookie| cookie.to_str().ok())
+                .find_map(|cookie| cookie.strip_prefix(&format!("{}=", name)))
+                .and_then(|cookie| cookie.split(';').next())
+                .map(str::to_string)
+        }
+
+        async fn send_json(
+            app: &Router,
+            request: http::request::Builder,
+            body: serde_json::Value,
+        ) -> Response<Body> {
+            app.clone()
+                .oneshot(
+                    request
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from(body.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+        }
+
+        // The code is read from the link in the confirmation email, the way a user would follow it
+        fn get_otc_from_email(mailer: &InMemoryMailer, recipient: &str) -> String {
+            let email = mailer
+                .get_sent_emails()
+                .into_iter()
+                .find(|email| email.recipient == recipient)
+                .expect("A confirmation email should be sent");
+
+            email
+                .body
+                .split("otc=")
+                .nth(1)
+                .and_then(|link| link.split('&').next())
+                .expect("The email should link to the code")
+                .to_string()
+        }
+
+        #[tokio::test]
+        async fn test_register_login_refresh_and_logout() {
+            init_fixture_jwt_keys();
+
+            let mailer = Arc::new(InMemoryMailer::new());
+            let app = app_routes(AppState {
+                mailer: mailer.clone(),
+                ..create_in_memory_state()
+            });
+
+            let password = "correct horse battery staple";
+
+            let response = send_json(
+                &app,
+                Request::post("/api/user"),
+                json!({
+                    "name": "Test",
+                    "email": "test@example.com",
+                    "password": password,
+                    "passwordConfirm": password,
+                }),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+
+            // Unconfirmed accounts can't log in yet
+            let login = json!({ "email": "test@example.com", "password": password });
+            let response = send_json(&app, Request::post("/api/auth"), login.clone()).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let otc = get_otc_from_email(&mailer, "test@example.com");
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::patch(format!(
+                        "/api/otc/verify?otc={}&email=test%40example.com",
+                        otc
+                    ))
+                    .body(Body::empty())
+                    .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let response = send_json(&app, Request::post("/api/auth"), login).await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let refresh_token = get_cookie(&response, "RefreshToken").unwrap();
+
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::post("/api/auth/token")
+                        .header(header::COOKIE, format!("RefreshToken={}", refresh_token))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let jwt = get_cookie(&response, "Bearer").unwrap();
+            let rotated_refresh_token = get_cookie(&response, "RefreshToken").unwrap();
+            assert_ne!(rotated_refresh_token, refresh_token);
+
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::post("/api/auth/logout")
+                        .header(header::COOKIE, format!("Bearer={}", jwt))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            // Logging out ended the session, so neither the JWT nor the refresh token works anymore
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::get("/api/user")
+                        .header(header::COOKIE, format!("Bearer={}", jwt))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let response = app
+                .oneshot(
+                    Request::post("/api/auth/token")
+                        .header(
+                            header::COOKIE,
+                            format!("RefreshToken={}", rotated_refresh_token),
+                        )
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        #[tokio::test]
+        async fn test_get_user_with_in_memory_stores() {
+            init_fixture_jwt_keys();
+
+            let state = create_in_memory_state();
+            let id = create_user(&state, "Test", "test@example.com", "correct horse battery")
+                .await
+                .unwrap();
+            let jwt = encode_jwt(state.config, &id, "Test", "test@example.com", None).unwrap();
+
+            let response = app_routes(state)
+                .oneshot(
+                    Request::get("/api/user")
+                        .header(header::COOKIE, format!("Bearer={}", jwt))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert!(String::from_utf8_lossy(&body).contains("test@example.com"));
+        }
+
+        #[tokio::test]
+        async fn test_reset_link_only_works_once() {
+            let state = create_in_memory_state();
+            let id = create_user(&state, "Test", "test@example.com", "correct horse battery")
+                .await
+                .unwrap();
+
+            let reset_token = generate_reset_token();
+            let reset_token_key = format_reset_token_key(state.config, &reset_token).unwrap();
+            set_token(&state, &reset_token_key, &id.to_string(), 60)
+                .await
+                .unwrap();
+
+            let app = app_routes(state);
+            let password = "another horse battery staple";
+            let reset = json!({ "password": password, "passwordConfirm": password });
+
+            let response = send_json(
+                &app,
+                Request::patch(format!("/api/user/reset-password?token={}", reset_token)),
+                reset.clone(),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let response = send_json(
+                &app,
+                Request::patch(format!("/api/user/reset-password?token={}", reset_token)),
+                reset,
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        #[tokio::test]
+        async fn test_get_user_without_jwt_is_unauthorized() {
+            let response = app_routes(create_in_memory_state())
+                .oneshot(Request::get("/api/user").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        #[tokio::test]
+        async fn test_health_with_in_memory_stores() {
+            let response = app_routes(create_in_memory_state())
+                .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+            assert_eq!(health["status"], "ok");
+            assert!(health["redis"].get("lastError").is_none());
+        }
+    }
+}
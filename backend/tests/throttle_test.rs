@@ -0,0 +1,224 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    mod fixtures {
+        use backend::models::session::models::SessionClient;
+
+        pub fn client() -> SessionClient {
+            SessionClient {
+                ip: Some("203.0.113.7".to_string()),
+                ..Default::default()
+            }
+        }
+    }
+
+    mod login_attempt_tests {
+        use backend::utils::{
+            redis::{get_time_to_live, get_token, set_token},
+            throttle::{
+                clear_login_failures, format_login_failures_key, format_login_throttle_key,
+                start_login_attempt, EMAIL_LOGIN_THROTTLE_POLICY,
+            },
+        };
+
+        use tokio::task::JoinSet;
+
+        use super::fixtures::client;
+        use crate::common::create_in_memory_state;
+
+        #[tokio::test]
+        async fn test_attempts_are_counted_before_they_are_allowed() {
+            let state = create_in_memory_state();
+            let free_attempts = EMAIL_LOGIN_THROTTLE_POLICY.free_attempts;
+
+            // The attempt after the free ones is still allowed, but holds off the next one
+            for _ in 0..=free_attempts {
+                assert_eq!(
+                    start_login_attempt(&state, "Test@example.com", &client())
+                        .await
+                        .unwrap(),
+                    None
+                );
+            }
+
+            assert!(start_login_attempt(&state, "test@example.com", &client())
+                .await
+                .unwrap()
+                .is_some());
+
+            // The rejected attempt is given back
+            assert_eq!(
+                get_token(
+                    &state,
+                    &format_login_failures_key("email", "test@example.com")
+                )
+                .await
+                .unwrap(),
+                Some((free_attempts + 1).to_string())
+            );
+        }
+
+        #[tokio::test]
+        async fn test_parallel_attempts_are_throttled() {
+            let state = create_in_memory_state();
+            let client = client();
+            let free_attempts = EMAIL_LOGIN_THROTTLE_POLICY.free_attempts as usize;
+
+            let mut attempts = JoinSet::new();
+
+            for _ in 0..free_attempts + 5 {
+                let state = state.clone();
+                let client = client.clone();
+
+                attempts.spawn(async move {
+                    start_login_attempt(&state, "test@example.com", &client).await
+                });
+            }
+
+            let allowed = attempts
+                .join_all()
+                .await
+                .into_iter()
+                .filter(|result| *result == Ok(None))
+                .count();
+
+            assert_eq!(allowed, free_attempts + 1);
+        }
+
+        #[tokio::test]
+        async fn test_success_clears_only_the_email() {
+            let state = create_in_memory_state();
+            let client = client();
+            let ip = client.ip.clone().unwrap();
+
+            start_login_attempt(&state, "test@example.com", &client)
+                .await
+                .unwrap();
+            set_token(
+                &state,
+                &format_login_throttle_key("email", "test@example.com"),
+                "4",
+                60,
+            )
+            .await
+            .unwrap();
+            set_token(&state, &format_login_throttle_key("ip", &ip), "21", 60)
+                .await
+                .unwrap();
+
+            clear_login_failures(&state, "test@example.com", &client)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                get_token(
+                    &state,
+                    &format_login_failures_key("email", "test@example.com")
+                )
+                .await
+                .unwrap(),
+                None
+            );
+            assert_eq!(
+                get_time_to_live(
+                    &state,
+                    &format_login_throttle_key("email", "test@example.com")
+                )
+                .await
+                .unwrap(),
+                None
+            );
+
+            // Only the successful attempt is given back to the IP, its throttle stays
+            assert_eq!(
+                get_token(&state, &format_login_failures_key("ip", &ip))
+                    .await
+                    .unwrap(),
+                Some("0".to_string())
+            );
+            assert!(
+                get_time_to_live(&state, &format_login_throttle_key("ip", &ip))
+                    .await
+                    .unwrap()
+                    .is_some()
+            );
+        }
+    }
+
+    mod backoff_tests {
+        use backend::utils::throttle::{
+            get_login_backoff_seconds, EMAIL_LOGIN_THROTTLE_POLICY, IP_LOGIN_THROTTLE_POLICY,
+        };
+
+        #[test]
+        fn test_first_failures_are_free() {
+            for failures in 0..=EMAIL_LOGIN_THROTTLE_POLICY.free_attempts {
+                assert_eq!(
+                    get_login_backoff_seconds(failures, &EMAIL_LOGIN_THROTTLE_POLICY),
+                    0
+                );
+            }
+        }
+
+        #[test]
+        fn test_backoff_doubles_after_free_attempts() {
+            let free_attempts = EMAIL_LOGIN_THROTTLE_POLICY.free_attempts;
+
+            let backoffs: Vec<u64> = (1..=4)
+                .map(|extra| {
+                    get_login_backoff_seconds(free_attempts + extra, &EMAIL_LOGIN_THROTTLE_POLICY)
+                })
+                .collect();
+
+            assert_eq!(backoffs, vec![1, 2, 4, 8]);
+        }
+
+        #[test]
+        fn test_lockout_after_too_many_failures() {
+            let policy = &EMAIL_LOGIN_THROTTLE_POLICY;
+
+            assert_eq!(
+                get_login_backoff_seconds(policy.lockout_failures, policy),
+                policy.lockout_seconds
+            );
+            assert_eq!(
+                get_login_backoff_seconds(i64::MAX, policy),
+                policy.lockout_seconds
+            );
+        }
+
+        #[test]
+        fn test_backoff_never_exceeds_lockout() {
+            let policy = &IP_LOGIN_THROTTLE_POLICY;
+
+            for failures in 0..policy.lockout_failures {
+                assert!(get_login_backoff_seconds(failures, policy) <= policy.lockout_seconds);
+            }
+        }
+    }
+
+    mod response_tests {
+        use axum::{http::StatusCode, response::IntoResponse};
+        use backend::utils::responses::AppError;
+        use http::header;
+
+        #[test]
+        fn test_app_error_sets_retry_after_header() {
+            let response = AppError::format_raw_error(StatusCode::TOO_MANY_REQUESTS, "Slow down")
+                .with_retry_after(30)
+                .into_response();
+
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+        }
+
+        #[test]
+        fn test_app_error_omits_retry_after_by_default() {
+            let response =
+                AppError::format_raw_error(StatusCode::UNAUTHORIZED, "Nope").into_response();
+
+            assert!(response.headers().get(header::RETRY_AFTER).is_none());
+        }
+    }
+}
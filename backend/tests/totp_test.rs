@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    mod totp_code_tests {
+        use backend::utils::totp::generate_totp_code;
+
+        // Test vectors from RFC 6238 Appendix B (SHA-1)
+        const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+        #[test]
+        fn test_generate_totp_code_rfc_vectors() {
+            let vectors = [
+                (59, "94287082"),
+                (1111111109, "07081804"),
+                (1111111111, "14050471"),
+                (1234567890, "89005924"),
+                (2000000000, "69279037"),
+                (20000000000, "65353130"),
+            ];
+
+            for (unix_time, expected) in vectors {
+                assert_eq!(
+                    generate_totp_code(RFC_SECRET, unix_time / 30, 8).as_deref(),
+                    Some(expected)
+                );
+            }
+        }
+
+        #[test]
+        fn test_generate_totp_code_pads_to_digits() {
+            let code = generate_totp_code(RFC_SECRET, 1111111109 / 30, 6);
+            assert_eq!(code.as_deref(), Some("081804"));
+        }
+    }
+
+    mod totp_verification_tests {
+        use backend::{
+            models::config::models::Config,
+            utils::totp::{
+                format_otpauth_uri, generate_totp_code, generate_totp_secret, get_totp_time_step,
+                verify_totp_code,
+            },
+        };
+
+        fn code_for(secret: &str, unix_time: u64) -> String {
+            let secret = base32::decode(base32::Alphabet::Rfc4648 { padding: false }, secret)
+                .expect("Generated secret should be valid base32");
+
+            generate_totp_code(&secret, get_totp_time_step(unix_time), 6)
+                .expect("HMAC accepts keys of any length")
+        }
+
+        #[test]
+        fn test_verify_totp_code_current_step() {
+            let secret = generate_totp_secret();
+            let now = 1_700_000_000;
+
+            assert_eq!(
+                verify_totp_code(&secret, &code_for(&secret, now), now),
+                Some(get_totp_time_step(now))
+            );
+        }
+
+        #[test]
+        fn test_verify_totp_code_allows_one_step_drift() {
+            let secret = generate_totp_secret();
+            let now = 1_700_000_000;
+
+            assert!(verify_totp_code(&secret, &code_for(&secret, now - 30), now).is_some());
+            assert!(verify_totp_code(&secret, &code_for(&secret, now + 30), now).is_some());
+        }
+
+        #[test]
+        fn test_verify_totp_code_rejects_old_code() {
+            let secret = generate_totp_secret();
+            let now = 1_700_000_000;
+
+            assert_eq!(
+                verify_totp_code(&secret, &code_for(&secret, now - 90), now),
+                None
+            );
+        }
+
+        #[test]
+        fn test_verify_totp_code_ignores_whitespace() {
+            let secret = generate_totp_secret();
+            let now = 1_700_000_000;
+            let code = code_for(&secret, now);
+            let spaced_code = format!("{} {}", &code[..3], &code[3..]);
+
+            assert!(verify_totp_code(&secret, &spaced_code, now).is_some());
+        }
+
+        #[test]
+        fn test_verify_totp_code_rejects_wrong_length() {
+            let secret = generate_totp_secret();
+
+            assert_eq!(verify_totp_code(&secret, "12345", 1_700_000_000), None);
+        }
+
+        #[test]
+        fn test_format_otpauth_uri() {
+            let config = Config {
+                company_name: "Example & Co".to_string(),
+                ..Default::default()
+            };
+
+            let uri = format_otpauth_uri(&config, "JBSWY3DPEHPK3PXP", "user@domain.com");
+
+            assert_eq!(
+                uri,
+                "otpauth://totp/Example%20%26%20Co:user%40domain.com?secret=JBSWY3DPEHPK3PXP&issuer=Example%20%26%20Co&algorithm=SHA1&digits=6&period=30"
+            );
+        }
+    }
+}
@@ -65,60 +65,232 @@ mod tests {
     }
 
     mod password_validation_tests {
-        use backend::utils::validation::get_password_feedback_message;
+        use backend::{
+            models::password_policy::models::{CharacterSet, PasswordPolicy},
+            utils::{
+                password_policy::{estimate_password_score, get_password_policy_feedback},
+                validation::get_password_feedback_message,
+            },
+        };
+
+        fn strict_policy() -> PasswordPolicy {
+            PasswordPolicy {
+                required_character_sets: vec![
+                    CharacterSet::Uppercase,
+                    CharacterSet::Number,
+                    CharacterSet::Symbol,
+                ],
+                allowed_character_sets: vec![
+                    CharacterSet::Lowercase,
+                    CharacterSet::Uppercase,
+                    CharacterSet::Number,
+                    CharacterSet::Symbol,
+                ],
+                min_score: 0,
+                ..PasswordPolicy::default()
+            }
+        }
 
         #[test]
         fn test_get_password_feedback_message_valid_password() {
-            let password = "Password1!";
-            assert_eq!(get_password_feedback_message(password), None);
+            let password = "Correct-Horse-42!";
+            assert_eq!(get_password_feedback_message(password, &[]), None);
+        }
+
+        #[test]
+        fn test_get_password_feedback_message_allows_passphrases() {
+            assert_eq!(
+                get_password_feedback_message("correct horse battery staple", &[]),
+                None
+            );
+            assert_eq!(
+                get_password_feedback_message("één twee drie vier vijf", &[]),
+                None
+            );
         }
 
         #[test]
         fn test_get_password_feedback_message_too_short() {
             let password = "Pass1!";
             assert_eq!(
-                get_password_feedback_message(password),
-                Some("authentication.errors.password_too_short")
+                get_password_feedback_message(password, &[]),
+                Some("auth.errors.password_too_short")
             );
         }
 
         #[test]
-        fn test_get_password_feedback_message_missing_uppercase() {
-            let password = "password1!";
+        fn test_get_password_feedback_message_too_long() {
+            let password = "a".repeat(129);
             assert_eq!(
-                get_password_feedback_message(password),
-                Some("authentication.errors.missing_uppercase")
+                get_password_feedback_message(&password, &[]),
+                Some("auth.errors.password_too_long")
             );
         }
 
         #[test]
-        fn test_get_password_feedback_message_missing_number() {
-            let password = "Password!";
+        fn test_get_password_feedback_message_too_weak() {
+            for password in ["Password1!", "12345678", "aaaaaaaaaaaa", "qwerty123"] {
+                assert_eq!(
+                    get_password_feedback_message(password, &[]),
+                    Some("auth.errors.password_too_weak"),
+                    "{}",
+                    password
+                );
+            }
+        }
+
+        #[test]
+        fn test_get_password_feedback_message_personal_info() {
             assert_eq!(
-                get_password_feedback_message(password),
-                Some("authentication.errors.missing_number")
+                get_password_feedback_message(
+                    "Correct-Johnson-42!",
+                    &["Johnson Doe", "jdoe@example.com"]
+                ),
+                Some("auth.errors.password_contains_personal_info")
+            );
+            assert_eq!(
+                get_password_feedback_message(
+                    "Correct-jdoe-42!",
+                    &["Johnson Doe", "jdoe@example.com"]
+                ),
+                Some("auth.errors.password_contains_personal_info")
             );
         }
 
         #[test]
-        fn test_get_password_feedback_message_missing_special_character() {
-            let password = "Password1";
+        fn test_get_password_feedback_message_ignores_email_domain() {
             assert_eq!(
-                get_password_feedback_message(password),
-                Some("authentication.errors.missing_special_character")
+                get_password_feedback_message(
+                    "Correct-Example-42!",
+                    &["Johnson Doe", "jdoe@example.com"]
+                ),
+                None
             );
         }
 
         #[test]
-        fn test_get_password_feedback_message_invalid_characters() {
-            let password = "Password1!@";
+        fn test_policy_missing_uppercase() {
             assert_eq!(
-                get_password_feedback_message(password),
-                Some("authentication.errors.invalid_characters")
+                get_password_policy_feedback("password1!", &strict_policy(), &[]),
+                Some("auth.errors.password_missing_uppercase")
+            );
+        }
+
+        #[test]
+        fn test_policy_missing_number() {
+            assert_eq!(
+                get_password_policy_feedback("Password!", &strict_policy(), &[]),
+                Some("auth.errors.password_missing_number")
+            );
+        }
+
+        #[test]
+        fn test_policy_missing_symbol() {
+            assert_eq!(
+                get_password_policy_feedback("Password1", &strict_policy(), &[]),
+                Some("auth.errors.password_missing_symbol")
+            );
+        }
+
+        #[test]
+        fn test_policy_invalid_characters() {
+            assert_eq!(
+                get_password_policy_feedback("Password 1!", &strict_policy(), &[]),
+                Some("auth.errors.password_invalid_characters")
+            );
+            assert_eq!(
+                get_password_policy_feedback("Password1!\n", &PasswordPolicy::default(), &[]),
+                Some("auth.errors.password_invalid_characters")
+            );
+        }
+
+        #[test]
+        fn test_estimate_password_score() {
+            assert_eq!(estimate_password_score("password", &[]), 0);
+            assert!(estimate_password_score("abcdefgh", &[]) <= 1);
+            assert!(estimate_password_score("Password1!", &[]) < 3);
+            assert_eq!(estimate_password_score("Correct-Horse-42!", &[]), 4);
+            assert!(
+                estimate_password_score("johndoe2024", &["John Doe"])
+                    < estimate_password_score("johndoe2024", &[])
             );
         }
     }
 
+    mod password_policy_tests {
+        use std::path::PathBuf;
+
+        use backend::{
+            models::{
+                config::models::PasswordPolicyConfig,
+                password_policy::models::{CharacterSet, PasswordPolicy},
+            },
+            utils::password_policy::load_password_policy,
+        };
+
+        #[test]
+        fn test_load_password_policy_from_file() {
+            let policy_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/password_policy/policy.json")
+                .to_string_lossy()
+                .to_string();
+
+            let policy = load_password_policy(&PasswordPolicyConfig {
+                path: Some(policy_path),
+                ..Default::default()
+            })
+            .unwrap();
+
+            assert_eq!(policy.min_length, 12);
+            assert_eq!(policy.max_length, 64);
+            assert_eq!(policy.required_character_sets, vec![CharacterSet::Number]);
+            assert!(!policy
+                .allowed_character_sets
+                .contains(&CharacterSet::Letter));
+            assert_eq!(policy.min_score, 4);
+            assert!(!policy.reject_personal_info);
+        }
+
+        #[test]
+        fn test_default_config_is_the_default_policy() {
+            let policy = load_password_policy(&PasswordPolicyConfig::default()).unwrap();
+
+            assert_eq!(policy, PasswordPolicy::default());
+        }
+
+        #[test]
+        fn test_rejects_impossible_policies() {
+            for config in [
+                PasswordPolicyConfig {
+                    min_length: 20,
+                    max_length: 10,
+                    ..Default::default()
+                },
+                PasswordPolicyConfig {
+                    min_score: 5,
+                    ..Default::default()
+                },
+                PasswordPolicyConfig {
+                    required_character_sets: vec![CharacterSet::Symbol],
+                    allowed_character_sets: vec![CharacterSet::Lowercase, CharacterSet::Number],
+                    ..Default::default()
+                },
+            ] {
+                assert!(load_password_policy(&config).is_err(), "{:?}", config);
+            }
+        }
+
+        #[test]
+        fn test_password_policy_serialization() {
+            let policy_json = serde_json::to_value(PasswordPolicy::default()).unwrap();
+
+            assert_eq!(policy_json["minLength"], 8);
+            assert_eq!(policy_json["requiredCharacterSets"], serde_json::json!([]));
+            assert_eq!(policy_json["allowedCharacterSets"][0], "lowercase");
+            assert_eq!(policy_json["minScore"], 3);
+        }
+    }
+
     mod phone_number_validation_tests {
         use backend::utils::validation::get_phone_number_feedback_message;
 
@@ -185,8 +357,8 @@ mod tests {
             let user = RegisterUser {
                 name: "John Doe".to_string(),
                 email: "john@example.com".to_string(),
-                password: "Password1!".to_string(),
-                password_confirm: "Password1!".to_string(),
+                password: "Correct-Horse-42!".to_string(),
+                password_confirm: "Correct-Horse-42!".to_string(),
             };
             assert_eq!(validate_register_user_data(&user), None);
         }
@@ -196,8 +368,8 @@ mod tests {
             let user = RegisterUser {
                 name: "".to_string(),
                 email: "john@example.com".to_string(),
-                password: "Password1!".to_string(),
-                password_confirm: "Password1!".to_string(),
+                password: "Correct-Horse-42!".to_string(),
+                password_confirm: "Correct-Horse-42!".to_string(),
             };
             assert_eq!(
                 validate_register_user_data(&user),
@@ -210,8 +382,8 @@ mod tests {
             let user = RegisterUser {
                 name: "John Doe".to_string(),
                 email: "john@example".to_string(),
-                password: "Password1!".to_string(),
-                password_confirm: "Password1!".to_string(),
+                password: "Correct-Horse-42!".to_string(),
+                password_confirm: "Correct-Horse-42!".to_string(),
             };
             assert_eq!(
                 validate_register_user_data(&user),
@@ -229,7 +401,7 @@ mod tests {
             };
             assert_eq!(
                 validate_register_user_data(&user),
-                Some("authentication.errors.missing_uppercase")
+                Some("auth.errors.password_too_weak")
             );
         }
 
@@ -238,8 +410,8 @@ mod tests {
             let user = RegisterUser {
                 name: "John Doe".to_string(),
                 email: "john@example.com".to_string(),
-                password: "Password1!".to_string(),
-                password_confirm: "Password2!".to_string(),
+                password: "Correct-Horse-42!".to_string(),
+                password_confirm: "Correct-Horse-43!".to_string(),
             };
             assert_eq!(
                 validate_register_user_data(&user),
@@ -260,8 +432,8 @@ mod tests {
                 name: "John Doe".to_string(),
                 email: "john@example.com".to_string(),
                 email_confirm: Some("john@example.com".to_string()),
-                password: Some("Password1!".to_string()),
-                password_confirm: Some("Password1!".to_string()),
+                password: Some("Correct-Horse-42!".to_string()),
+                password_confirm: Some("Correct-Horse-42!".to_string()),
                 phone: Some("+1 (123) 456-7890".to_string()),
             };
             assert_eq!(validate_update_user_data(&user), None);
@@ -317,6 +489,23 @@ mod tests {
                 Some("authentication.errors.invalid_phone_length")
             );
         }
+
+        #[test]
+        fn test_validate_update_user_data_password_without_confirm() {
+            let user = UpdateUser {
+                id: 123,
+                name: "John Doe".to_string(),
+                email: "john@example.com".to_string(),
+                email_confirm: None,
+                password: Some("Correct-Horse-42!".to_string()),
+                password_confirm: None,
+                phone: None,
+            };
+            assert_eq!(
+                validate_update_user_data(&user),
+                Some("auth.errors.password_mismatch")
+            );
+        }
     }
 
     mod password_reset_user_validation_tests {
@@ -328,10 +517,10 @@ mod tests {
         #[test]
         fn test_validate_password_reset_user_data_valid_user() {
             let user = PasswordResetUser {
-                password: "Password1!".to_string(),
-                password_confirm: "Password1!".to_string(),
+                password: "Correct-Horse-42!".to_string(),
+                password_confirm: "Correct-Horse-42!".to_string(),
             };
-            assert_eq!(validate_password_reset_user_data(&user), None);
+            assert_eq!(validate_password_reset_user_data(&user, &[]), None);
         }
 
         #[test]
@@ -341,19 +530,19 @@ mod tests {
                 password_confirm: "password".to_string(),
             };
             assert_eq!(
-                validate_password_reset_user_data(&user),
-                Some("authentication.errors.missing_uppercase")
+                validate_password_reset_user_data(&user, &[]),
+                Some("auth.errors.password_too_weak")
             );
         }
 
         #[test]
         fn test_validate_password_reset_user_data_password_mismatch() {
             let user = PasswordResetUser {
-                password: "Password1!".to_string(),
-                password_confirm: "Password2!".to_string(),
+                password: "Correct-Horse-42!".to_string(),
+                password_confirm: "Correct-Horse-43!".to_string(),
             };
             assert_eq!(
-                validate_password_reset_user_data(&user),
+                validate_password_reset_user_data(&user, &[]),
                 Some("auth.errors.password_mismatch")
             );
         }
@@ -0,0 +1,341 @@
+#[cfg(test)]
+mod tests {
+    mod software_authenticator {
+        use backend::utils::encoding::encode_base64url;
+        use ciborium::value::Value;
+        use p256::ecdsa::{signature::Signer, DerSignature, SigningKey};
+        use sha2::{Digest, Sha256};
+
+        pub const RP_ID: &str = "localhost";
+        pub const ORIGIN: &str = "http://localhost:3000";
+
+        // Minimal authenticator that creates ES256 passkeys the way a browser would hand them to us
+        pub struct SoftwareAuthenticator {
+            pub credential_id: Vec<u8>,
+            signing_key: SigningKey,
+            sign_count: u32,
+        }
+
+        impl SoftwareAuthenticator {
+            pub fn new() -> Self {
+                let signing_key =
+                    SigningKey::from_slice(&[7u8; 32]).expect("Fixed test key should be valid");
+
+                Self {
+                    credential_id: vec![1, 2, 3, 4, 5, 6, 7, 8],
+                    signing_key,
+                    sign_count: 0,
+                }
+            }
+
+            pub fn client_data(ceremony_type: &str, challenge: &str, origin: &str) -> Vec<u8> {
+                serde_json::json!({
+                    "type": ceremony_type,
+                    "challenge": challenge,
+                    "origin": origin,
+                    "crossOrigin": false,
+                })
+                .to_string()
+                .into_bytes()
+            }
+
+            fn authenticator_data(&self, rp_id: &str, flags: u8) -> Vec<u8> {
+                let mut auth_data = Sha256::digest(rp_id.as_bytes()).to_vec();
+                auth_data.push(flags);
+                auth_data.extend_from_slice(&self.sign_count.to_be_bytes());
+                auth_data
+            }
+
+            fn cose_public_key(&self) -> Vec<u8> {
+                let point = self.signing_key.verifying_key().to_encoded_point(false);
+
+                let cose_key = Value::Map(vec![
+                    (Value::Integer(1.into()), Value::Integer(2.into())),
+                    (Value::Integer(3.into()), Value::Integer((-7).into())),
+                    (Value::Integer((-1).into()), Value::Integer(1.into())),
+                    (
+                        Value::Integer((-2).into()),
+                        Value::Bytes(point.x().expect("Uncompressed point").to_vec()),
+                    ),
+                    (
+                        Value::Integer((-3).into()),
+                        Value::Bytes(point.y().expect("Uncompressed point").to_vec()),
+                    ),
+                ]);
+
+                let mut encoded = Vec::new();
+                ciborium::ser::into_writer(&cose_key, &mut encoded).expect("CBOR encoding");
+                encoded
+            }
+
+            pub fn make_credential(&self, rp_id: &str) -> Vec<u8> {
+                // User present and attested credential data included
+                let mut auth_data = self.authenticator_data(rp_id, 0x41);
+                auth_data.extend_from_slice(&[0u8; 16]);
+                auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+                auth_data.extend_from_slice(&self.credential_id);
+                auth_data.extend_from_slice(&self.cose_public_key());
+
+                let attestation_object = Value::Map(vec![
+                    (Value::Text("fmt".into()), Value::Text("none".into())),
+                    (Value::Text("attStmt".into()), Value::Map(vec![])),
+                    (Value::Text("authData".into()), Value::Bytes(auth_data)),
+                ]);
+
+                let mut encoded = Vec::new();
+                ciborium::ser::into_writer(&attestation_object, &mut encoded)
+                    .expect("CBOR encoding");
+                encoded
+            }
+
+            // Returns the authenticator data and DER signature for the given client data
+            pub fn get_assertion(&mut self, rp_id: &str, client_data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+                self.sign_count += 1;
+
+                // User present and user verified
+                let auth_data = self.authenticator_data(rp_id, 0x05);
+
+                let mut signed_data = auth_data.clone();
+                signed_data.extend_from_slice(&Sha256::digest(client_data));
+
+                let signature: DerSignature = self.signing_key.sign(&signed_data);
+
+                (auth_data, signature.as_bytes().to_vec())
+            }
+
+            pub fn credential_id_base64url(&self) -> String {
+                encode_base64url(&self.credential_id)
+            }
+        }
+    }
+
+    mod registration_tests {
+        use super::software_authenticator::{SoftwareAuthenticator, ORIGIN, RP_ID};
+        use backend::utils::{
+            encoding::encode_base64url,
+            webauthn::{
+                generate_webauthn_challenge, parse_client_data, verify_client_data,
+                verify_registration,
+            },
+        };
+        use http::StatusCode;
+
+        #[test]
+        fn test_verify_registration_valid_credential() {
+            let authenticator = SoftwareAuthenticator::new();
+            let challenge = generate_webauthn_challenge();
+
+            let client_data =
+                SoftwareAuthenticator::client_data("webauthn.create", &challenge, ORIGIN);
+            let client_data = parse_client_data(&client_data).expect("Valid client data");
+
+            assert_eq!(
+                verify_client_data(&client_data, "webauthn.create", &challenge, ORIGIN),
+                Ok(())
+            );
+
+            let passkey = verify_registration(&authenticator.make_credential(RP_ID), RP_ID)
+                .expect("Registration should verify");
+
+            assert_eq!(
+                encode_base64url(&passkey.credential_id),
+                authenticator.credential_id_base64url()
+            );
+            assert_eq!(passkey.public_key.len(), 65);
+            assert_eq!(passkey.sign_count, 0);
+        }
+
+        #[test]
+        fn test_verify_registration_wrong_rp_id() {
+            let authenticator = SoftwareAuthenticator::new();
+
+            assert_eq!(
+                verify_registration(&authenticator.make_credential("evil.example"), RP_ID).err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_verify_registration_malformed_attestation() {
+            assert_eq!(
+                verify_registration(b"not cbor", RP_ID).err(),
+                Some(StatusCode::BAD_REQUEST)
+            );
+        }
+
+        #[test]
+        fn test_verify_client_data_wrong_challenge() {
+            let client_data =
+                SoftwareAuthenticator::client_data("webauthn.create", "other-challenge", ORIGIN);
+            let client_data = parse_client_data(&client_data).expect("Valid client data");
+
+            assert_eq!(
+                verify_client_data(&client_data, "webauthn.create", "challenge", ORIGIN),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_verify_client_data_wrong_origin() {
+            let client_data = SoftwareAuthenticator::client_data(
+                "webauthn.create",
+                "challenge",
+                "https://evil.example",
+            );
+            let client_data = parse_client_data(&client_data).expect("Valid client data");
+
+            assert_eq!(
+                verify_client_data(&client_data, "webauthn.create", "challenge", ORIGIN),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_verify_client_data_wrong_ceremony() {
+            let client_data =
+                SoftwareAuthenticator::client_data("webauthn.get", "challenge", ORIGIN);
+            let client_data = parse_client_data(&client_data).expect("Valid client data");
+
+            assert_eq!(
+                verify_client_data(&client_data, "webauthn.create", "challenge", ORIGIN),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+    }
+
+    mod assertion_tests {
+        use super::software_authenticator::{SoftwareAuthenticator, ORIGIN, RP_ID};
+        use backend::utils::webauthn::{
+            generate_webauthn_challenge, verify_assertion, verify_registration,
+        };
+        use http::StatusCode;
+
+        fn register(authenticator: &SoftwareAuthenticator) -> Vec<u8> {
+            verify_registration(&authenticator.make_credential(RP_ID), RP_ID)
+                .expect("Registration should verify")
+                .public_key
+        }
+
+        #[test]
+        fn test_verify_assertion_valid_signature() {
+            let mut authenticator = SoftwareAuthenticator::new();
+            let public_key = register(&authenticator);
+
+            let client_data = SoftwareAuthenticator::client_data(
+                "webauthn.get",
+                &generate_webauthn_challenge(),
+                ORIGIN,
+            );
+            let (auth_data, signature) = authenticator.get_assertion(RP_ID, &client_data);
+
+            assert_eq!(
+                verify_assertion(&auth_data, &client_data, &signature, &public_key, RP_ID, 0),
+                Ok(1)
+            );
+        }
+
+        #[test]
+        fn test_verify_assertion_tampered_client_data() {
+            let mut authenticator = SoftwareAuthenticator::new();
+            let public_key = register(&authenticator);
+
+            let client_data =
+                SoftwareAuthenticator::client_data("webauthn.get", "challenge", ORIGIN);
+            let (auth_data, signature) = authenticator.get_assertion(RP_ID, &client_data);
+
+            let tampered_client_data =
+                SoftwareAuthenticator::client_data("webauthn.get", "other-challenge", ORIGIN);
+
+            assert_eq!(
+                verify_assertion(
+                    &auth_data,
+                    &tampered_client_data,
+                    &signature,
+                    &public_key,
+                    RP_ID,
+                    0
+                ),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_verify_assertion_signature_from_other_key() {
+            let mut authenticator = SoftwareAuthenticator::new();
+            let other_public_key = p256::ecdsa::SigningKey::from_slice(&[9u8; 32])
+                .expect("Fixed test key should be valid")
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec();
+
+            let client_data =
+                SoftwareAuthenticator::client_data("webauthn.get", "challenge", ORIGIN);
+            let (auth_data, signature) = authenticator.get_assertion(RP_ID, &client_data);
+
+            assert_eq!(
+                verify_assertion(
+                    &auth_data,
+                    &client_data,
+                    &signature,
+                    &other_public_key,
+                    RP_ID,
+                    0
+                ),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_verify_assertion_rejects_cloned_authenticator() {
+            let mut authenticator = SoftwareAuthenticator::new();
+            let public_key = register(&authenticator);
+
+            let client_data =
+                SoftwareAuthenticator::client_data("webauthn.get", "challenge", ORIGIN);
+            let (auth_data, signature) = authenticator.get_assertion(RP_ID, &client_data);
+
+            // The stored counter is already ahead of what the authenticator reports
+            assert_eq!(
+                verify_assertion(&auth_data, &client_data, &signature, &public_key, RP_ID, 5),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_verify_assertion_wrong_rp_id() {
+            let mut authenticator = SoftwareAuthenticator::new();
+            let public_key = register(&authenticator);
+
+            let client_data =
+                SoftwareAuthenticator::client_data("webauthn.get", "challenge", ORIGIN);
+            let (auth_data, signature) = authenticator.get_assertion("evil.example", &client_data);
+
+            assert_eq!(
+                verify_assertion(&auth_data, &client_data, &signature, &public_key, RP_ID, 0),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+    }
+
+    mod relying_party_tests {
+        use backend::utils::webauthn::get_relying_party_id;
+
+        #[test]
+        fn test_get_relying_party_id_strips_port_and_path() {
+            assert_eq!(
+                get_relying_party_id("http://localhost:3000"),
+                Some("localhost".to_string())
+            );
+            assert_eq!(
+                get_relying_party_id("https://App.Example.com/login"),
+                Some("app.example.com".to_string())
+            );
+        }
+
+        #[test]
+        fn test_get_relying_party_id_invalid_origin() {
+            assert_eq!(get_relying_party_id("localhost"), None);
+        }
+    }
+}
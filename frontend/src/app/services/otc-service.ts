@@ -5,14 +5,18 @@ import { gracefulFunction } from "@/utils/response";
 import { sanitize } from "@/utils/strings";
 
 export class OTCService {
-	otcUser(otc: string) {
+	// The signature only comes with the magic link, login codes don't work without it
+	otcUser(otc: string, email: string, signature?: string | null) {
 		return gracefulFunction(async () => {
-			if (!otc) {
+			if (!otc || !email) {
 				throw new Error('Invalid credentials');
 			}
 			const sanitizedOtc = sanitize(otc);
+			const sanitizedEmail = sanitize(email);
 
-			const response = await apiClient.patch(`${API_ROUTES.otc.verify}?otc=${encodeURIComponent(sanitizedOtc)}`)
+			const signatureParam = signature ? `&signature=${encodeURIComponent(signature)}` : '';
+
+			const response = await apiClient.patch(`${API_ROUTES.otc.verify}?otc=${encodeURIComponent(sanitizedOtc)}&email=${encodeURIComponent(sanitizedEmail)}${signatureParam}`)
 				.json<UnknownDataApiResult>();
 
 			return {
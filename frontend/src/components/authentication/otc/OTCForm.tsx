@@ -10,6 +10,7 @@ import { OTCInput } from "@/components/authentication/otc/OTCInput";
 import AuthFormWrapper from "@/components/authentication/wrappers/AuthFormWrapper";
 import Button from "@/components/common/buttons/Button";
 import { Flex } from "@/components/common/Flex";
+import TextInput from "@/components/common/input/text/TextInput";
 import { pages } from "@/constants/routes";
 import { useTranslationsContext } from "@/stores/translationsStore";
 import { useSetUser, useUser } from "@/stores/userStore";
@@ -38,13 +39,15 @@ const OTCForm = () => {
 	const [message, setMessage] = useState<string | null>(null);
 
 	const [otc, setOtc] = useState(initialOtc);
+	// Codes are scoped to the email they were sent to, the link from the email carries it along
+	const [email, setEmail] = useState(searchParams.get('email') ?? user?.email ?? '');
 
 	const handleOtcUser = async () => {
 		setIsPending(true);
 
 		const otcString = otc.join('');
 
-		const result = await otcService.otcUser(otcString);
+		const result = await otcService.otcUser(otcString, email, searchParams.get('signature'));
 
 		setIsError(!result.success);
 		setMessage(result.message);
@@ -66,7 +69,7 @@ const OTCForm = () => {
 	useEffect(() => {
 		setIsError(false);
 		setMessage(null);
-	}, [otc]);
+	}, [otc, email]);
 
 	return (
 		<Flex
@@ -90,6 +93,16 @@ const OTCForm = () => {
 					/>
 				</Flex>
 
+				{!searchParams.get('email') && !user?.email && (
+					<TextInput
+						dataTest="otc-email-input"
+						onChange={(newEmail) => setEmail(newEmail)}
+						placeholder={getTranslation('Authentication.emailPlaceholder')}
+						type="email"
+						value={email}
+					/>
+				)}
+
 				<Button
 					color="primary"
 					loading={isPending}
@@ -47,7 +47,7 @@ test.describe('register', () => {
 		const filteredEmail = getMessageByRecipient(allEmails, email);
 		const otc = extractOtcFromMessage(filteredEmail);
 
-		await page.goto(`${pages.Otc.path}?otc=${otc}`);
+		await page.goto(`${pages.Otc.path}?otc=${otc}&email=${encodeURIComponent(email)}`);
 
 		const { submitButton: otcSubmitButton } = getOTCFormLocators(page);
 
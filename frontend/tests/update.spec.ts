@@ -79,7 +79,7 @@ test.describe('update user details', () => {
 		const filteredEmail = getMessageByRecipient(allEmails, user.email);
 		const otc = extractOtcFromMessage(filteredEmail);
 
-		await page.goto(`${pages.Otc.path}?otc=${otc}`);
+		await page.goto(`${pages.Otc.path}?otc=${otc}&email=${encodeURIComponent(user.email)}`);
 
 		await otcFormLocators.submitButton.click();
 
@@ -140,7 +140,7 @@ test.describe('update user password', () => {
 		const filteredEmail = getMessageByRecipient(allEmails, user.email);
 		const otc = extractOtcFromMessage(filteredEmail);
 
-		await page.goto(`${pages.Otc.path}?otc=${otc}`);
+		await page.goto(`${pages.Otc.path}?otc=${otc}&email=${encodeURIComponent(user.email)}`);
 
 		await otcFormLocators.submitButton.click();
 
@@ -200,7 +200,7 @@ test.describe('delete user', () => {
 		const filteredEmail = getMessageByRecipient(allEmails, user.email);
 		const otc = extractOtcFromMessage(filteredEmail);
 
-		await page.goto(`${pages.Otc.path}?otc=${otc}`);
+		await page.goto(`${pages.Otc.path}?otc=${otc}&email=${encodeURIComponent(user.email)}`);
 
 		await otcFormLocators.submitButton.click();
 
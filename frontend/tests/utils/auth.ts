@@ -25,7 +25,7 @@ export async function createUser(page: Page) {
 	const filteredEmail = getMessageByRecipient(allEmails, email);
 	const otc = extractOtcFromMessage(filteredEmail);
 
-	await page.goto(`${pages.Otc.path}?otc=${otc}`);
+	await page.goto(`${pages.Otc.path}?otc=${otc}&email=${encodeURIComponent(email)}`);
 
 	const { submitButton: otcSubmitButton } = getOTCFormLocators(page);
 
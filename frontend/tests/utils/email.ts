@@ -26,7 +26,7 @@ export function extractOtcFromMessage(message: MailpitResponseMessage) {
 }
 
 export function extractPasswordResetTokenFromMessage(message: MailpitResponseMessage) {
-	const passwordResetToken = message.Snippet.match(/\b[A-Z0-9]{6}\b(?!.*password-reset-token=)/)?.[0];
+	const passwordResetToken = message.Snippet.match(/\b[A-Za-z0-9_-]{43}(?![A-Za-z0-9_-])/)?.[0];
 
 	return passwordResetToken;
 }
\ No newline at end of file